@@ -0,0 +1,19 @@
+use std::env;
+
+// Targets with a mirrored memory backend
+const MIRRORED: &[&str] = &["windows", "macos", "ios", "fuchsia", "linux", "android", "freebsd", "openbsd", "netbsd", "illumos", "solaris"];
+
+fn main() {
+	println!("cargo:rerun-if-changed=build.rs");
+
+	// The heap fallback backs WebAssembly and, with the `fallback-alloc` feature, every target without a mirrored backend
+	let family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+	let os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+	let wasm = family.split(',').any(|family| family == "wasm");
+	let unsupported = !MIRRORED.contains(&os.as_str());
+	// Miri cannot map memory twice, the fallback lets it check the unsafe code of the ring buffer itself
+	let miri = env::var_os("CARGO_CFG_MIRI").is_some();
+	if wasm || miri || (unsupported && env::var_os("CARGO_FEATURE_FALLBACK_ALLOC").is_some()) {
+		println!("cargo:rustc-cfg=heap_fallback");
+	}
+}
@@ -0,0 +1,96 @@
+/*!
+Applies arbitrary operations to a ring buffer and a `VecDeque` and compares the two.
+
+Run with `cargo fuzz run differential` from the root of the repository.
+*/
+
+#![no_main]
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use vringbuf::RingBuffer;
+
+// Element counting how many of its kind are alive
+#[derive(Debug)]
+struct Counted {
+	value: u32,
+	alive: Rc<Cell<usize>>,
+}
+impl Counted {
+	fn new(value: u32, alive: &Rc<Cell<usize>>) -> Counted {
+		alive.set(alive.get() + 1);
+		Counted { value, alive: alive.clone() }
+	}
+}
+impl PartialEq for Counted {
+	fn eq(&self, other: &Counted) -> bool {
+		self.value == other.value
+	}
+}
+impl Drop for Counted {
+	fn drop(&mut self) {
+		self.alive.set(self.alive.get() - 1);
+	}
+}
+
+// Lengths are kept small to explore wrapping around rather than growing
+#[derive(Arbitrary, Debug)]
+enum Op {
+	Push(u32),
+	Pop,
+	Extend(u32, u16),
+	RemoveTail(u16),
+	Reserve(u16),
+	Truncate(u16),
+	Clear,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+	let alive = Rc::new(Cell::new(0));
+	{
+		let mut rbuf = RingBuffer::new();
+		let mut model = VecDeque::new();
+		for op in &ops {
+			match *op {
+				Op::Push(value) => {
+					rbuf.push(Counted::new(value, &alive));
+					model.push_back(Counted::new(value, &alive));
+				},
+				Op::Pop => {
+					assert_eq!(rbuf.pop(), model.pop_front());
+				},
+				Op::Extend(start, n) => {
+					let values = (0..n as u32 % 5000).map(|i| start.wrapping_add(i));
+					rbuf.extend(values.clone().map(|value| Counted::new(value, &alive)));
+					model.extend(values.map(|value| Counted::new(value, &alive)));
+				},
+				Op::RemoveTail(n) => {
+					let n = n as usize;
+					rbuf.remove_tail(n);
+					model.drain(..usize::min(n, model.len()));
+				},
+				Op::Reserve(additional) => {
+					let additional = additional as usize;
+					rbuf.reserve(additional);
+					assert!(rbuf.capacity() >= rbuf.len() + additional);
+				},
+				Op::Truncate(len) => {
+					rbuf.truncate(len as usize);
+					model.truncate(len as usize);
+				},
+				Op::Clear => {
+					rbuf.clear();
+					model.clear();
+				},
+			}
+			assert!(rbuf.iter().eq(model.iter()));
+			assert_eq!(rbuf.validate(), Ok(()));
+			assert_eq!(alive.get(), rbuf.len() * 2);
+		}
+	}
+	// Dropping both leaks nothing
+	assert_eq!(alive.get(), 0);
+});
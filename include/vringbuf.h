@@ -0,0 +1,64 @@
+/* C API of the vringbuf crate, built with the `capi` feature. */
+
+#ifndef VRINGBUF_H
+#define VRINGBUF_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Byte ring buffer backed by mirrored virtual memory. */
+typedef struct vringbuf vringbuf;
+
+/* Creates a ring buffer with at least `capacity` bytes of capacity, returns NULL if it cannot be allocated. */
+vringbuf *vringbuf_create(size_t capacity);
+
+/* Frees a ring buffer, does nothing if it is NULL. */
+void vringbuf_destroy(vringbuf *rbuf);
+
+/* Appends `len` bytes growing the ring buffer if needed, returns `len` or 0 if it cannot grow. */
+size_t vringbuf_write(vringbuf *rbuf, const uint8_t *data, size_t len);
+
+/* Removes up to `len` bytes and copies them to `buf`, returns the number of bytes copied. */
+size_t vringbuf_read(vringbuf *rbuf, uint8_t *buf, size_t len);
+
+/* Returns a pointer to the contiguous contents, valid for `vringbuf_len` bytes until the ring buffer is modified. */
+const uint8_t *vringbuf_data(const vringbuf *rbuf);
+
+/* Removes up to `len` bytes without copying them. */
+void vringbuf_consume(vringbuf *rbuf, size_t len);
+
+/* Returns the number of bytes in the ring buffer. */
+size_t vringbuf_len(const vringbuf *rbuf);
+
+/* Returns the number of bytes the ring buffer can hold without growing. */
+size_t vringbuf_capacity(const vringbuf *rbuf);
+
+/* Header at the start of the shared memory of a `SharedRingBuffer`, readable without the `capi` feature.
+ * Positions are byte offsets modulo twice `cap`, the element at position `pos` is `pos % cap` bytes past the header,
+ * which is rounded up to the allocation granularity. Read `magic` first, the other fields are valid once it equals
+ * VRINGBUF_SHARED_MAGIC. */
+#define VRINGBUF_SHARED_MAGIC 0x68737276u
+#define VRINGBUF_SHARED_VERSION 4u
+
+typedef struct vringbuf_shared_header {
+	uint32_t magic;
+	uint32_t version;
+	size_t size_of;
+	size_t cap;
+	uint8_t _pad0[128 - 8 - 2 * sizeof(size_t)];
+	size_t head;
+	uint8_t _pad1[128 - sizeof(size_t)];
+	size_t tail;
+	uint8_t _pad2[128 - sizeof(size_t)];
+	uint8_t _private[256];
+} vringbuf_shared_header;
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
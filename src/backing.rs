@@ -0,0 +1,80 @@
+/*!
+Access to the memory object backing the mirrored mapping.
+
+The memfd (Linux), shared memory object (other Unix) or section (Windows) backing a ring buffer stays open for as long as the mapping lives.
+Mapping it again, for example in a child process, shares the same physical pages.
+*/
+
+use std::io;
+
+use super::{platform, GrowthPolicy, OsMemory, RingBuffer};
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+
+#[cfg(unix)]
+impl<T> AsRawFd for RingBuffer<T> {
+	/// Returns the memfd backing the ring buffer, or `-1` if the ring buffer has not allocated.
+	///
+	/// The memfd is grown in place or replaced when the ring buffer reallocates.
+	/// On Linux it is sealed against shrinking, sharing it with other processes cannot truncate it under the mapping.
+	/// Shared memory objects backing ring buffers where `memfd_create` is not available cannot be sealed.
+	#[inline]
+	fn as_raw_fd(&self) -> RawFd {
+		self.raw_handle()
+	}
+}
+
+#[cfg(unix)]
+impl<T> RingBuffer<T> {
+	/// Constructs an empty ring buffer mapping the pages of the memfd backing another ring buffer.
+	///
+	/// The ring buffer takes ownership of the file descriptor on success, the file is mapped from the start and its size is the capacity in bytes.
+	/// On Linux the file must be sealed against shrinking (`F_SEAL_SHRINK`) like the memfds backing ring buffers, otherwise an error of kind `InvalidInput` is returned.
+	/// This rejects the shared memory objects ring buffers fall back to where `memfd_create` is not available, such as on kernels before 3.17.
+	/// Its length and base offset start at zero, use [`set_len`](Self::set_len) to take over elements written by the other side.
+	///
+	/// # Safety
+	///
+	/// * `fd` must be an open file descriptor which is not used anywhere else.
+	/// * The ring buffer shares its memory with all other mappings of the file,
+	///   the caller must ensure that no two ring buffers access the same elements at the same time.
+	pub unsafe fn from_backing_fd(fd: RawFd) -> io::Result<RingBuffer<T>> {
+		let () = Self::ALIGNED;
+		let (ptr, cap) = platform::map_backing(fd)?;
+		Ok(RingBuffer { ptr: ptr.cast(), cap, base: 0, len: 0, handle: Some(fd), growth: GrowthPolicy::Doubling, limit: usize::MAX, memory: OsMemory::new() })
+	}
+}
+
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, RawHandle};
+
+#[cfg(windows)]
+impl<T> AsRawHandle for RingBuffer<T> {
+	/// Returns the section backing the ring buffer, or a null handle if the ring buffer has not allocated.
+	///
+	/// The section is replaced when the ring buffer reallocates.
+	#[inline]
+	fn as_raw_handle(&self) -> RawHandle {
+		self.raw_handle() as RawHandle
+	}
+}
+
+#[cfg(windows)]
+impl<T> RingBuffer<T> {
+	/// Constructs an empty ring buffer mapping the pages of the section backing another ring buffer.
+	///
+	/// The ring buffer takes ownership of the section handle on success, the section is mapped from the start and its size is the capacity in bytes.
+	/// Its length and base offset start at zero, use [`set_len`](Self::set_len) to take over elements written by the other side.
+	///
+	/// # Safety
+	///
+	/// * `handle` must be an open section handle which is not used anywhere else.
+	/// * The ring buffer shares its memory with all other mappings of the section,
+	///   the caller must ensure that no two ring buffers access the same elements at the same time.
+	pub unsafe fn from_backing_handle(handle: RawHandle) -> io::Result<RingBuffer<T>> {
+		let () = Self::ALIGNED;
+		let (ptr, cap) = platform::map_backing(handle as platform::Handle)?;
+		Ok(RingBuffer { ptr: ptr.cast(), cap, base: 0, len: 0, handle: Some(handle as platform::Handle), growth: GrowthPolicy::Doubling, limit: usize::MAX, memory: OsMemory::new() })
+	}
+}
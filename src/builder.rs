@@ -0,0 +1,212 @@
+/*!
+Constructing ring buffers with allocation options.
+*/
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::time::Duration;
+
+use super::{platform, GrowthPolicy, RingBuffer, TryReserveError};
+
+/// Builder for ring buffers with allocation options.
+///
+/// This struct is created by the [`builder`](RingBuffer::builder) method on ring buffers.
+///
+/// ```
+/// use vringbuf::{GrowthPolicy, RingBuffer};
+///
+/// let rb = RingBuffer::<u8>::builder()
+/// 	.capacity(4096)
+/// 	.growth_policy(GrowthPolicy::Exact)
+/// 	.capacity_limit(1 << 20)
+/// 	.build()
+/// 	.unwrap();
+/// assert!(rb.capacity() >= 4096);
+/// ```
+pub struct RingBufferBuilder<T> {
+	capacity: usize,
+	growth: GrowthPolicy,
+	limit: usize,
+	options: platform::Options,
+	_marker: PhantomData<fn() -> T>,
+}
+
+impl<T> RingBuffer<T> {
+	/// Returns a builder to construct a ring buffer with allocation options.
+	#[inline]
+	pub fn builder() -> RingBufferBuilder<T> {
+		RingBufferBuilder {
+			capacity: 0,
+			growth: GrowthPolicy::Doubling,
+			limit: usize::MAX,
+			options: platform::Options::new(),
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<T> RingBufferBuilder<T> {
+	/// Sets the initial capacity, rounded up like [`with_capacity`](RingBuffer::with_capacity).
+	///
+	/// The default of 0 does not allocate until elements are pushed.
+	#[inline]
+	pub fn capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity;
+		self
+	}
+
+	/// Sets the [growth policy](RingBuffer::set_growth_policy).
+	#[inline]
+	pub fn growth_policy(mut self, policy: GrowthPolicy) -> Self {
+		self.growth = policy;
+		self
+	}
+
+	/// Sets the [capacity limit](RingBuffer::set_capacity_limit).
+	#[inline]
+	pub fn capacity_limit(mut self, limit: usize) -> Self {
+		self.limit = limit;
+		self
+	}
+
+	/// Backs the ring buffer with huge pages to reduce TLB pressure for very large ring buffers.
+	///
+	/// The capacity is rounded up to the huge page size, see [`RingBuffer::page_size`].
+	/// On Linux the huge pages come from hugetlbfs, which only has pages available if the administrator reserved them.
+	/// On Windows large pages require the account to hold the *Lock pages in memory* privilege (`SeLockMemoryPrivilege`).
+	/// Falls back to regular pages if no huge pages are available.
+	#[inline]
+	pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+		self.options.huge_pages = huge_pages;
+		self
+	}
+
+	/// Hints the system to back the ring buffer with transparent huge pages.
+	///
+	/// Unlike [`huge_pages`](Self::huge_pages) this needs no configuration by the administrator, the capacity is not rounded up
+	/// and the kernel backs the ring buffer with huge pages when it can spare them.
+	/// On Linux the mapping is aligned to the huge page size and advised with `MADV_HUGEPAGE`,
+	/// shared memory only honors the hint if `/sys/kernel/mm/transparent_hugepage/shmem_enabled` is `advise` or `always`.
+	/// Has no effect on other platforms, see [`RingBuffer::transparent_huge_pages`] whether the hint was applied.
+	#[inline]
+	pub fn transparent_huge_pages(mut self, transparent_huge_pages: bool) -> Self {
+		self.options.transparent_huge_pages = transparent_huge_pages;
+		self
+	}
+
+	/// Locks the ring buffer into physical memory, see [`RingBuffer::lock_memory`].
+	#[inline]
+	pub fn lock_memory(mut self, lock_memory: bool) -> Self {
+		self.options.lock_memory = lock_memory;
+		self
+	}
+
+	/// Leaves the contents of the ring buffer out of core dumps, for ring buffers holding sensitive data such as keys.
+	///
+	/// On Linux the mapping is advised with `MADV_DONTDUMP`, on FreeBSD with `MADV_NOCORE` and OpenBSD maps it with `MAP_CONCEAL`.
+	/// Has no effect on other platforms.
+	#[inline]
+	pub fn exclude_from_dumps(mut self, exclude_from_dumps: bool) -> Self {
+		self.options.exclude_from_dumps = exclude_from_dumps;
+		self
+	}
+
+	/// Faults in all pages of the ring buffer whenever it allocates, see [`RingBuffer::prefault`].
+	///
+	/// Moves the latency of the first use of every page out of the hot path.
+	#[inline]
+	pub fn prefault(mut self, prefault: bool) -> Self {
+		self.options.prefault = prefault;
+		self
+	}
+
+	/// Names the memory object backing the ring buffer so memory usage can be attributed to it.
+	///
+	/// On Linux the memfd shows up as `/memfd:ringbuf:<name>` in `/proc/<pid>/maps` and memory profilers, names longer than the kernel allows are truncated.
+	/// Has no effect on other platforms, named sections on Windows would be shared with every other section of the same name.
+	///
+	/// # Panics
+	///
+	/// Panics if the name contains a nul byte.
+	#[inline]
+	pub fn name(mut self, name: &str) -> Self {
+		assert!(!name.contains('\0'), "ring buffer name contains a nul byte");
+		self.options.name = Some(name.into());
+		self
+	}
+
+	/// Sets how often mapping the ring buffer is attempted and how long to wait in between, where mapping races with other threads.
+	///
+	/// Windows before Windows 10 version 1803 reserves address space, frees it and maps the views at its address,
+	/// which fails if another thread allocates memory there in between.
+	/// Processes with heavy concurrent `VirtualAlloc` traffic such as JIT hosts may need more than the default of 10 attempts,
+	/// [`map_retries`](crate::map_retries) tells how often mapping was retried.
+	/// Has no effect on other platforms.
+	#[inline]
+	pub fn map_attempts(mut self, attempts: u32, backoff: Duration) -> Self {
+		self.options.map_attempts = Some(attempts);
+		self.options.map_backoff = backoff;
+		self
+	}
+
+	/// Places the mirrored mapping within a range of addresses, for processes which carefully partition their address space.
+	///
+	/// The mapping takes twice the capacity in bytes, `addr..addr + 2 * capacity_in_bytes` places it at `addr` exactly.
+	/// The aligned addresses are tried in order from the start of the range, allocating and growing the ring buffer fail
+	/// with [`AddrNotAvailable`](std::io::ErrorKind::AddrNotAvailable) if the range has no room left for the mapping.
+	/// Growing maps the new memory before unmapping the old, the range needs room for both.
+	/// Not supported on Fuchsia and with the heap fallback, where allocating fails with [`Unsupported`](std::io::ErrorKind::Unsupported).
+	#[inline]
+	pub fn address_range(mut self, range: Range<usize>) -> Self {
+		self.options.address_range = Some(range);
+		self
+	}
+
+	/// Surrounds the mirrored mapping with inaccessible guard pages, a debugging aid for unsafe code writing through [`reserved_ptr`](RingBuffer::reserved_ptr).
+	///
+	/// Off-by-one pointer arithmetic just before or after the mapping faults right away instead of corrupting adjacent allocations.
+	/// The guard pages only take address space, not memory.
+	/// Supported on Linux, Android, the BSDs, illumos and Solaris, has no effect on other platforms.
+	#[inline]
+	pub fn guard_pages(mut self, guard_pages: bool) -> Self {
+		self.options.guard_pages = guard_pages;
+		self
+	}
+
+	/// Revokes access to the unused capacity so writes past the room reserved with [`reserve`](RingBuffer::reserve) fault right away, a debugging aid like [`guard_pages`](Self::guard_pages).
+	///
+	/// Whenever the ring buffer reserves room the pages outside of its elements and the reserved room are made inaccessible in both views of the mapping.
+	/// Unsafe code writing through [`reserved_ptr`](RingBuffer::reserved_ptr) past what it reserved crashes deterministically,
+	/// as does reading elements claimed with [`add_len`](RingBuffer::add_len) beyond it. Overruns are detected to the page.
+	/// Every reservation changes the protection of the mapping with system calls, which is slow.
+	/// Supported on Linux, Android, the BSDs, illumos and Solaris, has no effect on other platforms.
+	#[inline]
+	pub fn overrun_detection(mut self, overrun_detection: bool) -> Self {
+		self.options.overrun_detection = overrun_detection;
+		self
+	}
+
+	/// Allocates the ring buffer.
+	///
+	/// Returns an error if the allocation fails, the memory cannot be locked or the initial capacity exceeds the capacity limit.
+	pub fn build(self) -> Result<RingBuffer<T>, TryReserveError> {
+		let mut rb = RingBuffer::new();
+		rb.set_growth_policy(self.growth);
+		rb.set_capacity_limit(self.limit);
+		rb.memory.options = self.options;
+		rb.try_reserve_exact(self.capacity)?;
+		Ok(rb)
+	}
+}
+
+impl<T> fmt::Debug for RingBufferBuilder<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("RingBufferBuilder")
+			.field("capacity", &self.capacity)
+			.field("growth", &self.growth)
+			.field("limit", &self.limit)
+			.field("options", &self.options)
+			.finish()
+	}
+}
@@ -0,0 +1,320 @@
+/*!
+Methods specific to byte ring buffers.
+*/
+
+use core::slice;
+use alloc::vec::Vec;
+
+use super::{FrameError, RingBuffer};
+
+impl RingBuffer<u8> {
+	/// Returns the index of the first occurrence of `byte` in the ring buffer.
+	///
+	/// The contents of the ring buffer are always contiguous so this is a single memchr call.
+	#[inline]
+	pub fn find_byte(&self, byte: u8) -> Option<usize> {
+		memchr::memchr(byte, self.as_slice())
+	}
+
+	/// Returns the index of the last occurrence of `byte` in the ring buffer.
+	#[inline]
+	pub fn rfind_byte(&self, byte: u8) -> Option<usize> {
+		memchr::memrchr(byte, self.as_slice())
+	}
+
+	/// Returns the index of the first occurrence of `needle` in the ring buffer.
+	///
+	/// An empty needle is found at index 0.
+	#[inline]
+	pub fn position_of(&self, needle: &[u8]) -> Option<usize> {
+		memchr::memmem::find(self.as_slice(), needle)
+	}
+
+	/// Splits off everything up to and including the first `delim` byte.
+	///
+	/// Returns `None` and leaves the ring buffer unchanged if it does not contain `delim`.
+	#[inline]
+	pub fn take_until(&mut self, delim: u8) -> Option<RingBuffer<u8>> {
+		let end = self.find_byte(delim)? + 1;
+		let mut rbuf = RingBuffer::with_capacity(end);
+		rbuf.extend_from_slice(&self.as_slice()[..end]);
+		self.remove_tail(end);
+		Some(rbuf)
+	}
+
+	/// Splits off everything up to and including the first `delim` byte into a `Vec`.
+	///
+	/// Returns `None` and leaves the ring buffer unchanged if it does not contain `delim`.
+	#[inline]
+	pub fn take_until_vec(&mut self, delim: u8) -> Option<Vec<u8>> {
+		let end = self.find_byte(delim)? + 1;
+		let vec = self.as_slice()[..end].to_vec();
+		self.remove_tail(end);
+		Some(vec)
+	}
+
+	/// Constructs a ring buffer holding `len` zero bytes.
+	///
+	/// Freshly mapped memory is already zeroed, unlike extending an empty ring buffer with zeroes nothing is written
+	/// and the pages are only faulted in when they are first accessed.
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits or there is not enough contigious memory for 2x the requested capacity.
+	#[inline]
+	pub fn with_capacity_zeroed(len: usize) -> RingBuffer<u8> {
+		let mut rbuf = RingBuffer::<u8>::with_capacity(len);
+		unsafe {
+			// The heap fallback allocates uninitialized memory
+			if cfg!(heap_fallback) {
+				rbuf.as_mut_ptr().write_bytes(0, len);
+			}
+			rbuf.set_len(len);
+		}
+		rbuf
+	}
+
+	/// Appends `n` zero bytes.
+	///
+	/// Fills the spare capacity with a single memset instead of pushing the bytes one by one.
+	///
+	/// # Panics
+	///
+	/// Panics if the new capacity fails to allocate.
+	#[inline]
+	pub fn fill_zero(&mut self, n: usize) {
+		self.reserve(n);
+		unsafe {
+			self.reserved_ptr().write_bytes(0, n);
+			self.add_len(n);
+		}
+	}
+
+	/// Resizes the ring buffer in-place so that `len` is equal to `new_len`, appending zero bytes with [`fill_zero`](Self::fill_zero).
+	///
+	/// # Panics
+	///
+	/// Panics if the new capacity fails to allocate.
+	#[inline]
+	pub fn resize_zeroed(&mut self, new_len: usize) {
+		if new_len <= self.len() {
+			self.truncate(new_len);
+		}
+		else {
+			self.fill_zero(new_len - self.len());
+		}
+	}
+}
+
+macro_rules! int_accessors {
+	($($ty:ident $size:literal $pop:ident $push:ident $from:ident $to:ident;)*) => {
+		impl RingBuffer<u8> {
+			$(
+				#[doc = concat!("Removes a `", stringify!($ty), "` in ", endian!($from), " byte order from the tail, or returns `None` if there are fewer than ", $size, " bytes.")]
+				#[inline]
+				pub fn $pop(&mut self) -> Option<$ty> {
+					let mut bytes = [0u8; $size];
+					bytes.copy_from_slice(self.as_slice().get(..$size)?);
+					self.remove_tail($size);
+					Some($ty::$from(bytes))
+				}
+
+				#[doc = concat!("Appends a `", stringify!($ty), "` in ", endian!($from), " byte order to the front.")]
+				#[inline]
+				pub fn $push(&mut self, value: $ty) {
+					self.extend_from_slice(&value.$to());
+				}
+			)*
+		}
+	};
+}
+
+macro_rules! endian {
+	(from_le_bytes) => { "little endian" };
+	(from_be_bytes) => { "big endian" };
+}
+
+int_accessors! {
+	u16 2 pop_u16_le push_u16_le from_le_bytes to_le_bytes;
+	u16 2 pop_u16_be push_u16_be from_be_bytes to_be_bytes;
+	u32 4 pop_u32_le push_u32_le from_le_bytes to_le_bytes;
+	u32 4 pop_u32_be push_u32_be from_be_bytes to_be_bytes;
+	u64 8 pop_u64_le push_u64_le from_le_bytes to_le_bytes;
+	u64 8 pop_u64_be push_u64_be from_be_bytes to_be_bytes;
+	i16 2 pop_i16_le push_i16_le from_le_bytes to_le_bytes;
+	i16 2 pop_i16_be push_i16_be from_be_bytes to_be_bytes;
+	i32 4 pop_i32_le push_i32_le from_le_bytes to_le_bytes;
+	i32 4 pop_i32_be push_i32_be from_be_bytes to_be_bytes;
+	i64 8 pop_i64_le push_i64_le from_le_bytes to_le_bytes;
+	i64 8 pop_i64_be push_i64_be from_be_bytes to_be_bytes;
+}
+
+// Maximum size of an encoded LEB128 64-bit integer
+pub(crate) const VARINT_MAX: usize = 10;
+
+// Encodes an unsigned LEB128 integer, returns the number of bytes written
+pub(crate) fn encode_varint(mut value: u64, dst: &mut [u8; VARINT_MAX]) -> usize {
+	let mut n = 0;
+	while value >= 0x80 {
+		dst[n] = value as u8 | 0x80;
+		value >>= 7;
+		n += 1;
+	}
+	dst[n] = value as u8;
+	n + 1
+}
+
+// Decodes an unsigned LEB128 integer, returns the number of bytes read and the value
+pub(crate) fn decode_varint(src: &[u8]) -> Result<Option<(usize, u64)>, FrameError> {
+	let mut value = 0u64;
+	for (i, &byte) in src.iter().enumerate().take(VARINT_MAX) {
+		// The tenth byte may only contribute the most significant bit
+		if i == VARINT_MAX - 1 && byte > 1 {
+			return Err(FrameError::Malformed);
+		}
+		value |= ((byte & 0x7f) as u64) << (i * 7);
+		if byte & 0x80 == 0 {
+			return Ok(Some((i + 1, value)));
+		}
+	}
+	if src.len() >= VARINT_MAX {
+		return Err(FrameError::Malformed);
+	}
+	Ok(None)
+}
+
+impl RingBuffer<u8> {
+	/// Removes an unsigned LEB128 variable length integer from the tail.
+	///
+	/// Returns `Ok(None)` without consuming anything if the integer is incomplete.
+	/// Returns an error without consuming anything if the encoding overflows 64 bits.
+	#[inline]
+	pub fn pop_varint(&mut self) -> Result<Option<u64>, FrameError> {
+		Ok(match decode_varint(self.as_slice())? {
+			Some((n, value)) => {
+				self.remove_tail(n);
+				Some(value)
+			},
+			None => None,
+		})
+	}
+
+	/// Appends an unsigned LEB128 variable length integer to the front.
+	#[inline]
+	pub fn push_varint(&mut self, value: u64) {
+		let mut bytes = [0u8; VARINT_MAX];
+		let n = encode_varint(value, &mut bytes);
+		self.extend_from_slice(&bytes[..n]);
+	}
+
+	/// Removes a signed zigzag LEB128 variable length integer from the tail.
+	///
+	/// See [`pop_varint`](Self::pop_varint) for details.
+	#[inline]
+	pub fn pop_varint_signed(&mut self) -> Result<Option<i64>, FrameError> {
+		Ok(self.pop_varint()?.map(|value| (value >> 1) as i64 ^ -((value & 1) as i64)))
+	}
+
+	/// Appends a signed zigzag LEB128 variable length integer to the front.
+	#[inline]
+	pub fn push_varint_signed(&mut self, value: i64) {
+		self.push_varint(((value << 1) ^ (value >> 63)) as u64);
+	}
+}
+
+impl RingBuffer<u8> {
+	/// Returns an iterator over the complete lines in the ring buffer.
+	///
+	/// Lines are terminated by `\n` with an optional `\r` before it, the line terminator is not included in the yielded lines.
+	/// A trailing partial line without terminator is not yielded and remains in the ring buffer.
+	///
+	/// The yielded lines are consumed from the tail when the iterator is dropped.
+	/// If the iterator is leaked (eg. with [`mem::forget`](std::mem::forget)) the ring buffer is left unchanged.
+	#[inline]
+	pub fn lines(&mut self) -> Lines<'_> {
+		Lines { records: self.records(b'\n') }
+	}
+
+	/// Returns an iterator over the complete records in the ring buffer separated by `delim`.
+	///
+	/// The delimiter is not included in the yielded records.
+	/// A trailing partial record without delimiter is not yielded and remains in the ring buffer.
+	///
+	/// The yielded records are consumed from the tail when the iterator is dropped.
+	/// If the iterator is leaked (eg. with [`mem::forget`](std::mem::forget)) the ring buffer is left unchanged.
+	#[inline]
+	pub fn records(&mut self, delim: u8) -> Records<'_> {
+		Records { rbuf: self, delim, consumed: 0 }
+	}
+}
+
+/// Consuming iterator over delimited records.
+///
+/// This struct is created by the [`records`](RingBuffer::records) method on byte ring buffers.
+#[derive(Debug)]
+pub struct Records<'a> {
+	rbuf: &'a mut RingBuffer<u8>,
+	delim: u8,
+	// Number of bytes yielded so far, including delimiters
+	consumed: usize,
+}
+
+impl<'a> Records<'a> {
+	/// Returns the bytes which have not been yielded yet.
+	#[inline]
+	pub fn remainder(&self) -> &[u8] {
+		&self.rbuf.as_slice()[self.consumed..]
+	}
+}
+
+impl<'a> Iterator for Records<'a> {
+	type Item = &'a [u8];
+
+	#[inline]
+	fn next(&mut self) -> Option<&'a [u8]> {
+		let remainder = self.remainder();
+		let end = memchr::memchr(self.delim, remainder)?;
+		// The yielded bytes are only consumed when the iterator is dropped,
+		// they remain in place for as long as the ring buffer is borrowed
+		let record = unsafe { slice::from_raw_parts(remainder.as_ptr(), end) };
+		self.consumed += end + 1;
+		Some(record)
+	}
+}
+
+impl<'a> Drop for Records<'a> {
+	#[inline]
+	fn drop(&mut self) {
+		// The yielded records borrow the bytes, so they are not wiped
+		self.rbuf.discard(self.consumed, false);
+	}
+}
+
+/// Consuming iterator over lines.
+///
+/// This struct is created by the [`lines`](RingBuffer::lines) method on byte ring buffers.
+#[derive(Debug)]
+pub struct Lines<'a> {
+	records: Records<'a>,
+}
+
+impl<'a> Lines<'a> {
+	/// Returns the bytes which have not been yielded yet.
+	#[inline]
+	pub fn remainder(&self) -> &[u8] {
+		self.records.remainder()
+	}
+}
+
+impl<'a> Iterator for Lines<'a> {
+	type Item = &'a [u8];
+
+	#[inline]
+	fn next(&mut self) -> Option<&'a [u8]> {
+		let line = self.records.next()?;
+		Some(match line.split_last() {
+			Some((b'\r', line)) => line,
+			_ => line,
+		})
+	}
+}
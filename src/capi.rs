@@ -0,0 +1,127 @@
+/*!
+C API over byte ring buffers.
+
+Build the library for C and C++ with `cargo rustc --release --features capi --crate-type cdylib` (or `staticlib`)
+and include `include/vringbuf.h`, which declares the functions below.
+
+Ring buffers are passed around as opaque pointers returned by [`vringbuf_create`] and freed by [`vringbuf_destroy`].
+The functions never panic, allocation failures are reported through their return values.
+
+```
+use vringbuf::capi::*;
+
+unsafe {
+	let rbuf = vringbuf_create(4096);
+	assert!(!rbuf.is_null());
+	assert_eq!(vringbuf_write(rbuf, b"hello".as_ptr(), 5), 5);
+
+	let mut buf = [0u8; 8];
+	assert_eq!(vringbuf_read(rbuf, buf.as_mut_ptr(), buf.len()), 5);
+	assert_eq!(&buf[..5], b"hello");
+	vringbuf_destroy(rbuf);
+}
+```
+*/
+
+use core::{cmp, ptr, slice};
+use alloc::boxed::Box;
+
+use super::RingBuffer;
+
+/// Creates a byte ring buffer with at least `capacity` bytes of capacity, rounded up to the allocation granularity.
+///
+/// Returns null if the memory cannot be allocated.
+#[no_mangle]
+pub extern "C" fn vringbuf_create(capacity: usize) -> *mut RingBuffer<u8> {
+	match RingBuffer::try_with_capacity(capacity) {
+		Ok(rbuf) => Box::into_raw(Box::new(rbuf)),
+		Err(_) => ptr::null_mut(),
+	}
+}
+
+/// Frees a ring buffer, does nothing if it is null.
+///
+/// # Safety
+///
+/// The ring buffer must have been returned by [`vringbuf_create`] and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_destroy(rbuf: *mut RingBuffer<u8>) {
+	if !rbuf.is_null() {
+		drop(Box::from_raw(rbuf));
+	}
+}
+
+/// Appends `len` bytes to the ring buffer, growing it if needed.
+///
+/// Returns `len`, or 0 without appending anything if the ring buffer cannot grow.
+///
+/// # Safety
+///
+/// The ring buffer must be valid and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_write(rbuf: *mut RingBuffer<u8>, data: *const u8, len: usize) -> usize {
+	let rbuf = &mut *rbuf;
+	if len == 0 || rbuf.try_reserve(len).is_err() {
+		return 0;
+	}
+	rbuf.extend_from_slice(slice::from_raw_parts(data, len));
+	len
+}
+
+/// Removes up to `len` bytes from the ring buffer and copies them to `buf`.
+///
+/// Returns the number of bytes copied.
+///
+/// # Safety
+///
+/// The ring buffer must be valid and `buf` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_read(rbuf: *mut RingBuffer<u8>, buf: *mut u8, len: usize) -> usize {
+	let rbuf = &mut *rbuf;
+	let n = cmp::min(len, rbuf.len());
+	ptr::copy_nonoverlapping(rbuf.as_ptr(), buf, n);
+	rbuf.remove_tail(n);
+	n
+}
+
+/// Returns a pointer to the contents of the ring buffer, which are contiguous in memory.
+///
+/// The pointer is valid for [`vringbuf_len`] bytes until the ring buffer is modified.
+///
+/// # Safety
+///
+/// The ring buffer must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_data(rbuf: *const RingBuffer<u8>) -> *const u8 {
+	(*rbuf).as_ptr()
+}
+
+/// Removes up to `len` bytes from the ring buffer without copying them, after reading them through [`vringbuf_data`].
+///
+/// # Safety
+///
+/// The ring buffer must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_consume(rbuf: *mut RingBuffer<u8>, len: usize) {
+	(*rbuf).remove_tail(len);
+}
+
+/// Returns the number of bytes in the ring buffer.
+///
+/// # Safety
+///
+/// The ring buffer must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_len(rbuf: *const RingBuffer<u8>) -> usize {
+	(*rbuf).len()
+}
+
+/// Returns the number of bytes the ring buffer can hold without growing.
+///
+/// # Safety
+///
+/// The ring buffer must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn vringbuf_capacity(rbuf: *const RingBuffer<u8>) -> usize {
+	(*rbuf).capacity()
+}
@@ -0,0 +1,66 @@
+/*!
+Integration with the `bytes` crate.
+
+`RingBuffer<u8>` implements [`Buf`] and [`BufMut`] so it can be used as the read buffer of framed protocol decoders.
+Unlike `BytesMut` consuming a frame never moves the leftover bytes, the mirrored mapping keeps the remaining data contiguous.
+
+```
+use bytes::{Buf, BufMut};
+use vringbuf::RingBuffer;
+
+let mut rbuf = RingBuffer::<u8>::new();
+rbuf.put_u16(5);
+rbuf.put_slice(b"hello");
+
+let len = rbuf.get_u16() as usize;
+assert_eq!(&rbuf.chunk()[..len], b"hello");
+rbuf.advance(len);
+assert!(!rbuf.has_remaining());
+```
+*/
+
+use bytes::{Buf, BufMut};
+use bytes::buf::UninitSlice;
+
+use super::RingBuffer;
+
+impl Buf for RingBuffer<u8> {
+	#[inline]
+	fn remaining(&self) -> usize {
+		self.len()
+	}
+	#[inline]
+	fn chunk(&self) -> &[u8] {
+		self.as_slice()
+	}
+	#[inline]
+	fn advance(&mut self, cnt: usize) {
+		assert!(cnt <= self.len(), "cannot advance past `remaining`: {:?} <= {:?}", cnt, self.len());
+		self.remove_tail(cnt);
+	}
+}
+
+unsafe impl BufMut for RingBuffer<u8> {
+	#[inline]
+	fn remaining_mut(&self) -> usize {
+		// The mirrored mapping can never be larger than isize::MAX bytes
+		isize::MAX as usize - self.len()
+	}
+	#[inline]
+	unsafe fn advance_mut(&mut self, cnt: usize) {
+		assert!(cnt <= self.reserved_len(), "cannot advance past `remaining_mut`: {:?} <= {:?}", cnt, self.reserved_len());
+		self.add_len(cnt);
+	}
+	#[inline]
+	fn chunk_mut(&mut self) -> &mut UninitSlice {
+		// Grow the ring buffer if there is no spare capacity left
+		if self.reserved_len() == 0 {
+			self.reserve(64);
+		}
+		self.reserved_mut().into()
+	}
+	#[inline]
+	fn put_slice(&mut self, src: &[u8]) {
+		self.extend_from_slice(src);
+	}
+}
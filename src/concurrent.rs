@@ -0,0 +1,68 @@
+/*!
+Concurrent ring buffers.
+
+A [`RingBuffer`](crate::RingBuffer) can be [`split`](crate::RingBuffer::split) into a [`Producer`] and [`Consumer`] pair
+which can be sent to different threads for single-producer single-consumer use.
+The mirrored mapping makes the readable elements and the free space each available as a single contiguous slice.
+When either half is dropped the other half is notified, pushing fails with [`PushError::Closed`]
+and popping fails with [`PopError::Closed`] once the remaining elements have been consumed.
+
+Both halves can wait for the other side by blocking the current thread (`push_blocking`, `pop_timeout`, ...)
+or asynchronously (`send`, `recv`), see [`WaitStrategy`] to tune how they wait.
+Watermarks ([`set_high_watermark`](Consumer::set_high_watermark), [`set_low_watermark`](Producer::set_low_watermark)) batch the wake ups so a waiting side is only woken up once enough elements or free space are available.
+With the `futures` feature the consumer implements `Stream` and the producer implements `Sink`.
+[`Select`] waits on several consumers at once.
+
+Other ways to share a ring buffer between threads:
+
+* [`broadcast`](crate::RingBuffer::broadcast) hands every element to any number of [`BroadcastConsumer`]s.
+* [`split_mpsc`](crate::RingBuffer::split_mpsc) lets any number of [`MpscProducer`]s claim slots for a single [`MpscConsumer`].
+* [`split_overwrite`](crate::RingBuffer::split_overwrite) lets the [`OverwriteProducer`] drop the oldest elements instead of stalling.
+* [`into_history`](crate::RingBuffer::into_history) lets [`HistoryReader`]s sample the most recent values without ever blocking the [`HistoryWriter`].
+* [`MpmcRingBuffer`] is a bounded queue for more than one thread on both sides.
+
+```
+let mut rbuf = vringbuf::RingBuffer::<i32>::with_capacity(1024);
+rbuf.push(1);
+
+let (mut producer, mut consumer) = rbuf.split();
+let thread = std::thread::spawn(move || {
+	for i in 2..=10 {
+		producer.push_blocking(i).unwrap();
+	}
+});
+
+let mut sum = 0;
+while let Some(value) = consumer.pop_blocking() {
+	sum += value;
+}
+thread.join().unwrap();
+assert_eq!(sum, 55);
+```
+*/
+
+mod broadcast;
+pub use self::broadcast::{BroadcastConsumer, BroadcastProducer};
+mod error;
+pub use self::error::{Disconnected, PopError, PushError};
+
+mod history;
+pub use self::history::{HistoryReader, HistoryWriter};
+mod mpmc;
+pub use self::mpmc::MpmcRingBuffer;
+mod mpsc;
+pub use self::mpsc::{MpscConsumer, MpscProducer};
+mod notify;
+pub use self::notify::WaitStrategy;
+mod overwrite;
+pub use self::overwrite::{OverwriteConsumer, OverwriteProducer};
+pub(crate) mod padded;
+mod ring;
+mod select;
+pub use self::select::Select;
+mod spsc;
+pub use self::spsc::{Consumer, Producer, TryIter};
+mod sync;
+
+#[cfg(feature = "futures")]
+mod stream;
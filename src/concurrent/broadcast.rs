@@ -0,0 +1,325 @@
+use std::{cmp, fmt, ptr, slice};
+
+use crate::RingBuffer;
+use super::{PopError, PushError};
+use super::padded::CachePadded;
+use super::ring::Ring;
+use super::sync::{Arc, AtomicBool, AtomicUsize, Mutex, Ordering};
+
+// Read offset of a consumer, only modified by its consumer.
+type Cursor = Arc<CachePadded<AtomicUsize>>;
+
+// State shared between the producer and all the consumers.
+//
+// Every consumer publishes its own cursor.
+// The producer reclaims and drops the elements all the cursors have passed.
+// The consumers only ever hand out references.
+struct Shared<T> {
+	ring: Ring<T>,
+	// Offset of the next element to be written, only modified by the producer.
+	head: CachePadded<AtomicUsize>,
+	// Offset up to which the elements have been dropped.
+	reclaimed: AtomicUsize,
+	// Cursors of all the live consumers.
+	cursors: Mutex<Vec<Cursor>>,
+	// Number of live consumers.
+	consumers: AtomicUsize,
+	// Set when the producer has been dropped.
+	closed: AtomicBool,
+}
+
+impl<T> Drop for Shared<T> {
+	fn drop(&mut self) {
+		let tail = self.reclaimed.load(Ordering::Relaxed);
+		let head = self.head.load(Ordering::Relaxed);
+		let len = self.ring.distance(tail, head);
+		unsafe { ptr::slice_from_raw_parts_mut(self.ring.at(tail), len).drop_in_place(); }
+	}
+}
+
+// The consumers only share references to the elements
+unsafe impl<T: Send + Sync> Send for Shared<T> {}
+unsafe impl<T: Send + Sync> Sync for Shared<T> {}
+
+impl<T> RingBuffer<T> {
+	/// Turns the ring buffer into a producer and consumer pair for single-producer multi-consumer broadcasting across threads.
+	///
+	/// Every consumer sees every element.
+	/// [Cloning](Clone) a consumer adds a consumer starting at the same position.
+	/// The memory of an element is reclaimed once all the consumers have moved past it.
+	///
+	/// The elements in the ring buffer are handed over to the consumer.
+	/// The capacity is fixed at the moment of splitting, so [`reserve`](Self::reserve) enough beforehand.
+	///
+	/// ```
+	/// let rbuf = vringbuf::RingBuffer::<i32>::with_capacity(16);
+	/// let (mut producer, mut first) = rbuf.broadcast();
+	/// let mut second = first.clone();
+	///
+	/// producer.push_slice(&[1, 2, 3]);
+	/// assert_eq!(first.read(), [1, 2, 3]);
+	/// first.release(3);
+	/// assert_eq!(second.pop(), Ok(1));
+	/// assert_eq!(producer.len(), 2);
+	/// ```
+	pub fn broadcast(self) -> (BroadcastProducer<T>, BroadcastConsumer<T>) {
+		let (ring, tail, head) = Ring::from_ring_buffer(self);
+		let cursor: Cursor = Arc::new(CachePadded(AtomicUsize::new(tail)));
+		let shared = Arc::new(Shared {
+			ring,
+			head: CachePadded(AtomicUsize::new(head)),
+			reclaimed: AtomicUsize::new(tail),
+			cursors: Mutex::new(vec![cursor.clone()]),
+			consumers: AtomicUsize::new(1),
+			closed: AtomicBool::new(false),
+		});
+		let producer = BroadcastProducer { shared: shared.clone(), head, reclaimed: tail };
+		let consumer = BroadcastConsumer { shared, cursor, tail, cached_head: head };
+		(producer, consumer)
+	}
+}
+
+/// Producer half of a broadcast ring buffer.
+///
+/// This struct is created by the [`broadcast`](RingBuffer::broadcast) method on ring buffers.
+pub struct BroadcastProducer<T> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared head offset.
+	head: usize,
+	// Offset up to which the elements have been dropped.
+	reclaimed: usize,
+}
+
+impl<T> BroadcastProducer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements which have not been reclaimed yet.
+	///
+	/// This is the number of elements the slowest consumer has yet to release.
+	#[inline]
+	pub fn len(&mut self) -> usize {
+		self.reclaim();
+		self.shared.ring.distance(self.reclaimed, self.head)
+	}
+
+	/// Returns `true` if all the consumers have released all the elements.
+	#[inline]
+	pub fn is_empty(&mut self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if all the consumers have been dropped.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.consumers.load(Ordering::Acquire) == 0
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the slowest consumer has not made room for it or all the consumers have been dropped.
+	#[inline]
+	pub fn push(&mut self, value: T) -> Result<(), PushError<T>> {
+		if self.is_closed() {
+			return Err(PushError::Closed(value));
+		}
+		if self.available(1) == 0 {
+			return Err(PushError::Full(value));
+		}
+		unsafe { self.shared.ring.at(self.head).write(value); }
+		self.publish(1);
+		Ok(())
+	}
+
+	/// Copies as many elements from `values` as there is room for and appends them to the front.
+	///
+	/// Returns the number of elements appended.
+	/// Nothing is appended if all the consumers have been dropped.
+	#[inline]
+	pub fn push_slice(&mut self, values: &[T]) -> usize where T: Copy {
+		let n = cmp::min(self.available(values.len()), values.len());
+		if n == 0 || self.is_closed() {
+			return 0;
+		}
+		unsafe { values.as_ptr().copy_to_nonoverlapping(self.shared.ring.at(self.head), n); }
+		self.publish(n);
+		n
+	}
+
+	// Number of free elements, reclaims memory only if fewer than `n` are known to be free
+	#[inline]
+	fn available(&mut self, n: usize) -> usize {
+		let free = self.capacity() - self.shared.ring.distance(self.reclaimed, self.head);
+		if free >= n {
+			return free;
+		}
+		self.reclaim();
+		self.capacity() - self.shared.ring.distance(self.reclaimed, self.head)
+	}
+
+	// Drops the elements all the consumers have moved past.
+	fn reclaim(&mut self) {
+		let cursors = self.shared.cursors.lock().unwrap();
+		// The slowest consumer is the one furthest away from the head
+		let tail = cursors.iter()
+			.map(|cursor| cursor.load(Ordering::Acquire))
+			.max_by_key(|&tail| self.shared.ring.distance(tail, self.head))
+			.unwrap_or(self.head);
+		drop(cursors);
+		let n = self.shared.ring.distance(self.reclaimed, tail);
+		unsafe { ptr::slice_from_raw_parts_mut(self.shared.ring.at(self.reclaimed), n).drop_in_place(); }
+		self.reclaimed = tail;
+	}
+
+	// Publishes `n` elements written after the head.
+	#[inline]
+	fn publish(&mut self, n: usize) {
+		self.head = self.shared.ring.advance(self.head, n);
+		self.shared.head.store(self.head, Ordering::Release);
+	}
+}
+
+impl<T> Drop for BroadcastProducer<T> {
+	fn drop(&mut self) {
+		self.shared.reclaimed.store(self.reclaimed, Ordering::Relaxed);
+		self.shared.closed.store(true, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for BroadcastProducer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("BroadcastProducer")
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+/// Consumer half of a broadcast ring buffer.
+///
+/// This struct is created by the [`broadcast`](RingBuffer::broadcast) method on ring buffers.
+pub struct BroadcastConsumer<T> {
+	shared: Arc<Shared<T>>,
+	// Published copy of the tail offset.
+	cursor: Cursor,
+	// Private copy of the tail offset.
+	tail: usize,
+	// Last observed head offset.
+	cached_head: usize,
+}
+
+impl<T> BroadcastConsumer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements this consumer has yet to release.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let head = self.shared.head.load(Ordering::Acquire);
+		self.shared.ring.distance(self.tail, head)
+	}
+
+	/// Returns `true` if this consumer has released all the elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the producer has been dropped.
+	///
+	/// There may still be elements left to read.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Returns all the elements this consumer has yet to release.
+	#[inline]
+	pub fn read(&mut self) -> &[T] {
+		let len = self.available(usize::MAX);
+		unsafe { slice::from_raw_parts(self.shared.ring.at(self.tail), len) }
+	}
+
+	/// Moves this consumer past the first `n` elements.
+	///
+	/// The memory is reclaimed once all the consumers have moved past the elements.
+	///
+	/// # Panics
+	///
+	/// Panics if fewer than `n` elements are available.
+	#[inline]
+	pub fn release(&mut self, n: usize) {
+		assert!(n <= self.available(n), "release exceeds the available elements");
+		self.tail = self.shared.ring.advance(self.tail, n);
+		self.cursor.store(self.tail, Ordering::Release);
+	}
+
+	/// Clones the element at the tail and moves this consumer past it.
+	///
+	/// Returns an error if no elements are available.
+	/// The error is [`PopError::Closed`] if the producer has also been dropped.
+	#[inline]
+	pub fn pop(&mut self) -> Result<T, PopError> where T: Clone {
+		if self.available(1) == 0 {
+			// The producer publishes its last elements before closing
+			return Err(if self.is_closed() && self.available(1) == 0 { PopError::Closed } else { PopError::Empty });
+		}
+		let value = unsafe { (*self.shared.ring.at(self.tail)).clone() };
+		self.release(1);
+		Ok(value)
+	}
+
+	// Number of available elements, reloads the head offset only if fewer than `n` are known to be available
+	#[inline]
+	fn available(&mut self, n: usize) -> usize {
+		let len = self.shared.ring.distance(self.tail, self.cached_head);
+		if len >= n {
+			return len;
+		}
+		self.cached_head = self.shared.head.load(Ordering::Acquire);
+		self.shared.ring.distance(self.tail, self.cached_head)
+	}
+}
+
+impl<T> Clone for BroadcastConsumer<T> {
+	/// Adds a consumer starting at the same position as this consumer.
+	fn clone(&self) -> BroadcastConsumer<T> {
+		let cursor: Cursor = Arc::new(CachePadded(AtomicUsize::new(self.tail)));
+		// Registered while this consumer holds back the producer, so the elements cannot have been reclaimed
+		let mut cursors = self.shared.cursors.lock().unwrap();
+		cursors.push(cursor.clone());
+		self.shared.consumers.fetch_add(1, Ordering::Release);
+		drop(cursors);
+		BroadcastConsumer {
+			shared: self.shared.clone(),
+			cursor,
+			tail: self.tail,
+			cached_head: self.cached_head,
+		}
+	}
+}
+
+impl<T> Drop for BroadcastConsumer<T> {
+	fn drop(&mut self) {
+		let mut cursors = self.shared.cursors.lock().unwrap();
+		if let Some(index) = cursors.iter().position(|cursor| Arc::ptr_eq(cursor, &self.cursor)) {
+			cursors.swap_remove(index);
+		}
+		self.shared.consumers.fetch_sub(1, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for BroadcastConsumer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("BroadcastConsumer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
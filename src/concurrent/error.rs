@@ -0,0 +1,86 @@
+use std::{error, fmt};
+
+/// Error pushing an element into a concurrent ring buffer.
+///
+/// The element is handed back to the caller.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum PushError<T> {
+	/// The ring buffer is full.
+	Full(T),
+	/// The consumer has been dropped.
+	Closed(T),
+}
+
+impl<T> PushError<T> {
+	/// Returns the element which failed to be pushed.
+	#[inline]
+	pub fn into_inner(self) -> T {
+		match self {
+			PushError::Full(value) | PushError::Closed(value) => value,
+		}
+	}
+
+	/// Returns `true` if the push failed because the ring buffer is full.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		matches!(self, PushError::Full(_))
+	}
+
+	/// Returns `true` if the push failed because the consumer has been dropped.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		matches!(self, PushError::Closed(_))
+	}
+}
+
+impl<T> fmt::Debug for PushError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			PushError::Full(_) => "Full(..)",
+			PushError::Closed(_) => "Closed(..)",
+		})
+	}
+}
+
+impl<T> fmt::Display for PushError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			PushError::Full(_) => "pushing into a full ring buffer",
+			PushError::Closed(_) => "pushing into a closed ring buffer",
+		})
+	}
+}
+
+impl<T> error::Error for PushError<T> {}
+
+/// Error popping an element from a concurrent ring buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PopError {
+	/// The ring buffer is empty.
+	Empty,
+	/// The ring buffer is empty and the producer has been dropped.
+	Closed,
+}
+
+impl fmt::Display for PopError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			PopError::Empty => "popping from an empty ring buffer",
+			PopError::Closed => "popping from an empty and closed ring buffer",
+		})
+	}
+}
+
+impl error::Error for PopError {}
+
+/// Error returned when the other half of a concurrent ring buffer has been dropped.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("the other half of the ring buffer has been dropped")
+	}
+}
+
+impl error::Error for Disconnected {}
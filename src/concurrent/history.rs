@@ -0,0 +1,227 @@
+use std::{cmp, fmt, mem};
+
+use crate::RingBuffer;
+use super::padded::CachePadded;
+use super::ring::Ring;
+use super::sync::{fence, Arc, AtomicUsize, Ordering};
+
+// State shared between the writer and the readers.
+//
+// The elements live at stream positions.
+// The most recent elements are always available as a single contiguous slice.
+//
+// The writer announces the end of the element it is about to write in `claimed` before overwriting the oldest bytes,
+// and publishes it in `head` afterwards. This is a seqlock where the readers validate their copy
+// by checking that the writer has not claimed any bytes overlapping with the copied range in the meantime.
+struct Shared<T> {
+	ring: Ring<T>,
+	// End position of the element being written.
+	claimed: CachePadded<AtomicUsize>,
+	// End position of the most recent element.
+	head: CachePadded<AtomicUsize>,
+	// Number of elements available to the readers.
+	len: AtomicUsize,
+}
+
+// The elements are only ever copied
+unsafe impl<T: Copy + Send> Send for Shared<T> {}
+unsafe impl<T: Copy + Send> Sync for Shared<T> {}
+
+impl<T: Copy> RingBuffer<T> {
+	/// Turns the ring buffer into a history of the most recent elements, shared between a writer and any number of readers.
+	///
+	/// The writer never blocks. Once the ring buffer is full it overwrites the oldest elements.
+	/// The readers copy out the most recent elements and retry if the writer overwrote them while copying.
+	///
+	/// The elements in the ring buffer are the initial history.
+	/// The capacity is fixed at the moment of splitting, so [`reserve`](Self::reserve) enough beforehand.
+	///
+	/// ```
+	/// let rbuf = vringbuf::RingBuffer::<f32>::with_capacity(1024);
+	/// let (mut writer, reader) = rbuf.into_history();
+	///
+	/// for i in 0..10_000 {
+	/// 	writer.push(i as f32);
+	/// }
+	///
+	/// let mut recent = [0.0; 3];
+	/// assert_eq!(reader.read_latest(&mut recent), 3);
+	/// assert_eq!(recent, [9997.0, 9998.0, 9999.0]);
+	/// assert_eq!(reader.latest(), Some(9999.0));
+	/// ```
+	pub fn into_history(self) -> (HistoryWriter<T>, HistoryReader<T>) {
+		let len = self.len();
+		let (ring, _, head) = Ring::from_ring_buffer(self);
+		let shared = Arc::new(Shared {
+			ring,
+			claimed: CachePadded(AtomicUsize::new(head)),
+			head: CachePadded(AtomicUsize::new(head)),
+			len: AtomicUsize::new(len),
+		});
+		let writer = HistoryWriter { shared: shared.clone(), head, len };
+		let reader = HistoryReader { shared };
+		(writer, reader)
+	}
+}
+
+/// Writer half of a history ring buffer.
+///
+/// This struct is created by the [`into_history`](RingBuffer::into_history) method on ring buffers.
+pub struct HistoryWriter<T: Copy> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared head position.
+	head: usize,
+	// Private copy of the shared length.
+	len: usize,
+}
+
+impl<T: Copy> HistoryWriter<T> {
+	/// Returns the number of elements the history can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the history.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Returns `true` if nothing has been written yet.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Appends an element, overwriting the oldest element if the history is full.
+	#[inline]
+	pub fn push(&mut self, value: T) {
+		self.push_slice(&[value]);
+	}
+
+	/// Appends all the elements of the slice, overwriting the oldest elements if the history is full.
+	///
+	/// Only the most recent elements are kept if the slice is longer than the capacity.
+	pub fn push_slice(&mut self, values: &[T]) {
+		let values = &values[values.len().saturating_sub(self.capacity())..];
+		if values.is_empty() {
+			return;
+		}
+		let shared = &*self.shared;
+		let head = shared.ring.stream_advance(self.head, values.len());
+		// Invalidate the readers copying the oldest elements before overwriting them
+		shared.claimed.store(head, Ordering::Relaxed);
+		fence(Ordering::Release);
+		unsafe { values.as_ptr().copy_to_nonoverlapping(shared.ring.stream_at(self.head), values.len()); }
+		self.head = head;
+		self.len = cmp::min(self.len + values.len(), self.capacity());
+		// Readers load the length first, so publish it last
+		shared.head.store(head, Ordering::Release);
+		shared.len.store(self.len, Ordering::Release);
+	}
+
+	/// Returns a reader of the history.
+	#[inline]
+	pub fn reader(&self) -> HistoryReader<T> {
+		HistoryReader { shared: self.shared.clone() }
+	}
+}
+
+impl<T: Copy> fmt::Debug for HistoryWriter<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("HistoryWriter")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+/// Reader half of a history ring buffer.
+///
+/// This struct is created by the [`into_history`](RingBuffer::into_history) method on ring buffers.
+/// Clone it to add more readers.
+pub struct HistoryReader<T: Copy> {
+	shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> HistoryReader<T> {
+	/// Returns the number of elements the history can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the history.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.shared.len.load(Ordering::Acquire)
+	}
+
+	/// Returns `true` if nothing has been written yet.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the most recent element.
+	#[inline]
+	pub fn latest(&self) -> Option<T> {
+		let mut value = [mem::MaybeUninit::uninit()];
+		match self.read_latest_uninit(&mut value) {
+			0 => None,
+			_ => Some(unsafe { value[0].assume_init() }),
+		}
+	}
+
+	/// Copies the most recent elements into `dst`, oldest first.
+	///
+	/// Returns the number of elements copied.
+	#[inline]
+	pub fn read_latest(&self, dst: &mut [T]) -> usize {
+		unsafe { self.read_latest_uninit(&mut *(dst as *mut [T] as *mut [mem::MaybeUninit<T>])) }
+	}
+
+	/// Copies the most recent elements into `dst`, oldest first.
+	///
+	/// Returns the number of elements `n` copied.
+	/// The first `n` elements of `dst` are initialized.
+	pub fn read_latest_uninit(&self, dst: &mut [mem::MaybeUninit<T>]) -> usize {
+		let shared = &*self.shared;
+		loop {
+			let n = cmp::min(dst.len(), shared.len.load(Ordering::Acquire));
+			if n == 0 {
+				return 0;
+			}
+			let head = shared.head.load(Ordering::Acquire);
+			let tail = shared.ring.stream_retreat(head, n);
+			// The copy races with the writer, so the bytes are read volatile
+			let src = shared.ring.stream_at(tail) as *const mem::MaybeUninit<T>;
+			for (i, elem) in dst[..n].iter_mut().enumerate() {
+				*elem = unsafe { src.add(i).read_volatile() };
+			}
+			fence(Ordering::Acquire);
+			let claimed = shared.claimed.load(Ordering::Relaxed);
+			// The writer overwrites the bytes one capacity before the claimed position
+			if shared.ring.stream_distance(tail, claimed) <= shared.ring.bytes() {
+				return n;
+			}
+		}
+	}
+}
+
+impl<T: Copy> Clone for HistoryReader<T> {
+	#[inline]
+	fn clone(&self) -> HistoryReader<T> {
+		HistoryReader { shared: self.shared.clone() }
+	}
+}
+
+impl<T: Copy> fmt::Debug for HistoryReader<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("HistoryReader")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
@@ -0,0 +1,254 @@
+use std::{cmp, fmt, mem, ptr};
+use std::cell::UnsafeCell;
+use std::ptr::NonNull;
+
+use crate::{platform, RingBuffer};
+use super::padded::CachePadded;
+use super::sync::{fence, hint, AtomicUsize, Ordering};
+
+// Each slot carries a stamp which tells the producers and consumers whose turn it is.
+//
+// A stamp is made of an index in the lower bits and a lap counter in the upper bits.
+// The slot is writable when its stamp equals the tail and readable when it equals the head + 1.
+struct Slot<T> {
+	stamp: AtomicUsize,
+	value: UnsafeCell<mem::MaybeUninit<T>>,
+}
+
+/// Bounded multi-producer multi-consumer ring buffer.
+///
+/// Any number of threads can push and pop elements concurrently through a shared reference.
+/// Wrap it in an [`Arc`](std::sync::Arc) to share it between threads.
+///
+/// The slots are laid out on a fixed grid, so the elements are never available as contiguous slices.
+/// Element types aligned to more than 4 KiB are rejected at compile time.
+///
+/// ```
+/// use std::sync::Arc;
+/// use vringbuf::concurrent::MpmcRingBuffer;
+///
+/// let rbuf = Arc::new(MpmcRingBuffer::<u32>::with_capacity(64));
+/// let threads: Vec<_> = (0..4).map(|i| {
+/// 	let rbuf = rbuf.clone();
+/// 	std::thread::spawn(move || while rbuf.try_push(i).is_err() {})
+/// }).collect();
+/// for thread in threads {
+/// 	thread.join().unwrap();
+/// }
+///
+/// let mut sum = 0;
+/// while let Some(value) = rbuf.try_pop() {
+/// 	sum += value;
+/// }
+/// assert_eq!(sum, 6);
+/// ```
+pub struct MpmcRingBuffer<T> {
+	ptr: NonNull<Slot<T>>,
+	// Size of the allocation in bytes.
+	cap: usize,
+	handle: platform::Handle,
+	// Number of slots.
+	capacity: usize,
+	// Stamp increment to go from one lap to the next.
+	one_lap: usize,
+	// Stamp of the next slot to pop.
+	head: CachePadded<AtomicUsize>,
+	// Stamp of the next slot to push.
+	tail: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send> Send for MpmcRingBuffer<T> {}
+unsafe impl<T: Send> Sync for MpmcRingBuffer<T> {}
+
+impl<T> MpmcRingBuffer<T> {
+	/// Constructs a new ring buffer with room for at least `capacity` elements.
+	///
+	/// The capacity is rounded up to the allocation granularity and is at least one.
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits or there is not enough contigious memory for 2x the requested capacity.
+	pub fn with_capacity(capacity: usize) -> MpmcRingBuffer<T> {
+		let () = RingBuffer::<Slot<T>>::ALIGNED;
+		let size = mem::size_of::<Slot<T>>();
+		let (ptr, cap, handle) = match unsafe { platform::allocate(cmp::max(capacity, 1), size, &platform::Options::new()) } {
+			Ok(alloc) => alloc,
+			Err(err) => platform::alloc_failed(err),
+		};
+		let ptr = ptr.cast::<Slot<T>>();
+		let capacity = cap / size;
+		for i in 0..capacity {
+			unsafe {
+				ptr.as_ptr().add(i).write(Slot {
+					stamp: AtomicUsize::new(i),
+					value: UnsafeCell::new(mem::MaybeUninit::uninit()),
+				});
+			}
+		}
+		MpmcRingBuffer {
+			ptr,
+			cap,
+			handle,
+			capacity,
+			one_lap: (capacity + 1).next_power_of_two(),
+			head: CachePadded(AtomicUsize::new(0)),
+			tail: CachePadded(AtomicUsize::new(0)),
+		}
+	}
+
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// The result is a snapshot which may be out of date by the time it is returned.
+	pub fn len(&self) -> usize {
+		loop {
+			let tail = self.tail.load(Ordering::SeqCst);
+			let head = self.head.load(Ordering::SeqCst);
+			// Retry until a consistent pair has been observed
+			if self.tail.load(Ordering::SeqCst) == tail {
+				let head_index = head & (self.one_lap - 1);
+				let tail_index = tail & (self.one_lap - 1);
+				return if head_index < tail_index {
+					tail_index - head_index
+				}
+				else if head_index > tail_index {
+					self.capacity - head_index + tail_index
+				}
+				else if head == tail {
+					0
+				}
+				else {
+					self.capacity
+				};
+			}
+		}
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the ring buffer has no room for more elements.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		self.len() == self.capacity
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the ring buffer is full.
+	pub fn try_push(&self, value: T) -> Result<(), T> {
+		let mut tail = self.tail.load(Ordering::Relaxed);
+		loop {
+			let slot = self.slot(tail);
+			let stamp = slot.stamp.load(Ordering::Acquire);
+			if stamp == tail {
+				// The slot is free, try to claim it
+				match self.tail.compare_exchange_weak(tail, self.next(tail), Ordering::SeqCst, Ordering::Relaxed) {
+					Ok(_) => {
+						unsafe { (*slot.value.get()).as_mut_ptr().write(value); }
+						slot.stamp.store(tail + 1, Ordering::Release);
+						return Ok(());
+					},
+					Err(current) => tail = current,
+				}
+			}
+			else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+				// The slot still holds the element from the previous lap
+				fence(Ordering::SeqCst);
+				let head = self.head.load(Ordering::Relaxed);
+				if head.wrapping_add(self.one_lap) == tail {
+					return Err(value);
+				}
+				hint::spin_loop();
+				tail = self.tail.load(Ordering::Relaxed);
+			}
+			else {
+				// Another producer claimed the slot but has not caught up yet
+				hint::spin_loop();
+				tail = self.tail.load(Ordering::Relaxed);
+			}
+		}
+	}
+
+	/// Removes the element at the tail and returns it.
+	///
+	/// Returns [`None`] if the ring buffer is empty.
+	pub fn try_pop(&self) -> Option<T> {
+		let mut head = self.head.load(Ordering::Relaxed);
+		loop {
+			let slot = self.slot(head);
+			let stamp = slot.stamp.load(Ordering::Acquire);
+			if stamp == head + 1 {
+				// The slot is readable, try to claim it
+				match self.head.compare_exchange_weak(head, self.next(head), Ordering::SeqCst, Ordering::Relaxed) {
+					Ok(_) => {
+						let value = unsafe { (*slot.value.get()).as_ptr().read() };
+						slot.stamp.store(head.wrapping_add(self.one_lap), Ordering::Release);
+						return Some(value);
+					},
+					Err(current) => head = current,
+				}
+			}
+			else if stamp == head {
+				// The slot has not been written in this lap yet
+				fence(Ordering::SeqCst);
+				let tail = self.tail.load(Ordering::Relaxed);
+				if tail == head {
+					return None;
+				}
+				hint::spin_loop();
+				head = self.head.load(Ordering::Relaxed);
+			}
+			else {
+				// Another consumer claimed the slot but has not caught up yet
+				hint::spin_loop();
+				head = self.head.load(Ordering::Relaxed);
+			}
+		}
+	}
+
+	// Slot addressed by the stamp
+	#[inline]
+	fn slot(&self, stamp: usize) -> &Slot<T> {
+		let index = stamp & (self.one_lap - 1);
+		unsafe { &*self.ptr.as_ptr().add(index) }
+	}
+	// Stamp following the given stamp
+	#[inline]
+	fn next(&self, stamp: usize) -> usize {
+		let index = stamp & (self.one_lap - 1);
+		if index + 1 < self.capacity {
+			stamp + 1
+		}
+		else {
+			(stamp & !(self.one_lap - 1)).wrapping_add(self.one_lap)
+		}
+	}
+}
+
+impl<T> Drop for MpmcRingBuffer<T> {
+	fn drop(&mut self) {
+		while self.try_pop().is_some() {}
+		unsafe {
+			ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.capacity).drop_in_place();
+			platform::free(self.ptr.cast(), self.cap, self.handle);
+		}
+	}
+}
+
+impl<T> fmt::Debug for MpmcRingBuffer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MpmcRingBuffer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
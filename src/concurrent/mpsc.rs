@@ -0,0 +1,339 @@
+use std::{cmp, fmt, iter};
+
+use crate::RingBuffer;
+use super::{PopError, PushError};
+use super::padded::CachePadded;
+use super::ring::Ring;
+use super::sync::{Arc, AtomicBool, AtomicUsize, Ordering};
+
+// State shared between the producers and the consumer.
+//
+// Producers claim a range of sequence numbers by advancing `claimed` and write the elements into their slots.
+// Then they publish every slot individually by storing its sequence number in the slot's flag.
+// The consumer reads the slots in order and stops at the first slot whose flag does not match its sequence number yet.
+//
+// A sequence number is made of the index of its slot in the lower bits and a lap counter in the upper bits.
+// A slot can only be claimed again once the consumer moved past its previous sequence number.
+// So a flag holds either the sequence number of the previous lap or the one the consumer is waiting for.
+struct Shared<T> {
+	ring: Ring<T>,
+	// Availability flag of every slot.
+	flags: Box<[AtomicUsize]>,
+	// Sequence number increment to go from one lap to the next.
+	one_lap: usize,
+	// Next sequence number to be claimed by the producers.
+	claimed: CachePadded<AtomicUsize>,
+	// Next sequence number to be read.
+	tail: CachePadded<AtomicUsize>,
+	// Number of live producers.
+	producers: AtomicUsize,
+	// Set when the consumer has been dropped.
+	closed: AtomicBool,
+}
+
+impl<T> Shared<T> {
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.flags.len()
+	}
+	// Number of sequence numbers between the tail and head
+	#[inline]
+	fn distance(&self, tail: usize, head: usize) -> usize {
+		let laps = (head & !(self.one_lap - 1)).wrapping_sub(tail & !(self.one_lap - 1)) / self.one_lap;
+		laps.wrapping_mul(self.capacity()).wrapping_add(self.index(head)).wrapping_sub(self.index(tail))
+	}
+	// Advances a sequence number by at most the capacity
+	#[inline]
+	fn advance(&self, seq: usize, n: usize) -> usize {
+		let index = self.index(seq) + n;
+		let lap = seq & !(self.one_lap - 1);
+		if index >= self.capacity() {
+			lap.wrapping_add(self.one_lap) | (index - self.capacity())
+		}
+		else {
+			lap | index
+		}
+	}
+	// Index of the slot of the sequence number
+	#[inline]
+	fn index(&self, seq: usize) -> usize {
+		seq & (self.one_lap - 1)
+	}
+}
+
+impl<T> Drop for Shared<T> {
+	fn drop(&mut self) {
+		// All the claimed elements have been published once the producers are gone
+		let mut tail = self.tail.load(Ordering::Relaxed);
+		let head = self.claimed.load(Ordering::Relaxed);
+		while tail != head {
+			unsafe { self.ring.slot(self.index(tail)).drop_in_place(); }
+			tail = self.advance(tail, 1);
+		}
+	}
+}
+
+// The slots are handed off between threads through the flags
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> RingBuffer<T> {
+	/// Splits the ring buffer into a producer and consumer pair for multi-producer single-consumer use across threads.
+	///
+	/// [Clone](Clone) the producer to add more producers.
+	/// Unlike [`split`](Self::split) the elements are not available as slices.
+	///
+	/// The elements in the ring buffer are handed over to the consumer.
+	/// The capacity is fixed at the moment of splitting, so [`reserve`](Self::reserve) enough beforehand.
+	///
+	/// ```
+	/// let rbuf = vringbuf::RingBuffer::<i32>::with_capacity(1024);
+	/// let (producer, mut consumer) = rbuf.split_mpsc();
+	///
+	/// let threads: Vec<_> = (1..=4).map(|i| {
+	/// 	let mut producer = producer.clone();
+	/// 	std::thread::spawn(move || producer.push(i).unwrap())
+	/// }).collect();
+	/// drop(producer);
+	/// for thread in threads {
+	/// 	thread.join().unwrap();
+	/// }
+	///
+	/// let sum: i32 = std::iter::from_fn(|| consumer.pop().ok()).sum();
+	/// assert_eq!(sum, 10);
+	/// ```
+	pub fn split_mpsc(mut self) -> (MpscProducer<T>, MpscConsumer<T>) {
+		// Move the elements onto the grid of slots
+		let values: Vec<T> = iter::from_fn(|| self.pop()).collect();
+		let (ring, _, _) = Ring::from_ring_buffer(self);
+		let capacity = ring.capacity();
+		let one_lap = (capacity + 1).next_power_of_two();
+		// The slots without an element are published in the lap before the first
+		let flags = (0..capacity)
+			.map(|index| AtomicUsize::new(if index < values.len() { index } else { index.wrapping_sub(one_lap) }))
+			.collect();
+		// A full ring buffer has its head a lap ahead
+		let head = if capacity != 0 && values.len() == capacity { one_lap } else { values.len() };
+		for (index, value) in values.into_iter().enumerate() {
+			unsafe { ring.slot(index).write(value); }
+		}
+		let shared = Arc::new(Shared {
+			ring,
+			flags,
+			one_lap,
+			claimed: CachePadded(AtomicUsize::new(head)),
+			tail: CachePadded(AtomicUsize::new(0)),
+			producers: AtomicUsize::new(1),
+			closed: AtomicBool::new(false),
+		});
+		let producer = MpscProducer { shared: shared.clone() };
+		let consumer = MpscConsumer { shared, tail: 0 };
+		(producer, consumer)
+	}
+}
+
+/// Producer half of a multi-producer ring buffer.
+///
+/// This struct is created by the [`split_mpsc`](RingBuffer::split_mpsc) method on ring buffers.
+pub struct MpscProducer<T> {
+	shared: Arc<Shared<T>>,
+}
+
+impl<T> MpscProducer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// Includes the elements other producers are still writing.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let tail = self.shared.tail.load(Ordering::Acquire);
+		let head = self.shared.claimed.load(Ordering::Acquire);
+		self.shared.distance(tail, head)
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the consumer has been dropped.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the ring buffer is full or the consumer has been dropped.
+	#[inline]
+	pub fn push(&mut self, value: T) -> Result<(), PushError<T>> {
+		if self.is_closed() {
+			return Err(PushError::Closed(value));
+		}
+		let seq = match self.claim(1) {
+			Some((seq, _)) => seq,
+			None => return Err(PushError::Full(value)),
+		};
+		let shared = &*self.shared;
+		unsafe { shared.ring.slot(shared.index(seq)).write(value); }
+		shared.flags[shared.index(seq)].store(seq, Ordering::Release);
+		Ok(())
+	}
+
+	/// Copies as many elements from `values` as there is room for and appends them to the front.
+	///
+	/// Elements from other producers are never interleaved.
+	/// Returns the number of elements appended, zero if the consumer has been dropped.
+	#[inline]
+	pub fn push_slice(&mut self, values: &[T]) -> usize where T: Copy {
+		if values.is_empty() || self.is_closed() {
+			return 0;
+		}
+		let (seq, n) = match self.claim(values.len()) {
+			Some(claim) => claim,
+			None => return 0,
+		};
+		let shared = &*self.shared;
+		let mut cur = seq;
+		for value in &values[..n] {
+			unsafe { shared.ring.slot(shared.index(cur)).write(*value); }
+			cur = shared.advance(cur, 1);
+		}
+		let mut cur = seq;
+		for _ in 0..n {
+			shared.flags[shared.index(cur)].store(cur, Ordering::Release);
+			cur = shared.advance(cur, 1);
+		}
+		n
+	}
+
+	// Claims up to `n` free slots, returns the first sequence number and the number claimed
+	fn claim(&self, n: usize) -> Option<(usize, usize)> {
+		let shared = &*self.shared;
+		let mut head = shared.claimed.load(Ordering::Relaxed);
+		loop {
+			// Other producers move the head too, so the tail is not cached
+			let tail = shared.tail.load(Ordering::Acquire);
+			let used = shared.distance(tail, head);
+			if used > shared.capacity() {
+				// The consumer moved past the outdated head
+				head = shared.claimed.load(Ordering::Relaxed);
+				continue;
+			}
+			let free = shared.capacity() - used;
+			if free == 0 {
+				return None;
+			}
+			let n = cmp::min(free, n);
+			match shared.claimed.compare_exchange_weak(head, shared.advance(head, n), Ordering::Relaxed, Ordering::Relaxed) {
+				Ok(_) => return Some((head, n)),
+				Err(current) => head = current,
+			}
+		}
+	}
+}
+
+impl<T> Clone for MpscProducer<T> {
+	fn clone(&self) -> MpscProducer<T> {
+		self.shared.producers.fetch_add(1, Ordering::Relaxed);
+		MpscProducer { shared: self.shared.clone() }
+	}
+}
+
+impl<T> Drop for MpscProducer<T> {
+	fn drop(&mut self) {
+		self.shared.producers.fetch_sub(1, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for MpscProducer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MpscProducer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+/// Consumer half of a multi-producer ring buffer.
+///
+/// This struct is created by the [`split_mpsc`](RingBuffer::split_mpsc) method on ring buffers.
+pub struct MpscConsumer<T> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared tail sequence number.
+	tail: usize,
+}
+
+impl<T> MpscConsumer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// Includes the elements the producers are still writing.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let head = self.shared.claimed.load(Ordering::Acquire);
+		self.shared.distance(self.tail, head)
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if all the producers have been dropped.
+	///
+	/// There may still be elements left to pop.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.producers.load(Ordering::Acquire) == 0
+	}
+
+	/// Removes the element at the tail and returns it.
+	///
+	/// Returns an error if the element at the tail has not been published yet,
+	/// [`PopError::Closed`] if additionally all the producers have been dropped.
+	#[inline]
+	pub fn pop(&mut self) -> Result<T, PopError> {
+		let shared = &*self.shared;
+		let index = shared.index(self.tail);
+		if shared.capacity() == 0 {
+			return Err(if self.is_closed() { PopError::Closed } else { PopError::Empty });
+		}
+		if shared.flags[index].load(Ordering::Acquire) != self.tail {
+			// The producers publish their elements before they are dropped
+			let closed = self.is_closed() && shared.flags[index].load(Ordering::Acquire) != self.tail;
+			return Err(if closed { PopError::Closed } else { PopError::Empty });
+		}
+		let value = unsafe { shared.ring.slot(index).read() };
+		self.tail = shared.advance(self.tail, 1);
+		shared.tail.store(self.tail, Ordering::Release);
+		Ok(value)
+	}
+}
+
+impl<T> Drop for MpscConsumer<T> {
+	fn drop(&mut self) {
+		self.shared.closed.store(true, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for MpscConsumer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MpscConsumer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
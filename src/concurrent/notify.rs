@@ -0,0 +1,177 @@
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+
+use super::sync::{fence, hint, thread, AtomicBool, Mutex, Ordering};
+use super::sync::thread::Thread;
+
+/// Strategy used by the halves of a concurrent ring buffer to wait for the other side.
+///
+/// Spinning reacts fastest but burns a CPU core while waiting.
+/// Parking frees the core at the cost of wake up latency.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WaitStrategy {
+	/// Busy-spin until the other side makes progress.
+	///
+	/// Asynchronous waits wake themselves up immediately and are polled again.
+	Spin,
+	/// Yield the time slice to other threads between checks.
+	///
+	/// Asynchronous waits wake themselves up immediately and are polled again.
+	Yield,
+	/// Spin for a short while, then yield, then park the thread.
+	///
+	/// Asynchronous waits behave like [`Park`](WaitStrategy::Park).
+	Backoff,
+	/// Park the thread (or suspend the task) until the other side wakes it up.
+	Park,
+}
+
+impl Default for WaitStrategy {
+	#[inline]
+	fn default() -> WaitStrategy {
+		WaitStrategy::Park
+	}
+}
+
+// Number of exponentially growing spin rounds before the backoff strategy starts yielding
+const SPIN_LIMIT: u32 = 6;
+// Number of yield rounds before the backoff strategy starts parking
+const YIELD_LIMIT: u32 = 10;
+
+// Wakes up a thread or task waiting on the other half of a concurrent ring buffer.
+//
+// The waiter announces itself before checking its condition a final time.
+// The notifier checks for a waiter after publishing its progress.
+// The SeqCst fences on both sides guarantee that either the waiter sees the progress or the notifier sees the waiter.
+//
+// The waiter registers a threshold along with itself.
+// The notifier passes the level of its progress (elements or free space) and only wakes up the waiter once it reaches the threshold.
+#[derive(Debug, Default)]
+pub(crate) struct Notify {
+	waiting: AtomicBool,
+	waiter: Mutex<Option<(Waiter, usize)>>,
+}
+
+#[derive(Debug)]
+enum Waiter {
+	Thread(Thread),
+	Waker(Waker),
+}
+
+impl Notify {
+	// Blocks the current thread until `ready` returns true or the deadline passes.
+	// Returns the final result of `ready`.
+	pub fn wait_until<F: FnMut() -> bool>(&self, strategy: WaitStrategy, deadline: Option<Instant>, threshold: usize, mut ready: F) -> bool {
+		let mut step = 0;
+		loop {
+			if ready() {
+				return true;
+			}
+			if let Some(deadline) = deadline {
+				if Instant::now() >= deadline {
+					return ready();
+				}
+			}
+			match strategy {
+				WaitStrategy::Spin => hint::spin_loop(),
+				WaitStrategy::Yield => thread::yield_now(),
+				WaitStrategy::Backoff if step < SPIN_LIMIT => {
+					for _ in 0..1 << step {
+						hint::spin_loop();
+					}
+				},
+				WaitStrategy::Backoff if step < SPIN_LIMIT + YIELD_LIMIT => thread::yield_now(),
+				WaitStrategy::Backoff | WaitStrategy::Park => return self.park_until(deadline, threshold, ready),
+			}
+			step += 1;
+		}
+	}
+
+	fn park_until<F: FnMut() -> bool>(&self, deadline: Option<Instant>, threshold: usize, mut ready: F) -> bool {
+		loop {
+			if ready() {
+				return true;
+			}
+			if self.register(Waiter::Thread(thread::current()), threshold, &mut ready) {
+				return true;
+			}
+			match deadline {
+				Some(deadline) => {
+					let now = Instant::now();
+					if now >= deadline {
+						self.cancel();
+						return ready();
+					}
+					thread::park_timeout(deadline - now);
+				},
+				None => thread::park(),
+			}
+			self.cancel();
+		}
+	}
+
+	// Polls `ready`, registering the task to be woken up if it is not ready yet.
+	pub fn poll_ready<F: FnMut() -> bool>(&self, strategy: WaitStrategy, cx: &mut Context, threshold: usize, mut ready: F) -> Poll<()> {
+		if ready() {
+			return Poll::Ready(());
+		}
+		match strategy {
+			WaitStrategy::Spin | WaitStrategy::Yield => {
+				cx.waker().wake_by_ref();
+				Poll::Pending
+			},
+			WaitStrategy::Backoff | WaitStrategy::Park => {
+				if self.register(Waiter::Waker(cx.waker().clone()), threshold, &mut ready) {
+					Poll::Ready(())
+				}
+				else {
+					Poll::Pending
+				}
+			},
+		}
+	}
+
+	// Wakes up the waiter if the level reaches its threshold.
+	// Must be called after the progress has been published.
+	// The level may overestimate but never underestimate the progress.
+	#[inline]
+	pub fn notify(&self, level: usize) {
+		fence(Ordering::SeqCst);
+		if self.waiting.load(Ordering::Relaxed) {
+			self.notify_slow(level);
+		}
+	}
+
+	#[cold]
+	fn notify_slow(&self, level: usize) {
+		let mut waiter = self.waiter.lock().unwrap();
+		if matches!(*waiter, Some((_, threshold)) if level < threshold) {
+			return;
+		}
+		self.waiting.store(false, Ordering::Relaxed);
+		match waiter.take() {
+			Some((Waiter::Thread(thread), _)) => thread.unpark(),
+			Some((Waiter::Waker(waker), _)) => waker.wake(),
+			None => (),
+		}
+	}
+
+	// Registers the waiter and checks `ready` a final time.
+	// Returns true and unregisters the waiter if it became ready in the meantime.
+	fn register<F: FnMut() -> bool>(&self, waiter: Waiter, threshold: usize, ready: &mut F) -> bool {
+		*self.waiter.lock().unwrap() = Some((waiter, threshold));
+		self.waiting.store(true, Ordering::Relaxed);
+		fence(Ordering::SeqCst);
+		if ready() {
+			self.cancel();
+			return true;
+		}
+		false
+	}
+
+	#[inline]
+	fn cancel(&self) {
+		self.waiting.store(false, Ordering::Relaxed);
+		self.waiter.lock().unwrap().take();
+	}
+}
@@ -0,0 +1,241 @@
+use std::{fmt, mem};
+
+use crate::RingBuffer;
+use super::PopError;
+use super::padded::CachePadded;
+use super::ring::Ring;
+use super::sync::{Arc, AtomicBool, AtomicUsize, Ordering};
+
+// State shared between the producer and the consumer.
+//
+// Both sides move the tail.
+// The consumer moves it when it pops an element and the producer when it drops the oldest element to make room.
+// The consumer copies the element out before claiming it and forgets the copy if the producer won the race.
+// The tail is a stream position so it cannot come back to the same value while the consumer is copying.
+struct Shared<T> {
+	ring: Ring<T>,
+	// Position of the next element to be written.
+	head: CachePadded<AtomicUsize>,
+	// Position of the next element to be read.
+	tail: CachePadded<AtomicUsize>,
+	// Number of elements the producer dropped since the consumer last asked.
+	skipped: CachePadded<AtomicUsize>,
+	// Set when either side has been dropped.
+	closed: AtomicBool,
+}
+
+impl<T> Drop for Shared<T> {
+	fn drop(&mut self) {
+		let mut tail = self.tail.load(Ordering::Relaxed);
+		let head = self.head.load(Ordering::Relaxed);
+		while tail != head {
+			unsafe { self.ring.stream_at(tail).drop_in_place(); }
+			tail = self.ring.stream_advance(tail, 1);
+		}
+	}
+}
+
+// An element is only ever owned by one side
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> RingBuffer<T> {
+	/// Splits the ring buffer into a producer and consumer pair which drops the oldest elements instead of filling up.
+	///
+	/// The producer never stalls.
+	/// The consumer is told how many elements it [skipped](OverwriteConsumer::take_skipped).
+	///
+	/// The elements in the ring buffer are handed over to the consumer.
+	/// The capacity is fixed at the moment of splitting, so [`reserve`](Self::reserve) enough beforehand.
+	///
+	/// ```
+	/// let rbuf = vringbuf::RingBuffer::<i32>::with_capacity(16);
+	/// let (mut producer, mut consumer) = rbuf.split_overwrite();
+	/// let cap = producer.capacity() as i32;
+	///
+	/// for i in 0..cap + 2 {
+	/// 	producer.push(i).unwrap();
+	/// }
+	/// assert_eq!(consumer.take_skipped(), 2);
+	/// assert_eq!(consumer.pop(), Ok(2));
+	/// ```
+	pub fn split_overwrite(self) -> (OverwriteProducer<T>, OverwriteConsumer<T>) {
+		let (ring, tail, head) = Ring::from_ring_buffer(self);
+		let shared = Arc::new(Shared {
+			ring,
+			head: CachePadded(AtomicUsize::new(head)),
+			tail: CachePadded(AtomicUsize::new(tail)),
+			skipped: CachePadded(AtomicUsize::new(0)),
+			closed: AtomicBool::new(false),
+		});
+		let producer = OverwriteProducer { shared: shared.clone(), head };
+		let consumer = OverwriteConsumer { shared };
+		(producer, consumer)
+	}
+}
+
+/// Producer half of an overwriting ring buffer.
+///
+/// This struct is created by the [`split_overwrite`](RingBuffer::split_overwrite) method on ring buffers.
+pub struct OverwriteProducer<T> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared head position.
+	head: usize,
+}
+
+impl<T> OverwriteProducer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let tail = self.shared.tail.load(Ordering::Acquire);
+		self.shared.ring.stream_distance(tail, self.head) / mem::size_of::<T>()
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the consumer has been dropped.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Appends an element to the front, dropping the oldest element if the ring buffer is full.
+	///
+	/// Without any capacity the element itself is dropped and counted as skipped.
+	///
+	/// Returns the element back if the consumer has been dropped.
+	pub fn push(&mut self, value: T) -> Result<(), T> {
+		if self.is_closed() {
+			return Err(value);
+		}
+		let shared = &*self.shared;
+		if self.capacity() == 0 {
+			shared.skipped.fetch_add(1, Ordering::Relaxed);
+			return Ok(());
+		}
+		let mut tail = shared.tail.load(Ordering::Acquire);
+		while shared.ring.stream_distance(tail, self.head) / mem::size_of::<T>() == self.capacity() {
+			// Race the consumer for the oldest element
+			let next = shared.ring.stream_advance(tail, 1);
+			match shared.tail.compare_exchange(tail, next, Ordering::AcqRel, Ordering::Acquire) {
+				Ok(_) => {
+					unsafe { shared.ring.stream_at(tail).drop_in_place(); }
+					shared.skipped.fetch_add(1, Ordering::Relaxed);
+					break;
+				},
+				Err(current) => tail = current,
+			}
+		}
+		unsafe { shared.ring.stream_at(self.head).write(value); }
+		self.head = shared.ring.stream_advance(self.head, 1);
+		shared.head.store(self.head, Ordering::Release);
+		Ok(())
+	}
+}
+
+impl<T> Drop for OverwriteProducer<T> {
+	fn drop(&mut self) {
+		self.shared.closed.store(true, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for OverwriteProducer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("OverwriteProducer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+/// Consumer half of an overwriting ring buffer.
+///
+/// This struct is created by the [`split_overwrite`](RingBuffer::split_overwrite) method on ring buffers.
+pub struct OverwriteConsumer<T> {
+	shared: Arc<Shared<T>>,
+}
+
+impl<T> OverwriteConsumer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let tail = self.shared.tail.load(Ordering::Acquire);
+		let head = self.shared.head.load(Ordering::Acquire);
+		self.shared.ring.stream_distance(tail, head) / mem::size_of::<T>()
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the producer has been dropped.
+	///
+	/// There may still be elements left to pop.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Returns the number of elements the producer dropped since the last call and resets the count.
+	#[inline]
+	pub fn take_skipped(&mut self) -> usize {
+		self.shared.skipped.swap(0, Ordering::Relaxed)
+	}
+
+	/// Removes the element at the tail and returns it.
+	///
+	/// Returns an error if the ring buffer is empty.
+	/// The error is [`PopError::Closed`] if the producer has also been dropped.
+	pub fn pop(&mut self) -> Result<T, PopError> {
+		let shared = &*self.shared;
+		let mut tail = shared.tail.load(Ordering::Acquire);
+		loop {
+			let head = shared.head.load(Ordering::Acquire);
+			if tail == head {
+				// The producer publishes its last elements before closing
+				let closed = self.is_closed() && shared.head.load(Ordering::Acquire) == tail;
+				return Err(if closed { PopError::Closed } else { PopError::Empty });
+			}
+			// The producer may be overwriting the element while it is copied
+			let value = unsafe { (shared.ring.stream_at(tail) as *const mem::MaybeUninit<T>).read_volatile() };
+			let next = shared.ring.stream_advance(tail, 1);
+			match shared.tail.compare_exchange(tail, next, Ordering::AcqRel, Ordering::Acquire) {
+				Ok(_) => return Ok(unsafe { value.assume_init() }),
+				Err(current) => tail = current,
+			}
+		}
+	}
+}
+
+impl<T> Drop for OverwriteConsumer<T> {
+	fn drop(&mut self) {
+		self.shared.closed.store(true, Ordering::Release);
+	}
+}
+
+impl<T> fmt::Debug for OverwriteConsumer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("OverwriteConsumer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
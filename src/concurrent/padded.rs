@@ -0,0 +1,23 @@
+use std::ops;
+
+// Aligns the value to the cache line size to avoid false sharing.
+//
+// Modern x86_64 and aarch64 processors prefetch cache lines in pairs, use 128 bytes there.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64")), repr(align(64)))]
+#[derive(Debug, Default)]
+pub(crate) struct CachePadded<T>(pub T);
+
+impl<T> ops::Deref for CachePadded<T> {
+	type Target = T;
+	#[inline]
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+impl<T> ops::DerefMut for CachePadded<T> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.0
+	}
+}
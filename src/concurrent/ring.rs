@@ -0,0 +1,102 @@
+use std::mem;
+use std::ptr::NonNull;
+
+use crate::{platform, MirroredMemory, OsMemory, RingBuffer};
+
+// Mirrored mapping shared by the halves of the concurrent ring buffers.
+//
+// Positions in the ring are byte offsets modulo twice the capacity.
+// Keeping them modulo `2 * cap` instead of `cap` distinguishes a full ring buffer from an empty one,
+// the number of elements is the distance between two offsets divided by the element size.
+//
+// The variants where more than one thread moves the same offset use stream positions instead,
+// byte offsets wrapped around at a large multiple of the capacity so they practically never repeat.
+//
+// Dropping the ring frees the mapping but not the elements in it.
+pub(super) struct Ring<T> {
+	ptr: NonNull<T>,
+	// Capacity of the ring buffer in bytes.
+	cap: usize,
+	// Stream position at which the stream wraps around.
+	wrap: usize,
+	handle: Option<platform::Handle>,
+	// Backend which allocated the mapping.
+	memory: OsMemory,
+}
+
+impl<T> Ring<T> {
+	// Takes over the mapping of the ring buffer along with the tail and head offsets of its elements
+	pub fn from_ring_buffer(rbuf: RingBuffer<T>) -> (Ring<T>, usize, usize) {
+		let mut rbuf = mem::ManuallyDrop::new(rbuf);
+		// The halves access all of the capacity
+		rbuf.protect(rbuf.reserved_len());
+		let memory = mem::take(&mut rbuf.memory);
+		let tail = rbuf.base;
+		let head = rbuf.base + rbuf.len * mem::size_of::<T>();
+		let wrap = usize::MAX.checked_div(rbuf.cap).map_or(0, |laps| laps * rbuf.cap);
+		(Ring { ptr: rbuf.ptr, cap: rbuf.cap, wrap, handle: rbuf.handle, memory }, tail, head)
+	}
+	// Capacity in bytes
+	#[inline]
+	pub fn bytes(&self) -> usize {
+		self.cap
+	}
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.cap / mem::size_of::<T>()
+	}
+	// Number of elements between the tail and head offsets
+	#[inline]
+	pub fn distance(&self, tail: usize, head: usize) -> usize {
+		let bytes = if head >= tail { head - tail } else { head + 2 * self.cap - tail };
+		bytes / mem::size_of::<T>()
+	}
+	// Advances an offset by `n` elements
+	#[inline]
+	pub fn advance(&self, offset: usize, n: usize) -> usize {
+		let offset = offset + n * mem::size_of::<T>();
+		if offset >= 2 * self.cap { offset - 2 * self.cap } else { offset }
+	}
+	// Number of bytes between the tail and head stream positions
+	#[inline]
+	pub fn stream_distance(&self, tail: usize, head: usize) -> usize {
+		if head >= tail { head - tail } else { head + (self.wrap - tail) }
+	}
+	// Advances a stream position by `n` elements
+	#[inline]
+	pub fn stream_advance(&self, pos: usize, n: usize) -> usize {
+		let bytes = n * mem::size_of::<T>();
+		if bytes >= self.wrap - pos { bytes - (self.wrap - pos) } else { pos + bytes }
+	}
+	// Moves a stream position back by `n` elements
+	#[inline]
+	pub fn stream_retreat(&self, pos: usize, n: usize) -> usize {
+		let bytes = n * mem::size_of::<T>();
+		if bytes > pos { pos + (self.wrap - bytes) } else { pos - bytes }
+	}
+	// Pointer to the element at the stream position
+	#[inline]
+	pub fn stream_at(&self, pos: usize) -> *mut T {
+		self.at(pos % self.cap)
+	}
+	// Pointer to the element at the index of a fixed grid of `capacity` elements
+	#[inline]
+	pub fn slot(&self, index: usize) -> *mut T {
+		debug_assert!(index < self.capacity());
+		unsafe { self.ptr.as_ptr().add(index) }
+	}
+	// Pointer to the element at the offset
+	#[inline]
+	pub fn at(&self, offset: usize) -> *mut T {
+		let offset = if offset >= self.cap { offset - self.cap } else { offset };
+		unsafe { (self.ptr.as_ptr() as *mut u8).add(offset) as *mut T }
+	}
+}
+
+impl<T> Drop for Ring<T> {
+	fn drop(&mut self) {
+		if let Some(handle) = self.handle {
+			unsafe { self.memory.free(self.ptr.cast(), self.cap, handle); }
+		}
+	}
+}
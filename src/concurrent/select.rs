@@ -0,0 +1,201 @@
+use std::{fmt, future, thread};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use super::{Consumer, PopError};
+
+/// Waits on several consumers at once.
+///
+/// Pops from whichever consumer has elements available.
+/// The consumers take turns so a busy consumer cannot starve the others.
+/// The popped elements are returned along with the index of their consumer.
+///
+/// ```
+/// use vringbuf::{RingBuffer, concurrent::Select};
+///
+/// let (mut first, consumer) = RingBuffer::<i32>::with_capacity(16).split();
+/// let mut select = Select::new();
+/// select.push(consumer);
+/// let (mut second, consumer) = RingBuffer::<i32>::with_capacity(16).split();
+/// select.push(consumer);
+///
+/// second.push(2).unwrap();
+/// assert_eq!(select.pop_blocking(), Some((1, 2)));
+/// first.push(1).unwrap();
+/// drop((first, second));
+/// assert_eq!(select.pop_blocking(), Some((0, 1)));
+/// assert_eq!(select.pop_blocking(), None);
+/// ```
+pub struct Select<T> {
+	consumers: Vec<Consumer<T>>,
+	// Index of the consumer to try first.
+	next: usize,
+}
+
+impl<T> Select<T> {
+	/// Creates a new select without any consumers.
+	#[inline]
+	pub fn new() -> Select<T> {
+		Select { consumers: Vec::new(), next: 0 }
+	}
+
+	/// Adds a consumer and returns its index.
+	#[inline]
+	pub fn push(&mut self, consumer: Consumer<T>) -> usize {
+		self.consumers.push(consumer);
+		self.consumers.len() - 1
+	}
+
+	/// Removes the consumer at the index and returns it.
+	///
+	/// The consumers after it shift down by one.
+	///
+	/// # Panics
+	///
+	/// Panics if the index is out of bounds.
+	#[inline]
+	pub fn remove(&mut self, index: usize) -> Consumer<T> {
+		self.next = 0;
+		self.consumers.remove(index)
+	}
+
+	/// Returns the number of consumers.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.consumers.len()
+	}
+
+	/// Returns `true` if there are no consumers.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.consumers.is_empty()
+	}
+
+	/// Returns the consumers.
+	#[inline]
+	pub fn consumers(&self) -> &[Consumer<T>] {
+		&self.consumers
+	}
+
+	/// Returns the consumers mutably.
+	#[inline]
+	pub fn consumers_mut(&mut self) -> &mut [Consumer<T>] {
+		&mut self.consumers
+	}
+
+	/// Returns the consumers.
+	#[inline]
+	pub fn into_inner(self) -> Vec<Consumer<T>> {
+		self.consumers
+	}
+
+	/// Removes an element from the next consumer with elements available.
+	///
+	/// Returns an error if all the consumers are empty.
+	/// The error is [`PopError::Closed`] if all their producers have also been dropped.
+	pub fn pop(&mut self) -> Result<(usize, T), PopError> {
+		let len = self.consumers.len();
+		let mut result = Err(PopError::Closed);
+		for i in 0..len {
+			let index = (self.next + i) % len;
+			match self.consumers[index].pop() {
+				Ok(value) => {
+					self.next = (index + 1) % len;
+					return Ok((index, value));
+				},
+				Err(PopError::Empty) => result = Err(PopError::Empty),
+				Err(PopError::Closed) => (),
+			}
+		}
+		result
+	}
+
+	/// Removes an element from the next consumer with elements available, blocking the current thread while all the consumers are empty.
+	///
+	/// Returns [`None`] if all the consumers are empty and all their producers have been dropped.
+	pub fn pop_blocking(&mut self) -> Option<(usize, T)> {
+		let waker = Waker::from(Arc::new(Unparker(thread::current())));
+		let mut cx = Context::from_waker(&waker);
+		loop {
+			match self.poll_pop(&mut cx) {
+				Poll::Ready(result) => return result,
+				Poll::Pending => thread::park(),
+			}
+		}
+	}
+
+	/// Removes an element from the next consumer with elements available, waiting asynchronously while all the consumers are empty.
+	///
+	/// Returns [`None`] if all the consumers are empty and all their producers have been dropped.
+	pub async fn recv(&mut self) -> Option<(usize, T)> {
+		future::poll_fn(|cx| self.poll_pop(cx)).await
+	}
+
+	/// Polls for an element, registering the task to be woken up when any of the producers appends elements.
+	///
+	/// Returns [`Poll::Ready`] with [`None`] if all the consumers are empty and all their producers have been dropped.
+	pub fn poll_pop(&mut self, cx: &mut Context) -> Poll<Option<(usize, T)>> {
+		loop {
+			match self.pop() {
+				Ok(item) => return Poll::Ready(Some(item)),
+				Err(PopError::Closed) => return Poll::Ready(None),
+				Err(PopError::Empty) => (),
+			}
+			// Register the task with the consumers which may still receive elements
+			let mut open = false;
+			let mut pending = true;
+			for consumer in &mut self.consumers {
+				if consumer.is_exhausted() {
+					continue;
+				}
+				open = true;
+				// Retry if a consumer became ready while registering the task
+				if consumer.poll_read_ready(cx).is_ready() {
+					pending = false;
+					break;
+				}
+			}
+			// Also retry if the last open consumer was closed meanwhile
+			if open && pending {
+				return Poll::Pending;
+			}
+		}
+	}
+}
+
+impl<T> Default for Select<T> {
+	#[inline]
+	fn default() -> Select<T> {
+		Select::new()
+	}
+}
+
+impl<T> From<Vec<Consumer<T>>> for Select<T> {
+	#[inline]
+	fn from(consumers: Vec<Consumer<T>>) -> Select<T> {
+		Select { consumers, next: 0 }
+	}
+}
+
+impl<T> fmt::Debug for Select<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Select")
+			.field("consumers", &self.consumers)
+			.finish()
+	}
+}
+
+// Wakes up the thread blocked in `pop_blocking`.
+struct Unparker(thread::Thread);
+
+impl Wake for Unparker {
+	#[inline]
+	fn wake(self: Arc<Self>) {
+		self.0.unpark();
+	}
+
+	#[inline]
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.0.unpark();
+	}
+}
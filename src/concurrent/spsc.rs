@@ -0,0 +1,717 @@
+use std::{cmp, fmt, future, mem, ptr, slice};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::RingBuffer;
+use super::{PopError, PushError};
+use super::notify::{Notify, WaitStrategy};
+use super::padded::CachePadded;
+use super::ring::Ring;
+use super::sync::{Arc, AtomicBool, AtomicUsize, Mutex, Ordering};
+
+// State shared between the producer and the consumer.
+//
+// Each side keeps a private copy of its own offset and a cached copy of the other side's offset,
+// the shared atomics are only touched when publishing progress or when the cached copy runs out.
+// Every field written by one side and read by the other is on its own cache line to avoid false sharing.
+//
+// When the producer grows the ring buffer it links a larger ring buffer after this one and moves on to it,
+// the consumer follows once it has drained this one.
+struct Shared<T> {
+	ring: Ring<T>,
+	// Offset of the next element to be written.
+	head: CachePadded<AtomicUsize>,
+	// Offset of the next element to be read.
+	tail: CachePadded<AtomicUsize>,
+	// Wakes up the producer waiting for free space.
+	producer_notify: CachePadded<Notify>,
+	// Wakes up the consumer waiting for elements.
+	consumer_notify: CachePadded<Notify>,
+	// Set when either side has been dropped.
+	closed: CachePadded<AtomicBool>,
+	// Set once the producer has moved on to the next ring buffer and published its last element in this one.
+	moved: AtomicBool,
+	// Ring buffer the producer moved on to.
+	next: Mutex<Option<Arc<Shared<T>>>>,
+}
+
+impl<T> Shared<T> {
+	fn new(ring: Ring<T>, tail: usize, head: usize) -> Shared<T> {
+		Shared {
+			ring,
+			head: CachePadded(AtomicUsize::new(head)),
+			tail: CachePadded(AtomicUsize::new(tail)),
+			producer_notify: CachePadded::default(),
+			consumer_notify: CachePadded::default(),
+			closed: CachePadded::default(),
+			moved: AtomicBool::new(false),
+			next: Mutex::new(None),
+		}
+	}
+}
+
+impl<T> Drop for Shared<T> {
+	fn drop(&mut self) {
+		// The last reference synchronized with both halves when it was dropped
+		let tail = self.tail.load(Ordering::Relaxed);
+		let head = self.head.load(Ordering::Relaxed);
+		let len = self.ring.distance(tail, head);
+		unsafe { ptr::slice_from_raw_parts_mut(self.ring.at(tail), len).drop_in_place(); }
+	}
+}
+
+// The producer and consumer never access the same elements at the same time
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> RingBuffer<T> {
+	/// Splits the ring buffer into a producer and consumer pair for single-producer single-consumer use across threads.
+	///
+	/// The elements in the ring buffer are handed over to the consumer.
+	/// The producer can [`grow`](Producer::grow) the capacity later on without stopping the consumer.
+	pub fn split(self) -> (Producer<T>, Consumer<T>) {
+		let (ring, tail, head) = Ring::from_ring_buffer(self);
+		let low_watermark = ring.capacity().saturating_sub(1);
+		let shared = Arc::new(Shared::new(ring, tail, head));
+		let producer = Producer { shared: shared.clone(), head, cached_tail: tail, wait: WaitStrategy::Park, low_watermark };
+		let consumer = Consumer { shared, tail, cached_head: head, wait: WaitStrategy::Park, high_watermark: 1 };
+		(producer, consumer)
+	}
+}
+
+/// Producer half of a split ring buffer.
+///
+/// This struct is created by the [`split`](RingBuffer::split) method on ring buffers.
+pub struct Producer<T> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared head offset.
+	head: usize,
+	// Last observed tail offset.
+	cached_tail: usize,
+	wait: WaitStrategy,
+	// Length the consumer must drain the ring buffer to before a waiting push is woken up.
+	low_watermark: usize,
+}
+
+impl<T> Producer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// The consumer may remove elements concurrently, so the returned length is an upper bound.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let tail = self.shared.tail.load(Ordering::Acquire);
+		self.shared.ring.distance(tail, self.head)
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the ring buffer has no room for more elements.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		self.len() == self.capacity()
+	}
+
+	/// Returns the number of elements which can be pushed without the ring buffer being full.
+	#[inline]
+	pub fn free_len(&self) -> usize {
+		self.capacity() - self.len()
+	}
+
+	/// Returns `true` if the consumer has been dropped.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Returns the strategy used to wait for free space.
+	#[inline]
+	pub fn wait_strategy(&self) -> WaitStrategy {
+		self.wait
+	}
+
+	/// Sets the strategy used to wait for free space in the blocking and asynchronous methods.
+	///
+	/// The default is [`WaitStrategy::Park`].
+	#[inline]
+	pub fn set_wait_strategy(&mut self, strategy: WaitStrategy) {
+		self.wait = strategy;
+	}
+
+	/// Returns the length the ring buffer must drop to before a push waiting on a full ring buffer is woken up.
+	#[inline]
+	pub fn low_watermark(&self) -> usize {
+		self.low_watermark
+	}
+
+	/// Sets the length the ring buffer must drop to before a push waiting on a full ring buffer is woken up.
+	///
+	/// Lets the consumer drain a batch of elements before the producer is woken up.
+	/// The watermark is clamped below the capacity.
+	/// The default wakes up the producer as soon as there is room for one element.
+	#[inline]
+	pub fn set_low_watermark(&mut self, len: usize) {
+		self.low_watermark = cmp::min(len, self.capacity().saturating_sub(1));
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the ring buffer is full or the consumer has been dropped.
+	#[inline]
+	pub fn push(&mut self, value: T) -> Result<(), PushError<T>> {
+		if self.is_closed() {
+			return Err(PushError::Closed(value));
+		}
+		if self.available(1) == 0 {
+			return Err(PushError::Full(value));
+		}
+		unsafe { self.shared.ring.at(self.head).write(value); }
+		self.publish(1);
+		Ok(())
+	}
+
+	/// Copies as many elements from `values` as there is room for and appends them to the front.
+	///
+	/// Returns the number of elements appended, zero if the consumer has been dropped.
+	#[inline]
+	pub fn push_slice(&mut self, values: &[T]) -> usize where T: Copy {
+		unsafe { self.push_slice_uninit(&*(values as *const [T] as *const [mem::MaybeUninit<T>])) }
+	}
+
+	/// Moves as many elements from `values` as there is room for and appends them to the front.
+	///
+	/// Returns the number of elements `n` appended, zero if the consumer has been dropped.
+	///
+	/// # Safety
+	///
+	/// * The first `n` elements of `values` must be initialized.
+	/// * The first `n` elements must not be used or dropped afterwards.
+	#[inline]
+	pub unsafe fn push_slice_uninit(&mut self, values: &[mem::MaybeUninit<T>]) -> usize {
+		let n = cmp::min(self.available(values.len()), values.len());
+		if n == 0 || self.is_closed() {
+			return 0;
+		}
+		(values.as_ptr() as *const T).copy_to_nonoverlapping(self.shared.ring.at(self.head), n);
+		self.publish(n);
+		n
+	}
+
+	/// Returns a write grant over exactly `n` free elements after the front.
+	///
+	/// The elements can be written in place and are appended once they are [committed](Producer::commit).
+	/// Returns [`None`] if fewer than `n` elements are free or the consumer has been dropped.
+	#[inline]
+	pub fn grant(&mut self, n: usize) -> Option<&mut [mem::MaybeUninit<T>]> {
+		if self.available(n) < n || self.is_closed() {
+			return None;
+		}
+		Some(unsafe { slice::from_raw_parts_mut(self.shared.ring.at(self.head) as *mut mem::MaybeUninit<T>, n) })
+	}
+
+	/// Appends the first `n` elements of the last write grant to the front.
+	///
+	/// # Safety
+	///
+	/// The first `n` elements after the front must have been initialized through a [write grant](Producer::grant).
+	///
+	/// # Panics
+	///
+	/// Panics if fewer than `n` elements are free.
+	#[inline]
+	pub unsafe fn commit(&mut self, n: usize) {
+		assert!(n <= self.available(n), "commit exceeds the free space");
+		self.publish(n);
+	}
+
+	/// Moves the producer on to a new ring buffer with room for at least `capacity` elements.
+	///
+	/// The consumer drains the elements already pushed before it follows the producer.
+	/// Until then [`len`](Producer::len) only counts the elements in the new ring buffer.
+	/// Does nothing if the ring buffer can already hold `capacity` elements or the consumer has been dropped.
+	///
+	/// # Panics
+	///
+	/// Panics if the new ring buffer fails to allocate.
+	pub fn grow(&mut self, capacity: usize) {
+		if capacity <= self.capacity() || self.is_closed() {
+			return;
+		}
+		let (ring, tail, head) = Ring::from_ring_buffer(RingBuffer::with_capacity(capacity));
+		let next = Arc::new(Shared::new(ring, tail, head));
+		let prev = mem::replace(&mut self.shared, next.clone());
+		self.head = head;
+		self.cached_tail = tail;
+		let mut slot = prev.next.lock().unwrap();
+		// The consumer closes the ring buffers under the lock
+		if prev.closed.load(Ordering::Acquire) {
+			next.closed.store(true, Ordering::Release);
+		}
+		*slot = Some(next);
+		drop(slot);
+		prev.moved.store(true, Ordering::Release);
+		prev.consumer_notify.notify(usize::MAX);
+	}
+
+	/// Appends an element to the front, blocking the current thread while the ring buffer is full.
+	///
+	/// Returns the element back if the consumer has been dropped.
+	#[inline]
+	pub fn push_blocking(&mut self, value: T) -> Result<(), T> {
+		if !self.is_writable() {
+			self.shared.producer_notify.wait_until(self.wait, None, self.wake_free(), || self.is_drained());
+		}
+		self.push(value).map_err(PushError::into_inner)
+	}
+
+	/// Appends an element to the front, blocking the current thread for at most `timeout` while the ring buffer is full.
+	///
+	/// Returns the element back if the ring buffer is still full after the timeout or the consumer has been dropped.
+	#[inline]
+	pub fn push_timeout(&mut self, value: T, timeout: Duration) -> Result<(), PushError<T>> {
+		if !self.is_writable() {
+			let deadline = Instant::now() + timeout;
+			self.shared.producer_notify.wait_until(self.wait, Some(deadline), self.wake_free(), || self.is_drained());
+		}
+		self.push(value)
+	}
+
+	/// Appends an element to the front, waiting asynchronously while the ring buffer is full.
+	///
+	/// Returns the element back if the consumer has been dropped.
+	pub async fn send(&mut self, value: T) -> Result<(), T> {
+		future::poll_fn(|cx| self.poll_write_ready(cx)).await;
+		self.push(value).map_err(PushError::into_inner)
+	}
+
+	/// Polls for free space, registering the task to be woken up when the consumer removes elements.
+	///
+	/// Returns [`Poll::Ready`] once a push would not fail with [`PushError::Full`].
+	/// Useful to write futures by hand or to wait for free space without creating a future every time.
+	#[inline]
+	pub fn poll_write_ready(&self, cx: &mut Context) -> Poll<()> {
+		if self.is_writable() {
+			return Poll::Ready(());
+		}
+		self.shared.producer_notify.poll_ready(self.wait, cx, self.wake_free(), || self.is_drained())
+	}
+
+	// Returns true if a push would not fail with `PushError::Full`.
+	#[inline]
+	fn is_writable(&self) -> bool {
+		self.free_len() > 0 || self.is_closed()
+	}
+
+	// Returns true if a waiting push should be woken up.
+	#[inline]
+	fn is_drained(&self) -> bool {
+		self.len() <= self.low_watermark || self.is_closed()
+	}
+
+	// Free space at which the consumer wakes up a waiting push.
+	#[inline]
+	fn wake_free(&self) -> usize {
+		self.capacity() - self.low_watermark
+	}
+
+	// Number of free elements, reloads the tail offset only if fewer than `n` are known to be free
+	#[inline]
+	fn available(&mut self, n: usize) -> usize {
+		let free = self.capacity() - self.shared.ring.distance(self.cached_tail, self.head);
+		if free >= n {
+			return free;
+		}
+		self.cached_tail = self.shared.tail.load(Ordering::Acquire);
+		self.capacity() - self.shared.ring.distance(self.cached_tail, self.head)
+	}
+
+	// Publishes `n` elements written after the head.
+	#[inline]
+	fn publish(&mut self, n: usize) {
+		self.head = self.shared.ring.advance(self.head, n);
+		self.shared.head.store(self.head, Ordering::Release);
+		// The cached tail lags behind the consumer, so this overestimates the length
+		self.shared.consumer_notify.notify(self.shared.ring.distance(self.cached_tail, self.head));
+	}
+
+	// Closes the ring buffer and wakes up the consumer to drain the remaining elements.
+	#[inline]
+	pub(super) fn close(&self) {
+		self.shared.closed.store(true, Ordering::Release);
+		self.shared.consumer_notify.notify(usize::MAX);
+	}
+}
+
+impl<T> Drop for Producer<T> {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
+
+impl<T> fmt::Debug for Producer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Producer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+/// Consumer half of a split ring buffer.
+///
+/// This struct is created by the [`split`](RingBuffer::split) method on ring buffers.
+pub struct Consumer<T> {
+	shared: Arc<Shared<T>>,
+	// Private copy of the shared tail offset.
+	tail: usize,
+	// Last observed head offset.
+	cached_head: usize,
+	wait: WaitStrategy,
+	// Length the producer must fill the ring buffer to before a waiting pop is woken up.
+	high_watermark: usize,
+}
+
+impl<T> Consumer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.shared.ring.capacity()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// The producer may append elements concurrently, so the returned length is a lower bound.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let head = self.shared.head.load(Ordering::Acquire);
+		self.shared.ring.distance(self.tail, head)
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the producer has been dropped.
+	///
+	/// There may still be elements left to pop.
+	#[inline]
+	pub fn is_closed(&self) -> bool {
+		self.shared.closed.load(Ordering::Acquire)
+	}
+
+	/// Returns the strategy used to wait for elements.
+	#[inline]
+	pub fn wait_strategy(&self) -> WaitStrategy {
+		self.wait
+	}
+
+	/// Sets the strategy used to wait for elements in the blocking and asynchronous methods.
+	///
+	/// The default is [`WaitStrategy::Park`].
+	#[inline]
+	pub fn set_wait_strategy(&mut self, strategy: WaitStrategy) {
+		self.wait = strategy;
+	}
+
+	/// Returns the length the ring buffer must reach before a pop waiting on an empty ring buffer is woken up.
+	#[inline]
+	pub fn high_watermark(&self) -> usize {
+		self.high_watermark
+	}
+
+	/// Sets the length the ring buffer must reach before a pop waiting on an empty ring buffer is woken up.
+	///
+	/// Lets the producer append a batch of elements before the consumer is woken up.
+	/// The watermark is clamped to the capacity.
+	/// The default of `1` wakes up the consumer as soon as an element is available.
+	/// A waiting pop is always woken up when the producer is dropped.
+	#[inline]
+	pub fn set_high_watermark(&mut self, len: usize) {
+		self.high_watermark = cmp::max(1, cmp::min(len, self.capacity()));
+	}
+
+	/// Extracts a slice containing the elements available to the consumer.
+	#[inline]
+	pub fn as_slice(&self) -> &[T] {
+		unsafe { slice::from_raw_parts(self.shared.ring.at(self.tail), self.len()) }
+	}
+
+	/// Extracts a mutable slice containing the elements available to the consumer.
+	#[inline]
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		unsafe { slice::from_raw_parts_mut(self.shared.ring.at(self.tail), self.len()) }
+	}
+
+	/// Removes the element at the tail and returns it.
+	///
+	/// Returns an error if the ring buffer is empty.
+	/// The error is [`PopError::Closed`] if the producer has also been dropped.
+	#[inline]
+	pub fn pop(&mut self) -> Result<T, PopError> {
+		if self.available(1) == 0 {
+			return Err(self.empty_error());
+		}
+		let value = unsafe { self.shared.ring.at(self.tail).read() };
+		self.retire(1);
+		Ok(value)
+	}
+
+	/// Copies as many elements as are available into `dst` and removes them from the tail.
+	///
+	/// Returns the number of elements removed.
+	#[inline]
+	pub fn pop_slice(&mut self, dst: &mut [T]) -> usize where T: Copy {
+		unsafe { self.pop_slice_uninit(&mut *(dst as *mut [T] as *mut [mem::MaybeUninit<T>])) }
+	}
+
+	/// Moves as many elements as are available into `dst` and removes them from the tail.
+	///
+	/// Returns the number of elements `n` removed.
+	/// The first `n` elements of `dst` are initialized.
+	#[inline]
+	pub fn pop_slice_uninit(&mut self, dst: &mut [mem::MaybeUninit<T>]) -> usize {
+		let n = cmp::min(self.available(dst.len()), dst.len());
+		if n == 0 {
+			return 0;
+		}
+		unsafe { self.shared.ring.at(self.tail).copy_to_nonoverlapping(dst.as_mut_ptr() as *mut T, n); }
+		self.retire(n);
+		n
+	}
+
+	/// Returns a read grant over all the elements available to the consumer.
+	///
+	/// The elements can be inspected in place and are removed once they are [released](Consumer::release).
+	#[inline]
+	pub fn read(&mut self) -> &[T] {
+		let len = self.available(usize::MAX);
+		unsafe { slice::from_raw_parts(self.shared.ring.at(self.tail), len) }
+	}
+
+	/// Drops the first `n` elements of the read grant and hands their memory back to the producer.
+	///
+	/// # Panics
+	///
+	/// Panics if fewer than `n` elements are available.
+	#[inline]
+	pub fn release(&mut self, n: usize) {
+		assert!(n <= self.available(n), "release exceeds the available elements");
+		self.remove_tail(n);
+	}
+
+	/// Returns an iterator which pops the elements that are available without blocking.
+	///
+	/// The iterator ends as soon as the ring buffer is empty, even if the producer has not been dropped yet.
+	#[inline]
+	pub fn try_iter(&mut self) -> TryIter<'_, T> {
+		TryIter { consumer: self }
+	}
+
+	/// Moves all the elements that are currently available to the end of `dst`.
+	///
+	/// Returns the number of elements moved.
+	#[inline]
+	pub fn drain_available(&mut self, dst: &mut Vec<T>) -> usize {
+		let len = self.available(usize::MAX);
+		dst.reserve(len);
+		let n = self.pop_slice_uninit(&mut dst.spare_capacity_mut()[..len]);
+		unsafe { dst.set_len(dst.len() + n); }
+		n
+	}
+
+	/// Removes the element at the tail and returns it, blocking the current thread while the ring buffer is empty.
+	///
+	/// Returns [`None`] if the ring buffer is empty and the producer has been dropped.
+	#[inline]
+	pub fn pop_blocking(&mut self) -> Option<T> {
+		loop {
+			match self.pop() {
+				Ok(value) => return Some(value),
+				Err(PopError::Closed) => return None,
+				Err(PopError::Empty) => {
+					self.shared.consumer_notify.wait_until(self.wait, None, self.high_watermark, || self.is_filled());
+				},
+			}
+		}
+	}
+
+	/// Removes the element at the tail and returns it, blocking the current thread for at most `timeout` while the ring buffer is empty.
+	///
+	/// Returns an error if the ring buffer is still empty after the timeout or the producer has been dropped.
+	#[inline]
+	pub fn pop_timeout(&mut self, timeout: Duration) -> Result<T, PopError> {
+		let deadline = Instant::now() + timeout;
+		loop {
+			match self.pop() {
+				Err(PopError::Empty) if Instant::now() < deadline => {
+					self.shared.consumer_notify.wait_until(self.wait, Some(deadline), self.high_watermark, || self.is_filled());
+				},
+				result => return result,
+			}
+		}
+	}
+
+	/// Removes the element at the tail and returns it, waiting asynchronously while the ring buffer is empty.
+	///
+	/// Returns [`None`] if the ring buffer is empty and the producer has been dropped.
+	pub async fn recv(&mut self) -> Option<T> {
+		future::poll_fn(|cx| self.poll_read_ready(cx)).await;
+		self.pop().ok()
+	}
+
+	/// Polls for elements, registering the task to be woken up when the producer appends elements.
+	///
+	/// Returns [`Poll::Ready`] once a pop would not fail with [`PopError::Empty`].
+	/// Useful to write futures by hand or to wait for elements without creating a future every time.
+	#[inline]
+	pub fn poll_read_ready(&mut self, cx: &mut Context) -> Poll<()> {
+		loop {
+			if self.available(1) > 0 || self.is_closed() {
+				return Poll::Ready(());
+			}
+			// Ready because the producer moved on, follow it and poll again
+			if self.shared.consumer_notify.poll_ready(self.wait, cx, self.high_watermark, || self.is_filled()).is_pending() {
+				return Poll::Pending;
+			}
+		}
+	}
+
+	// Returns true if a waiting pop should be woken up.
+	#[inline]
+	fn is_filled(&self) -> bool {
+		self.len() >= self.high_watermark || self.is_closed() || self.shared.moved.load(Ordering::Acquire)
+	}
+
+	// Returns true if the producer has been dropped and all its elements have been popped.
+	#[inline]
+	pub(super) fn is_exhausted(&mut self) -> bool {
+		// The producer publishes its last elements before closing
+		self.is_closed() && self.available(1) == 0
+	}
+
+	// Distinguishes an empty ring buffer from a closed one.
+	#[cold]
+	fn empty_error(&mut self) -> PopError {
+		if self.is_exhausted() {
+			PopError::Closed
+		}
+		else {
+			PopError::Empty
+		}
+	}
+
+	/// Removes `n` elements from the tail.
+	pub fn remove_tail(&mut self, n: usize) {
+		// Keep the method safe by removing max of `len` elements
+		let n = cmp::min(self.available(n), n);
+		let s = ptr::slice_from_raw_parts_mut(self.shared.ring.at(self.tail), n);
+		// Hand the memory back to the producer after the elements are dropped
+		unsafe { s.drop_in_place(); }
+		self.retire(n);
+	}
+
+	// Number of available elements, reloads the head offset only if fewer than `n` are known to be available
+	#[inline]
+	fn available(&mut self, n: usize) -> usize {
+		let len = self.shared.ring.distance(self.tail, self.cached_head);
+		if len >= n {
+			return len;
+		}
+		self.cached_head = self.shared.head.load(Ordering::Acquire);
+		let mut len = self.shared.ring.distance(self.tail, self.cached_head);
+		while len == 0 && self.follow() {
+			len = self.shared.ring.distance(self.tail, self.cached_head);
+		}
+		len
+	}
+
+	// Moves on to the ring buffer the producer grew into once this one has been drained.
+	#[cold]
+	fn follow(&mut self) -> bool {
+		if !self.shared.moved.load(Ordering::Acquire) {
+			return false;
+		}
+		// The producer published its last element in this ring buffer before moving on
+		if self.shared.head.load(Ordering::Acquire) != self.tail {
+			return false;
+		}
+		let next = match self.shared.next.lock().unwrap().take() {
+			Some(next) => next,
+			None => return false,
+		};
+		self.tail = next.tail.load(Ordering::Relaxed);
+		self.cached_head = next.head.load(Ordering::Acquire);
+		self.shared = next;
+		true
+	}
+
+	// Hands `n` elements after the tail back to the producer.
+	#[inline]
+	fn retire(&mut self, n: usize) {
+		self.tail = self.shared.ring.advance(self.tail, n);
+		self.shared.tail.store(self.tail, Ordering::Release);
+		// The cached head lags behind the producer, so this overestimates the free space
+		self.shared.producer_notify.notify(self.capacity() - self.shared.ring.distance(self.tail, self.cached_head));
+	}
+}
+
+/// Iterator over the elements available to a consumer.
+///
+/// This struct is created by the [`try_iter`](Consumer::try_iter) method on consumers.
+#[derive(Debug)]
+pub struct TryIter<'a, T> {
+	consumer: &'a mut Consumer<T>,
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+	type Item = T;
+
+	#[inline]
+	fn next(&mut self) -> Option<T> {
+		self.consumer.pop().ok()
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		// More elements may be pushed while iterating
+		(self.consumer.len(), None)
+	}
+}
+
+impl<T> Drop for Consumer<T> {
+	fn drop(&mut self) {
+		// The producer may have moved on to any of the ring buffers linked after this one
+		let mut shared = self.shared.clone();
+		loop {
+			let slot = shared.next.lock().unwrap();
+			shared.closed.store(true, Ordering::Release);
+			shared.producer_notify.notify(usize::MAX);
+			let next = slot.clone();
+			drop(slot);
+			match next {
+				Some(next) => shared = next,
+				None => break,
+			}
+		}
+	}
+}
+
+impl<T> fmt::Debug for Consumer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Consumer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
@@ -0,0 +1,71 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use super::{Consumer, Disconnected, Producer, PushError, Select};
+
+impl<T> Stream for Consumer<T> {
+	type Item = T;
+
+	/// Ends the stream once the producer has been dropped and the remaining elements have been consumed.
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+		let this = self.get_mut();
+		match this.poll_read_ready(cx) {
+			Poll::Ready(()) => Poll::Ready(this.pop().ok()),
+			Poll::Pending => Poll::Pending,
+		}
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(self.len(), None)
+	}
+}
+
+impl<T> Sink<T> for Producer<T> {
+	type Error = Disconnected;
+
+	#[inline]
+	fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Disconnected>> {
+		match self.poll_write_ready(cx) {
+			Poll::Ready(()) if self.is_closed() => Poll::Ready(Err(Disconnected)),
+			Poll::Ready(()) => Poll::Ready(Ok(())),
+			Poll::Pending => Poll::Pending,
+		}
+	}
+
+	#[inline]
+	fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Disconnected> {
+		match self.get_mut().push(item) {
+			Ok(()) => Ok(()),
+			Err(PushError::Closed(_)) => Err(Disconnected),
+			Err(PushError::Full(_)) => panic!("start_send called without poll_ready"),
+		}
+	}
+
+	#[inline]
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Disconnected>> {
+		// Elements are visible to the consumer as soon as they are pushed
+		Poll::Ready(Ok(()))
+	}
+
+	#[inline]
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Disconnected>> {
+		// The consumer still drains the elements pushed before
+		self.close();
+		Poll::Ready(Ok(()))
+	}
+}
+
+impl<T> Stream for Select<T> {
+	type Item = (usize, T);
+
+	/// Ends the stream once all the producers have been dropped and the remaining elements have been consumed.
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<(usize, T)>> {
+		self.get_mut().poll_pop(cx)
+	}
+}
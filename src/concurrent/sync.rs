@@ -0,0 +1,26 @@
+// Synchronization primitives used by the concurrent ring buffers.
+// Under `cfg(loom)` these are swapped for the instrumented versions of the loom model checker.
+
+#[cfg(not(loom))]
+pub(crate) use std::{hint, thread};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+#[cfg(loom)]
+pub(crate) use loom::hint;
+#[cfg(loom)]
+pub(crate) use loom::sync::{Arc, Mutex};
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+#[cfg(loom)]
+pub(crate) mod thread {
+	pub(crate) use loom::thread::{current, park, yield_now, Thread};
+
+	// Loom has no notion of time, treat the timeout as a spurious wake up.
+	pub(crate) fn park_timeout(_dur: std::time::Duration) {
+		loom::thread::yield_now();
+	}
+}
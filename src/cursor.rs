@@ -0,0 +1,33 @@
+/*!
+Filling the spare capacity through `BorrowedCursor`.
+*/
+
+use std::io;
+use std::io::{BorrowedBuf, BorrowedCursor, Read};
+
+use super::RingBuffer;
+
+impl RingBuffer<u8> {
+	/// Fills the spare capacity through a [`BorrowedCursor`] and appends the written bytes.
+	///
+	/// Returns the number of bytes appended, bytes written before an error are still appended.
+	/// Reserve capacity beforehand, the cursor covers exactly the current [`reserved_len`](Self::reserved_len).
+	pub fn fill_reserved<F>(&mut self, f: F) -> io::Result<usize> where F: FnOnce(BorrowedCursor<'_>) -> io::Result<()> {
+		let mut buf = BorrowedBuf::from(self.reserved_mut());
+		let result = f(buf.unfilled());
+		let filled = buf.len();
+		// Safety: BorrowedBuf tracks how many bytes have been filled in
+		unsafe { self.add_len(filled); }
+		result.map(|()| filled)
+	}
+
+	/// Reads bytes from `reader` directly into the spare capacity with [`Read::read_buf`].
+	///
+	/// Reserves room for at least `additional` bytes first.
+	/// Returns the number of bytes appended, 0 indicates end of file when `additional` is not 0.
+	#[inline]
+	pub fn read_buf_from<R: Read + ?Sized>(&mut self, reader: &mut R, additional: usize) -> io::Result<usize> {
+		self.reserve(additional);
+		self.fill_reserved(|cursor| reader.read_buf(cursor))
+	}
+}
@@ -0,0 +1,80 @@
+/*!
+Removing elements from the tail while yielding them.
+*/
+
+use core::{cmp, fmt, iter};
+
+use super::{MirroredMemory, RingBuffer};
+
+impl<T, M: MirroredMemory> RingBuffer<T, M> {
+	/// Removes `n` elements from the tail and yields them, like [`remove_tail`](Self::remove_tail) without dropping them.
+	///
+	/// Removes at most [`len`](Self::len) elements.
+	/// Each element is removed as it is yielded, the elements which have not been yielded are dropped together with the iterator.
+	/// If the iterator is leaked they stay in the ring buffer.
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::new();
+	/// rbuf.extend(["a", "b", "c"].iter().map(|s| s.to_string()));
+	/// let drained: Vec<String> = rbuf.drain_tail(2).collect();
+	/// assert_eq!(drained, ["a", "b"]);
+	/// assert_eq!(rbuf.as_slice(), ["c"]);
+	/// ```
+	#[inline]
+	pub fn drain_tail(&mut self, n: usize) -> Drain<'_, T, M> {
+		let remaining = cmp::min(n, self.len());
+		Drain { rbuf: self, remaining }
+	}
+}
+
+/// Iterator removing elements from the tail of a ring buffer.
+///
+/// This struct is created by the [`drain_tail`](RingBuffer::drain_tail) method on ring buffers.
+pub struct Drain<'a, T, M: MirroredMemory> {
+	rbuf: &'a mut RingBuffer<T, M>,
+	remaining: usize,
+}
+
+impl<'a, T, M: MirroredMemory> Drain<'a, T, M> {
+	/// Returns the elements which have not been yielded yet.
+	#[inline]
+	pub fn as_slice(&self) -> &[T] {
+		&self.rbuf.as_slice()[..self.remaining]
+	}
+}
+
+impl<'a, T, M: MirroredMemory> Iterator for Drain<'a, T, M> {
+	type Item = T;
+
+	#[inline]
+	fn next(&mut self) -> Option<T> {
+		if self.remaining == 0 {
+			return None;
+		}
+		self.remaining -= 1;
+		self.rbuf.pop()
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(self.remaining, Some(self.remaining))
+	}
+}
+
+impl<'a, T, M: MirroredMemory> ExactSizeIterator for Drain<'a, T, M> {}
+impl<'a, T, M: MirroredMemory> iter::FusedIterator for Drain<'a, T, M> {}
+
+impl<'a, T, M: MirroredMemory> Drop for Drain<'a, T, M> {
+	#[inline]
+	fn drop(&mut self) {
+		self.rbuf.remove_tail(self.remaining);
+	}
+}
+
+impl<'a, T: fmt::Debug, M: MirroredMemory> fmt::Debug for Drain<'a, T, M> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("Drain").field(&self.as_slice()).finish()
+	}
+}
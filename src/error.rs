@@ -0,0 +1,187 @@
+/*!
+Allocation errors.
+*/
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{error, io};
+
+/// Error returned by the fallible allocation methods, see [`try_reserve`](super::RingBuffer::try_reserve).
+#[derive(Debug)]
+pub enum TryReserveError {
+	/// The requested capacity exceeds system limits.
+	CapacityOverflow,
+	/// The required capacity exceeds the [capacity limit](super::RingBuffer::set_capacity_limit) of the ring buffer.
+	LimitExceeded,
+	/// The system failed to allocate or map the memory, for example when running out of address space or file descriptors.
+	///
+	/// Holds the [`PlatformError`] describing the failure.
+	AllocError(PlatformError),
+}
+
+impl fmt::Display for TryReserveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TryReserveError::CapacityOverflow => f.write_str("capacity exceeds system limits"),
+			TryReserveError::LimitExceeded => f.write_str("capacity exceeds the limit"),
+			TryReserveError::AllocError(err) => write!(f, "memory allocation failed: {}", err),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl error::Error for TryReserveError {
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			TryReserveError::CapacityOverflow | TryReserveError::LimitExceeded => None,
+			TryReserveError::AllocError(err) => Some(err),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<TryReserveError> for io::Error {
+	#[inline]
+	fn from(err: TryReserveError) -> io::Error {
+		match err {
+			TryReserveError::CapacityOverflow | TryReserveError::LimitExceeded => io::Error::new(io::ErrorKind::InvalidInput, err),
+			TryReserveError::AllocError(err) => err.into(),
+		}
+	}
+}
+
+/// Error of the system call which failed to allocate or map the memory of a ring buffer.
+///
+/// Converts into an `io::Error` of the same kind as the OS error.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlatformError {
+	/// The system call failed with an OS error code, `errno` on Unix and `GetLastError` on Windows.
+	Os {
+		call: &'static str,
+		code: i32,
+		size: usize,
+	},
+	/// The Mach call failed with a `kern_return_t`, on macOS and iOS.
+	Mach {
+		call: &'static str,
+		code: i32,
+		size: usize,
+	},
+	/// The Zircon system call failed with a `zx_status_t`, on Fuchsia.
+	Zircon {
+		call: &'static str,
+		status: i32,
+		size: usize,
+	},
+	/// No room was left for the mapping within its [address range](super::RingBufferBuilder::address_range).
+	OutOfRange {
+		size: usize,
+	},
+	/// The backend does not support the call, such as [address ranges](super::RingBufferBuilder::address_range) or locking memory with the heap fallback.
+	Unsupported {
+		call: &'static str,
+		size: usize,
+	},
+	/// The heap allocation of the fallback backend failed.
+	OutOfMemory {
+		size: usize,
+	},
+}
+
+impl PlatformError {
+	/// Returns the name of the system call which failed, or of the operation if it failed without one.
+	#[inline]
+	pub fn call(&self) -> &'static str {
+		match *self {
+			PlatformError::Os { call, .. } | PlatformError::Mach { call, .. } | PlatformError::Zircon { call, .. } | PlatformError::Unsupported { call, .. } => call,
+			PlatformError::OutOfRange { .. } => "reserve",
+			PlatformError::OutOfMemory { .. } => "alloc",
+		}
+	}
+
+	/// Returns the number of bytes which failed to be allocated or mapped.
+	#[inline]
+	pub fn size(&self) -> usize {
+		match *self {
+			PlatformError::Os { size, .. } | PlatformError::Mach { size, .. } | PlatformError::Zircon { size, .. } | PlatformError::Unsupported { size, .. } => size,
+			PlatformError::OutOfRange { size } | PlatformError::OutOfMemory { size } => size,
+		}
+	}
+
+	/// Returns the OS error code, see `io::Error::raw_os_error`.
+	#[inline]
+	pub fn raw_os_error(&self) -> Option<i32> {
+		match *self {
+			PlatformError::Os { code, .. } => Some(code),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for PlatformError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			#[cfg(feature = "std")]
+			PlatformError::Os { call, code, size } => write!(f, "{}() failed for {} bytes: {}", call, size, io::Error::from_raw_os_error(code)),
+			#[cfg(not(feature = "std"))]
+			PlatformError::Os { call, code, size } => write!(f, "{}() failed for {} bytes: os error {}", call, size, code),
+			PlatformError::Mach { call, code, size } => write!(f, "{}() failed for {} bytes: kern_return_t {}", call, size, code),
+			PlatformError::Zircon { call, status, size } => write!(f, "{}() failed for {} bytes: zx_status_t {}", call, size, status),
+			PlatformError::OutOfRange { size } => write!(f, "no room for the mapping of {} bytes within the address range", size),
+			PlatformError::Unsupported { call, size } => write!(f, "{}() is not supported by the backend, failed for {} bytes", call, size),
+			PlatformError::OutOfMemory { size } => write!(f, "heap allocation of {} bytes failed", size),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl error::Error for PlatformError {}
+
+#[cfg(feature = "std")]
+impl From<PlatformError> for io::Error {
+	#[inline]
+	fn from(err: PlatformError) -> io::Error {
+		let kind = match err {
+			PlatformError::Os { code, .. } => io::Error::from_raw_os_error(code).kind(),
+			PlatformError::OutOfRange { .. } => io::ErrorKind::AddrNotAvailable,
+			PlatformError::Unsupported { .. } => io::ErrorKind::Unsupported,
+			PlatformError::OutOfMemory { .. } => io::ErrorKind::OutOfMemory,
+			PlatformError::Mach { .. } | PlatformError::Zircon { .. } => io::ErrorKind::Other,
+		};
+		io::Error::new(kind, err)
+	}
+}
+
+impl From<PlatformError> for TryReserveError {
+	#[inline]
+	fn from(err: PlatformError) -> TryReserveError {
+		TryReserveError::AllocError(err)
+	}
+}
+
+/// Error returned by [`validate`](super::RingBuffer::validate) when the internal invariants of a ring buffer are violated.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InvariantError {
+	/// The length exceeds the capacity, see [`set_len`](super::RingBuffer::set_len).
+	Length {
+		len: usize,
+		capacity: usize,
+	},
+	/// The start of the ring buffer lies outside the first half of the mapping, both in bytes.
+	Base {
+		base: usize,
+		size: usize,
+	},
+}
+
+impl fmt::Display for InvariantError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			InvariantError::Length { len, capacity } => write!(f, "ring buffer length {} exceeds its capacity {}", len, capacity),
+			InvariantError::Base { base, size } => write!(f, "ring buffer starts at offset {} outside the first {} bytes", base, size),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl error::Error for InvariantError {}
@@ -0,0 +1,351 @@
+/*!
+Ring buffers persisted in files.
+*/
+
+use std::{cmp, fmt, io, mem, ops, slice};
+use std::fs::{File, OpenOptions};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use super::{platform, HeaderError, RingBuffer};
+
+// Identifies files holding ring buffers
+const MAGIC: u64 = u64::from_le_bytes(*b"vringbuf");
+// Version of the file layout
+const VERSION: u64 = 2;
+
+// Header at the start of the file, followed by the mirrored ring buffer.
+//
+// Positions are byte offsets which only ever increase.
+// The elements live at positions modulo the capacity.
+// Every operation updates a single position so the state survives the process crashing at any point.
+#[repr(C)]
+struct Header {
+	magic: u64,
+	version: u64,
+	// Size of the elements in bytes.
+	size_of: u64,
+	// Capacity of the ring buffer in bytes.
+	cap: u64,
+	// Nonzero while the file is open.
+	open: u64,
+	// Position of the next element to be pushed.
+	head: u64,
+	// Position of the next element to be popped.
+	tail: u64,
+	// Positions as of the last flush.
+	checkpoint: Checkpoint,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Checkpoint {
+	head: u64,
+	tail: u64,
+	// Detects checkpoints torn by a power loss.
+	check: u64,
+}
+
+impl Checkpoint {
+	#[inline]
+	fn new(head: u64, tail: u64) -> Checkpoint {
+		Checkpoint { head, tail, check: Checkpoint::checksum(head, tail) }
+	}
+	#[inline]
+	fn checksum(head: u64, tail: u64) -> u64 {
+		(head ^ tail.rotate_left(32) ^ VERSION).wrapping_mul(0x9e3779b97f4a7c15)
+	}
+	#[inline]
+	fn is_valid<T>(&self, cap: usize) -> bool {
+		self.check == Checkpoint::checksum(self.head, self.tail) && is_valid::<T>(cap, self.head, self.tail)
+	}
+}
+
+// Positions must be aligned and at most the capacity apart
+#[inline]
+fn is_valid<T>(cap: usize, head: u64, tail: u64) -> bool {
+	let size_of = mem::size_of::<T>() as u64;
+	let align_mask = mem::align_of::<T>() as u64 - 1;
+	let bytes = head.wrapping_sub(tail);
+	tail <= head && bytes.is_multiple_of(size_of) && bytes / size_of <= (cap as u64) / size_of && (head | tail) & align_mask == 0
+}
+
+// Validates the header of an existing file
+fn validate<T>(state: &Header, cap: usize) -> Result<(), HeaderError> {
+	if state.magic != MAGIC {
+		return Err(HeaderError::Magic);
+	}
+	if state.version != VERSION {
+		return Err(HeaderError::Version { expected: VERSION, found: state.version });
+	}
+	if state.size_of != mem::size_of::<T>() as u64 {
+		return Err(HeaderError::ElementSize { expected: mem::size_of::<T>(), found: state.size_of as usize });
+	}
+	if state.cap != cap as u64 {
+		return Err(HeaderError::Capacity { expected: cap, found: state.cap as usize });
+	}
+	if !is_valid::<T>(cap, state.head, state.tail) && !state.checkpoint.is_valid::<T>(cap) {
+		return Err(HeaderError::Corrupted);
+	}
+	Ok(())
+}
+
+// Size of the mapping reserved for the header
+#[inline]
+fn header_len() -> usize {
+	cmp::max(platform::granularity(), mem::size_of::<Header>())
+}
+
+impl<T: Copy> RingBuffer<T> {
+	/// Opens a ring buffer persisted in a file, creating the file if it does not exist.
+	///
+	/// An empty file is sized to hold `capacity` elements, rounded up like [`with_capacity`](Self::with_capacity).
+	/// An existing ring buffer keeps its elements and capacity.
+	///
+	/// The operating system writes changes back to the file at its own pace.
+	/// Use [`flush`](FileRingBuffer::flush) to write them back immediately and [`recover`](FileRingBuffer::recover) after a crash.
+	///
+	/// Returns an error if the file cannot be opened or does not hold a valid ring buffer.
+	/// An invalid header is reported with a [`HeaderError`].
+	///
+	/// # Safety
+	///
+	/// * The file must only ever hold ring buffers with the same element type `T`.
+	/// * `T` must be valid for any bit pattern.
+	/// * The file must not be opened more than once at the same time.
+	/// * The file must not be modified by other means while open.
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits.
+	pub unsafe fn open_file<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<FileRingBuffer<T>> {
+		let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+		let () = Self::ALIGNED;
+		let header = header_len();
+		let (ptr, cap) = platform::open_file(&file, header, capacity, mem::size_of::<T>())?;
+		let state = &mut *(ptr.as_ptr() as *mut Header);
+
+		// Never trust the file contents
+		// New files start out zeroed
+		let is_new = state.magic == 0 && state.version == 0 && state.open == 0 && state.head == 0 && state.tail == 0;
+		if is_new {
+			state.version = VERSION;
+			state.size_of = mem::size_of::<T>() as u64;
+			state.cap = cap as u64;
+			state.checkpoint = Checkpoint::new(0, 0);
+			state.magic = MAGIC;
+		}
+		else if let Err(err) = validate::<T>(state, cap) {
+			platform::free_shared(ptr, header, cap);
+			return Err(err.into());
+		}
+		let crashed = state.open != 0;
+		state.open = 1;
+
+		let mut ring = FileRingBuffer { ptr, header, cap, file, crashed, _marker: PhantomData };
+		if !is_valid::<T>(cap, state.head, state.tail) {
+			ring.restore();
+		}
+		Ok(ring)
+	}
+}
+
+/// Ring buffer persisted in a file.
+///
+/// This struct is created by [`open_file`](RingBuffer::open_file).
+/// Unlike [`RingBuffer`] it does not grow. Pushing onto a full ring buffer fails instead.
+pub struct FileRingBuffer<T> {
+	// Start of the header.
+	ptr: NonNull<u8>,
+	// Size of the header in bytes.
+	header: usize,
+	// Capacity of the ring buffer in bytes.
+	cap: usize,
+	file: File,
+	// The previous session did not close the file.
+	crashed: bool,
+	_marker: PhantomData<T>,
+}
+
+impl<T: Copy> FileRingBuffer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.cap / mem::size_of::<T>()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let header = self.header();
+		(header.head - header.tail) as usize / mem::size_of::<T>()
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the ring buffer has no room for more elements.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		self.len() == self.capacity()
+	}
+
+	/// Extracts a slice containing the entire ring buffer.
+	#[inline]
+	pub fn as_slice(&self) -> &[T] {
+		unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+	}
+
+	/// Extracts a mutable slice of the entire ring buffer.
+	#[inline]
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		unsafe { slice::from_raw_parts_mut(self.as_ptr(), self.len()) }
+	}
+
+	/// Clears the ring buffer, removing all values.
+	#[inline]
+	pub fn clear(&mut self) {
+		let header = self.header_mut();
+		header.tail = header.head;
+	}
+
+	/// Removes `n` elements from the tail.
+	#[inline]
+	pub fn remove_tail(&mut self, n: usize) {
+		let n = cmp::min(self.len(), n);
+		self.header_mut().tail += (n * mem::size_of::<T>()) as u64;
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the ring buffer is full.
+	#[inline]
+	pub fn push(&mut self, value: T) -> Result<(), T> {
+		match self.extend_from_slice(slice::from_ref(&value)) {
+			0 => Err(value),
+			_ => Ok(()),
+		}
+	}
+
+	/// Copies as many elements from `other` as there is room for and appends them to the front.
+	///
+	/// Returns the number of elements appended.
+	pub fn extend_from_slice(&mut self, other: &[T]) -> usize {
+		let len = self.len();
+		let n = cmp::min(self.capacity() - len, other.len());
+		unsafe { other.as_ptr().copy_to_nonoverlapping(self.as_ptr().add(len), n); }
+		self.header_mut().head += (n * mem::size_of::<T>()) as u64;
+		n
+	}
+
+	/// Removes the element at the tail and returns it, or [`None`] if the ring buffer is empty.
+	#[inline]
+	pub fn pop(&mut self) -> Option<T> {
+		let value = *self.as_slice().first()?;
+		self.remove_tail(1);
+		Some(value)
+	}
+
+	/// Writes the ring buffer back to the file and waits for the file to be synced to disk.
+	///
+	/// The flushed state is what [`recover`](Self::recover) restores.
+	pub fn flush(&mut self) -> io::Result<()> {
+		// The elements must be on disk before the checkpoint referring to them
+		self.sync()?;
+		let header = self.header_mut();
+		header.checkpoint = Checkpoint::new(header.head, header.tail);
+		self.sync()
+	}
+
+	/// Restores the ring buffer to its last flushed state if the previous session crashed.
+	///
+	/// Elements which were not flushed may not have reached the disk before a power loss.
+	/// Recovering discards the changes made since the last flush.
+	/// Returns `true` if the previous session crashed, otherwise does nothing and returns `false`.
+	pub fn recover(&mut self) -> bool {
+		if !self.crashed {
+			return false;
+		}
+		self.crashed = false;
+		self.restore()
+	}
+
+	/// Returns the file backing the ring buffer.
+	#[inline]
+	pub fn file(&self) -> &File {
+		&self.file
+	}
+
+	// Restores the checkpoint unless it is invalid
+	fn restore(&mut self) -> bool {
+		let checkpoint = self.header().checkpoint;
+		if !checkpoint.is_valid::<T>(self.cap) {
+			return false;
+		}
+		let header = self.header_mut();
+		header.head = checkpoint.head;
+		header.tail = checkpoint.tail;
+		true
+	}
+	fn sync(&self) -> io::Result<()> {
+		unsafe { platform::flush(self.ptr, self.header, self.cap)?; }
+		self.file.sync_data()
+	}
+	#[inline]
+	fn header(&self) -> &Header {
+		unsafe { &*(self.ptr.as_ptr() as *const Header) }
+	}
+	#[inline]
+	fn header_mut(&mut self) -> &mut Header {
+		unsafe { &mut *(self.ptr.as_ptr() as *mut Header) }
+	}
+	// Pointer to the first element
+	#[inline]
+	fn as_ptr(&self) -> *mut T {
+		let base = (self.header().tail % self.cap as u64) as usize;
+		unsafe { self.ptr.as_ptr().add(self.header + base) as *mut T }
+	}
+}
+
+impl<T: Copy> ops::Deref for FileRingBuffer<T> {
+	type Target = [T];
+	#[inline]
+	fn deref(&self) -> &[T] {
+		self.as_slice()
+	}
+}
+impl<T: Copy> ops::DerefMut for FileRingBuffer<T> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut [T] {
+		self.as_mut_slice()
+	}
+}
+
+impl<T> Drop for FileRingBuffer<T> {
+	fn drop(&mut self) {
+		unsafe {
+			// Closing the file cleanly flushes it so there is nothing to recover
+			let header = &mut *(self.ptr.as_ptr() as *mut Header);
+			if platform::flush(self.ptr, self.header, self.cap).is_ok() && self.file.sync_data().is_ok() {
+				header.checkpoint = Checkpoint::new(header.head, header.tail);
+				header.open = 0;
+				let _ = platform::flush(self.ptr, self.header, self.cap);
+			}
+			platform::free_shared(self.ptr, self.header, self.cap);
+		}
+	}
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for FileRingBuffer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(self.as_slice(), f)
+	}
+}
+
+// The ring buffer owns its mapping
+unsafe impl<T: Copy + Send> Send for FileRingBuffer<T> {}
+unsafe impl<T: Copy + Sync> Sync for FileRingBuffer<T> {}
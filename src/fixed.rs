@@ -0,0 +1,453 @@
+/*!
+Ring buffers with a fixed capacity stored inline.
+*/
+
+use core::{cmp, fmt, mem, slice};
+
+use super::{MirroredMemory, RingBuffer, TryReserveError};
+
+/// Operations shared by [`RingBuffer`] and [`StaticRingBuffer`].
+///
+/// Code written against this trait chooses per target whether its ring buffers are backed by mirrored memory or stored inline without allocating.
+///
+/// ```
+/// use vringbuf::{Ring, RingBuffer, StaticRingBuffer};
+///
+/// // Keeps the last 4 samples
+/// fn record<R: Ring<u32>>(ring: &mut R, sample: u32) {
+/// 	if ring.len() == 4 {
+/// 		ring.pop();
+/// 	}
+/// 	ring.try_push(sample).unwrap();
+/// }
+///
+/// let mut heap = RingBuffer::new();
+/// let mut inline = StaticRingBuffer::<u32, 4>::new();
+/// for sample in 0..10 {
+/// 	record(&mut heap, sample);
+/// 	record(&mut inline, sample);
+/// }
+/// assert_eq!(heap.as_slice(), &[6, 7, 8, 9]);
+/// assert_eq!(inline.as_slices(), (&[6, 7][..], &[8, 9][..]));
+/// ```
+pub trait Ring<T> {
+	/// Returns the number of elements in the ring buffer.
+	fn len(&self) -> usize;
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the number of elements the ring buffer can hold without reallocating.
+	fn capacity(&self) -> usize;
+
+	/// Returns the elements as two slices, from the oldest to the newest element.
+	///
+	/// The second slice is empty for ring buffers backed by mirrored memory.
+	fn as_slices(&self) -> (&[T], &[T]);
+
+	/// Returns the elements as two mutable slices, from the oldest to the newest element.
+	fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]);
+
+	/// Appends an element to the front, the element is dropped on error.
+	fn try_push(&mut self, value: T) -> Result<(), TryReserveError>;
+
+	/// Removes the back element and returns it, or [`None`] if the ring buffer is empty.
+	fn pop(&mut self) -> Option<T>;
+
+	/// Removes `n` elements from the tail.
+	fn remove_tail(&mut self, n: usize);
+
+	/// Shortens the ring buffer, keeping the first `len` elements and dropping the rest.
+	fn truncate(&mut self, len: usize);
+
+	/// Clears the ring buffer, removing all values.
+	fn clear(&mut self);
+}
+
+impl<T, M: MirroredMemory> Ring<T> for RingBuffer<T, M> {
+	#[inline]
+	fn len(&self) -> usize {
+		RingBuffer::len(self)
+	}
+	#[inline]
+	fn capacity(&self) -> usize {
+		RingBuffer::capacity(self)
+	}
+	#[inline]
+	fn as_slices(&self) -> (&[T], &[T]) {
+		(self.as_slice(), &[])
+	}
+	#[inline]
+	fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+		(self.as_mut_slice(), &mut [])
+	}
+	#[inline]
+	fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+		RingBuffer::try_push(self, value)
+	}
+	#[inline]
+	fn pop(&mut self) -> Option<T> {
+		RingBuffer::pop(self)
+	}
+	#[inline]
+	fn remove_tail(&mut self, n: usize) {
+		RingBuffer::remove_tail(self, n)
+	}
+	#[inline]
+	fn truncate(&mut self, len: usize) {
+		RingBuffer::truncate(self, len)
+	}
+	#[inline]
+	fn clear(&mut self) {
+		RingBuffer::clear(self)
+	}
+}
+
+/// Ring buffer of at most `N` elements stored inline, without mirrored memory or allocations.
+///
+/// Meant for targets without virtual memory and for statics, [`new`](Self::new) is a `const fn`.
+/// The elements wrap around the end of the array and are returned as two slices by [`as_slices`](Self::as_slices).
+/// [`make_contiguous`](Self::make_contiguous) rotates them into a single slice.
+///
+/// Implements [`Ring`] to share code with [`RingBuffer`].
+///
+/// ```
+/// use vringbuf::StaticRingBuffer;
+///
+/// let mut rbuf = StaticRingBuffer::<u8, 4>::new();
+/// rbuf.extend_from_slice(b"abc");
+/// assert_eq!(rbuf.pop(), Some(b'a'));
+/// rbuf.extend_from_slice(b"de");
+/// assert_eq!(rbuf.as_slices(), (&b"bcd"[..], &b"e"[..]));
+/// assert_eq!(rbuf.make_contiguous(), b"bcde");
+/// assert!(rbuf.try_push(b'f').is_err());
+/// ```
+pub struct StaticRingBuffer<T, const N: usize> {
+	buf: [mem::MaybeUninit<T>; N],
+	// Index of the oldest element
+	head: usize,
+	len: usize,
+}
+
+impl<T, const N: usize> StaticRingBuffer<T, N> {
+	/// Constructs a new, empty ring buffer.
+	#[inline]
+	pub const fn new() -> StaticRingBuffer<T, N> {
+		StaticRingBuffer {
+			// Safe because an array of `MaybeUninit` does not require initialization
+			buf: unsafe { mem::MaybeUninit::<[mem::MaybeUninit<T>; N]>::uninit().assume_init() },
+			head: 0,
+			len: 0,
+		}
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns `true` if the ring buffer holds `N` elements.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		self.len == N
+	}
+
+	/// Returns the number of elements the ring buffer can hold, always `N`.
+	#[inline]
+	pub const fn capacity(&self) -> usize {
+		N
+	}
+
+	// Returns the lengths of the elements before and after wrapping around the end of the array
+	#[inline]
+	fn split(&self) -> (usize, usize) {
+		let first = cmp::min(N - self.head, self.len);
+		(first, self.len - first)
+	}
+
+	// Returns the index in the array of the `i`th element
+	#[inline]
+	fn index(&self, i: usize) -> usize {
+		let i = self.head + i;
+		if i >= N { i - N } else { i }
+	}
+
+	/// Returns the elements as two slices, from the oldest to the newest element.
+	#[inline]
+	pub fn as_slices(&self) -> (&[T], &[T]) {
+		let (first, second) = self.split();
+		unsafe {
+			let ptr = self.buf.as_ptr() as *const T;
+			(slice::from_raw_parts(ptr.add(self.head), first), slice::from_raw_parts(ptr, second))
+		}
+	}
+
+	/// Returns the elements as two mutable slices, from the oldest to the newest element.
+	#[inline]
+	pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+		let (first, second) = self.split();
+		unsafe {
+			let ptr = self.buf.as_mut_ptr() as *mut T;
+			(slice::from_raw_parts_mut(ptr.add(self.head), first), slice::from_raw_parts_mut(ptr, second))
+		}
+	}
+
+	/// Rotates the elements so they are contiguous and returns them as a single slice.
+	pub fn make_contiguous(&mut self) -> &mut [T] {
+		if self.head + self.len > N {
+			self.buf.rotate_left(self.head);
+			self.head = 0;
+		}
+		unsafe { slice::from_raw_parts_mut((self.buf.as_mut_ptr() as *mut T).add(self.head), self.len) }
+	}
+
+	/// Returns an iterator over the elements, from the oldest to the newest element.
+	#[inline]
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + Clone {
+		let (first, second) = self.as_slices();
+		Iter { first: first.iter(), second: second.iter() }
+	}
+
+	/// Appends an element to the front.
+	///
+	/// # Panics
+	///
+	/// Panics if the ring buffer is full.
+	#[inline]
+	pub fn push(&mut self, value: T) {
+		if self.try_push(value).is_err() {
+			panic!("ring buffer is full");
+		}
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns [`TryReserveError::LimitExceeded`] if the ring buffer is full, the element is dropped on error.
+	#[inline]
+	pub fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+		if self.len == N {
+			return Err(TryReserveError::LimitExceeded);
+		}
+		let i = self.index(self.len);
+		self.buf[i] = mem::MaybeUninit::new(value);
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Removes the back element and returns it, or [`None`] if the ring buffer is empty.
+	#[inline]
+	pub fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			return None;
+		}
+		let value = unsafe { self.buf[self.head].as_ptr().read() };
+		self.head = self.index(1);
+		self.len -= 1;
+		Some(value)
+	}
+
+	/// Copies and appends all elements in a slice to the ring buffer.
+	///
+	/// # Panics
+	///
+	/// Panics if the elements do not fit, nothing is appended.
+	#[inline]
+	pub fn extend_from_slice(&mut self, other: &[T]) where T: Copy {
+		if self.try_extend_from_slice(other).is_err() {
+			panic!("ring buffer is full");
+		}
+	}
+
+	/// Copies and appends all elements in a slice to the ring buffer.
+	///
+	/// Returns [`TryReserveError::LimitExceeded`] if the elements do not fit, nothing is appended on error.
+	pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError> where T: Copy {
+		if other.len() > N - self.len {
+			return Err(TryReserveError::LimitExceeded);
+		}
+		let start = self.index(self.len);
+		let first = cmp::min(N - start, other.len());
+		unsafe {
+			let ptr = self.buf.as_mut_ptr() as *mut T;
+			other.as_ptr().copy_to_nonoverlapping(ptr.add(start), first);
+			other.as_ptr().add(first).copy_to_nonoverlapping(ptr, other.len() - first);
+		}
+		self.len += other.len();
+		Ok(())
+	}
+
+	/// Removes `n` elements from the tail.
+	pub fn remove_tail(&mut self, n: usize) {
+		// Keep the method safe by removing max of `len` elements
+		let n = cmp::min(self.len, n);
+		let (first, second) = self.as_mut_slices();
+		let split = cmp::min(first.len(), n);
+		let first = &mut first[..split] as *mut [T];
+		let second = &mut second[..n - split] as *mut [T];
+		// Remove the elements before dropping them in case a destructor panics
+		self.head = self.index(n);
+		self.len -= n;
+		unsafe {
+			first.drop_in_place();
+			second.drop_in_place();
+		}
+	}
+
+	/// Shortens the ring buffer, keeping the first `len` elements and dropping the rest.
+	///
+	/// If `len` is greater than the ring buffer’s current length, this has no effect.
+	pub fn truncate(&mut self, len: usize) {
+		if len >= self.len {
+			return;
+		}
+		let (first, second) = self.as_mut_slices();
+		let split = cmp::min(first.len(), len);
+		let first = &mut first[split..] as *mut [T];
+		let second = &mut second[len - split..] as *mut [T];
+		self.len = len;
+		unsafe {
+			first.drop_in_place();
+			second.drop_in_place();
+		}
+	}
+
+	/// Clears the ring buffer, removing all values.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.truncate(0);
+		self.head = 0;
+	}
+}
+
+impl<T, const N: usize> Ring<T> for StaticRingBuffer<T, N> {
+	#[inline]
+	fn len(&self) -> usize {
+		self.len
+	}
+	#[inline]
+	fn capacity(&self) -> usize {
+		N
+	}
+	#[inline]
+	fn as_slices(&self) -> (&[T], &[T]) {
+		StaticRingBuffer::as_slices(self)
+	}
+	#[inline]
+	fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+		StaticRingBuffer::as_mut_slices(self)
+	}
+	#[inline]
+	fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+		StaticRingBuffer::try_push(self, value)
+	}
+	#[inline]
+	fn pop(&mut self) -> Option<T> {
+		StaticRingBuffer::pop(self)
+	}
+	#[inline]
+	fn remove_tail(&mut self, n: usize) {
+		StaticRingBuffer::remove_tail(self, n)
+	}
+	#[inline]
+	fn truncate(&mut self, len: usize) {
+		StaticRingBuffer::truncate(self, len)
+	}
+	#[inline]
+	fn clear(&mut self) {
+		StaticRingBuffer::clear(self)
+	}
+}
+
+impl<T, const N: usize> Drop for StaticRingBuffer<T, N> {
+	#[inline]
+	fn drop(&mut self) {
+		self.clear();
+	}
+}
+
+impl<T, const N: usize> Default for StaticRingBuffer<T, N> {
+	#[inline]
+	fn default() -> StaticRingBuffer<T, N> {
+		StaticRingBuffer::new()
+	}
+}
+
+impl<T: Clone, const N: usize> Clone for StaticRingBuffer<T, N> {
+	fn clone(&self) -> StaticRingBuffer<T, N> {
+		let mut rbuf = StaticRingBuffer::new();
+		rbuf.extend(self.iter().cloned());
+		rbuf
+	}
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for StaticRingBuffer<T, N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_list().entries(self.iter()).finish()
+	}
+}
+
+impl<T, const N: usize> Extend<T> for StaticRingBuffer<T, N> {
+	/// Appends the elements of the iterator.
+	///
+	/// # Panics
+	///
+	/// Panics if the ring buffer becomes full, the elements appended so far are kept.
+	#[inline]
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+// Iterator over the two slices of a ring buffer
+struct Iter<'a, T> {
+	first: slice::Iter<'a, T>,
+	second: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+	#[inline]
+	fn clone(&self) -> Self {
+		Iter { first: self.first.clone(), second: self.second.clone() }
+	}
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+	type Item = &'a T;
+	#[inline]
+	fn next(&mut self) -> Option<&'a T> {
+		match self.first.next() {
+			Some(value) => Some(value),
+			None => {
+				mem::swap(&mut self.first, &mut self.second);
+				self.first.next()
+			},
+		}
+	}
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = self.first.len() + self.second.len();
+		(len, Some(len))
+	}
+}
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+	#[inline]
+	fn next_back(&mut self) -> Option<&'a T> {
+		match self.second.next_back() {
+			Some(value) => Some(value),
+			None => self.first.next_back(),
+		}
+	}
+}
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
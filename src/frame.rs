@@ -0,0 +1,150 @@
+/*!
+Length prefixed framing.
+*/
+
+use core::{fmt, slice};
+use core::convert::{TryFrom, TryInto};
+#[cfg(feature = "std")]
+use std::error;
+
+use super::{bytes, RingBuffer};
+
+/// Error decoding a frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameError {
+	/// The frame contains an invalid encoding.
+	Malformed,
+	/// The decoded frame does not fit in the destination buffer.
+	Overflow,
+}
+
+impl fmt::Display for FrameError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			FrameError::Malformed => "malformed frame",
+			FrameError::Overflow => "frame too large for buffer",
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FrameError {}
+
+/// Encoding of the length prefix of a frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LengthPrefix {
+	/// Single byte length.
+	U8,
+	/// Little endian 16-bit length.
+	U16Le,
+	/// Big endian 16-bit length.
+	U16Be,
+	/// Little endian 32-bit length.
+	U32Le,
+	/// Big endian 32-bit length.
+	U32Be,
+	/// Unsigned LEB128 variable length integer.
+	Varint,
+}
+
+impl LengthPrefix {
+	/// Returns the largest frame length which can be encoded with this prefix.
+	#[inline]
+	pub fn max_len(self) -> usize {
+		match self {
+			LengthPrefix::U8 => u8::MAX as usize,
+			LengthPrefix::U16Le | LengthPrefix::U16Be => u16::MAX as usize,
+			LengthPrefix::U32Le | LengthPrefix::U32Be => u32::MAX as usize,
+			LengthPrefix::Varint => usize::MAX,
+		}
+	}
+
+	// Encodes the length prefix, returns the number of bytes written
+	fn encode(self, len: usize, dst: &mut [u8; bytes::VARINT_MAX]) -> usize {
+		if len > self.max_len() {
+			invalid_frame_len(len, self);
+		}
+		match self {
+			LengthPrefix::U8 => { dst[0] = len as u8; 1 },
+			LengthPrefix::U16Le => { dst[..2].copy_from_slice(&(len as u16).to_le_bytes()); 2 },
+			LengthPrefix::U16Be => { dst[..2].copy_from_slice(&(len as u16).to_be_bytes()); 2 },
+			LengthPrefix::U32Le => { dst[..4].copy_from_slice(&(len as u32).to_le_bytes()); 4 },
+			LengthPrefix::U32Be => { dst[..4].copy_from_slice(&(len as u32).to_be_bytes()); 4 },
+			LengthPrefix::Varint => bytes::encode_varint(len as u64, dst),
+		}
+	}
+
+	// Decodes the length prefix, returns the size of the prefix and the frame length
+	fn decode(self, src: &[u8]) -> Result<Option<(usize, usize)>, FrameError> {
+		fn array<const N: usize>(src: &[u8]) -> Option<[u8; N]> {
+			src.get(..N).map(|bytes| bytes.try_into().unwrap())
+		}
+		Ok(match self {
+			LengthPrefix::U8 => src.first().map(|&len| (1, len as usize)),
+			LengthPrefix::U16Le => array(src).map(|bytes| (2, u16::from_le_bytes(bytes) as usize)),
+			LengthPrefix::U16Be => array(src).map(|bytes| (2, u16::from_be_bytes(bytes) as usize)),
+			LengthPrefix::U32Le => array(src).map(|bytes| (4, u32::from_le_bytes(bytes) as usize)),
+			LengthPrefix::U32Be => array(src).map(|bytes| (4, u32::from_be_bytes(bytes) as usize)),
+			LengthPrefix::Varint => match bytes::decode_varint(src)? {
+				Some((n, len)) => match usize::try_from(len) {
+					Ok(len) => Some((n, len)),
+					Err(_) => return Err(FrameError::Overflow),
+				},
+				None => None,
+			},
+		})
+	}
+}
+
+#[cold]
+#[track_caller]
+fn invalid_frame_len(len: usize, prefix: LengthPrefix) -> ! {
+	panic!("frame length {} does not fit in {:?} prefix", len, prefix)
+}
+
+impl RingBuffer<u8> {
+	/// Appends a frame with a length prefix.
+	///
+	/// # Panics
+	///
+	/// Panics if the length of the frame exceeds the [`max_len`](LengthPrefix::max_len) of the prefix.
+	pub fn write_frame(&mut self, prefix: LengthPrefix, frame: &[u8]) {
+		let mut header = [0u8; bytes::VARINT_MAX];
+		let header_len = prefix.encode(frame.len(), &mut header);
+		self.reserve(header_len + frame.len());
+		self.extend_from_slice(&header[..header_len]);
+		self.extend_from_slice(frame);
+	}
+
+	/// Returns the next complete length prefixed frame without consuming it.
+	///
+	/// Returns `Ok(None)` if the ring buffer does not contain a complete frame yet.
+	/// Returns an error if the length prefix is malformed or does not fit in `usize`.
+	pub fn read_frame(&self, prefix: LengthPrefix) -> Result<Option<&[u8]>, FrameError> {
+		let data = self.as_slice();
+		Ok(match prefix.decode(data)? {
+			Some((header_len, len)) if data.len() - header_len >= len => Some(&data[header_len..header_len + len]),
+			_ => None,
+		})
+	}
+
+	/// Consumes the next complete length prefixed frame from the tail.
+	///
+	/// The returned frame remains valid until the ring buffer is modified again.
+	///
+	/// Returns `Ok(None)` if the ring buffer does not contain a complete frame yet.
+	/// Returns an error if the length prefix is malformed or does not fit in `usize`, nothing is consumed.
+	pub fn take_frame(&mut self, prefix: LengthPrefix) -> Result<Option<&[u8]>, FrameError> {
+		let (header_len, len) = match prefix.decode(self.as_slice())? {
+			Some((header_len, len)) if self.len() - header_len >= len => (header_len, len),
+			_ => return Ok(None),
+		};
+		unsafe {
+			let ptr = self.as_ptr().add(header_len);
+			// Discarding bytes from the tail without wiping them does not touch the memory,
+			// the frame stays in place for as long as the ring buffer is borrowed
+			self.discard(header_len + len, false);
+			Ok(Some(slice::from_raw_parts(ptr, len)))
+		}
+	}
+}
@@ -0,0 +1,47 @@
+/*!
+Reallocation behavior of growing ring buffers.
+*/
+
+use core::cmp;
+
+/// Decides how much a [`RingBuffer`](super::RingBuffer) grows when it runs out of room.
+///
+/// Every policy grows to at least the required capacity and the result is rounded up to the system's allocation granularity.
+/// [`reserve_exact`](super::RingBuffer::reserve_exact) ignores the policy and never deliberately over-allocates.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum GrowthPolicy {
+	/// Grows to exactly the required capacity.
+	///
+	/// Keeps memory use low for ring buffers which rarely grow, at the cost of reallocating on every growth.
+	Exact,
+	/// At least doubles the capacity, pushing elements is amortized O(1).
+	#[default]
+	Doubling,
+	/// Grows by at least the given number of pages of the allocation granularity.
+	///
+	/// Bounds the unused capacity for large ring buffers, growing is amortized O(n) like with [`Exact`](Self::Exact) but reallocates less often.
+	Pages(usize),
+	/// Computes the new capacity in elements from the current and the required capacity in elements.
+	///
+	/// Returning less than the required capacity grows to the required capacity.
+	Custom(fn(capacity: usize, required: usize) -> usize),
+}
+
+impl GrowthPolicy {
+	// New capacity in elements when growing beyond `capacity` to hold `required` elements of `size_of` bytes in pages of `granularity` bytes
+	pub(crate) fn grow(self, capacity: usize, required: usize, size_of: usize, granularity: usize) -> usize {
+		// Don't let growing exceed system limits when the required capacity would fit
+		let limit = isize::MAX as usize / 4 / size_of;
+		let grown = match self {
+			GrowthPolicy::Exact => required,
+			GrowthPolicy::Doubling => capacity * 2,
+			GrowthPolicy::Pages(pages) => {
+				let step = cmp::max(pages, 1).saturating_mul(granularity);
+				let bytes = (capacity * size_of).saturating_add(step);
+				bytes / size_of
+			},
+			GrowthPolicy::Custom(f) => f(capacity, required),
+		};
+		cmp::max(required, cmp::min(grown, limit))
+	}
+}
@@ -0,0 +1,53 @@
+/*!
+Validating the headers of ring buffers in files and shared memory.
+*/
+
+use std::{error, fmt, io};
+
+/// Error validating the header of a file-backed or shared ring buffer.
+///
+/// Opening such a ring buffer returns it wrapped in an [`io::Error`] of kind [`InvalidData`](io::ErrorKind::InvalidData),
+/// get it back with `err.get_ref().and_then(|err| err.downcast_ref::<HeaderError>())`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HeaderError {
+	/// The magic number is missing, the memory does not hold a ring buffer of this kind.
+	Magic,
+	/// The layout version is not supported.
+	Version {
+		expected: u64,
+		found: u64,
+	},
+	/// The ring buffer was created with elements of a different size.
+	ElementSize {
+		expected: usize,
+		found: usize,
+	},
+	/// The capacity in bytes recorded in the header does not match the size of the mapping.
+	Capacity {
+		expected: usize,
+		found: usize,
+	},
+	/// The positions of the head and tail are corrupted.
+	Corrupted,
+}
+
+impl fmt::Display for HeaderError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			HeaderError::Magic => f.write_str("not a ring buffer"),
+			HeaderError::Version { expected, found } => write!(f, "unsupported ring buffer version {} (expected {})", found, expected),
+			HeaderError::ElementSize { expected, found } => write!(f, "ring buffer element size {} (expected {})", found, expected),
+			HeaderError::Capacity { expected, found } => write!(f, "ring buffer capacity {} (expected {})", found, expected),
+			HeaderError::Corrupted => f.write_str("corrupted ring buffer"),
+		}
+	}
+}
+
+impl error::Error for HeaderError {}
+
+impl From<HeaderError> for io::Error {
+	#[inline]
+	fn from(err: HeaderError) -> io::Error {
+		io::Error::new(io::ErrorKind::InvalidData, err)
+	}
+}
@@ -0,0 +1,90 @@
+/*!
+Ring buffers in statics.
+*/
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, OnceLock, TryLockError};
+
+use super::RingBuffer;
+
+/// Ring buffer in a static which is constructed on first use, for flight recorders logging from anywhere in the program.
+///
+/// The ring buffer is behind a mutex, [`lock`](Self::lock) it to push and pop elements.
+/// Panic hooks and other code which may run while the current thread holds the lock use [`try_lock`](Self::try_lock), which never blocks.
+///
+/// ```
+/// use vringbuf::{LazyRing, RingBuffer};
+///
+/// // Keep the last 64 KiB of log messages
+/// static LOG: LazyRing<u8> = LazyRing::new(|| {
+/// 	let mut rbuf = RingBuffer::with_capacity(65536);
+/// 	rbuf.set_capacity_limit(65536);
+/// 	rbuf
+/// });
+///
+/// fn log(msg: &str) {
+/// 	let mut log = LOG.lock();
+/// 	let room = log.capacity() - log.len();
+/// 	if room < msg.len() {
+/// 		log.remove_tail(msg.len() - room);
+/// 	}
+/// 	log.extend_from_slice(msg.as_bytes());
+/// }
+///
+/// std::panic::set_hook(Box::new(|info| {
+/// 	if let Some(log) = LOG.try_lock() {
+/// 		eprintln!("{}\n{}", String::from_utf8_lossy(&log), info);
+/// 	}
+/// }));
+/// log("started\n");
+/// # let _ = std::panic::take_hook();
+/// ```
+pub struct LazyRing<T, F = fn() -> RingBuffer<T>> {
+	ring: OnceLock<Mutex<RingBuffer<T>>>,
+	init: F,
+}
+
+impl<T, F: Fn() -> RingBuffer<T>> LazyRing<T, F> {
+	/// Constructs a lazily constructed ring buffer, `init` constructs it on first use.
+	#[inline]
+	pub const fn new(init: F) -> LazyRing<T, F> {
+		LazyRing { ring: OnceLock::new(), init }
+	}
+
+	/// Locks the ring buffer, constructing it on first use.
+	///
+	/// Blocks until the lock is available. Locking is not poisoned by threads which panicked while holding the lock,
+	/// the ring buffer stays in a valid state when its methods panic.
+	#[inline]
+	pub fn lock(&self) -> MutexGuard<'_, RingBuffer<T>> {
+		let ring = self.ring.get_or_init(|| Mutex::new((self.init)()));
+		ring.lock().unwrap_or_else(|err| err.into_inner())
+	}
+
+	/// Locks the ring buffer without blocking.
+	///
+	/// Returns `None` if the lock is held elsewhere, including by the current thread, or the ring buffer has not been constructed yet.
+	/// Safe to call from panic hooks, it neither blocks nor constructs the ring buffer.
+	#[inline]
+	pub fn try_lock(&self) -> Option<MutexGuard<'_, RingBuffer<T>>> {
+		match self.ring.get()?.try_lock() {
+			Ok(guard) => Some(guard),
+			Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+			Err(TryLockError::WouldBlock) => None,
+		}
+	}
+
+	/// Returns whether the ring buffer has been constructed.
+	#[inline]
+	pub fn is_initialized(&self) -> bool {
+		self.ring.get().is_some()
+	}
+}
+
+impl<T, F> fmt::Debug for LazyRing<T, F> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("LazyRing")
+			.field("initialized", &self.ring.get().is_some())
+			.finish()
+	}
+}
@@ -1,15 +1,163 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(core_io_borrowed_buf, read_buf))]
+#![allow(clippy::tabs_in_doc_comments)]
+
 /*!
+Ring buffer backed by mirrored virtual memory.
+
+The backing memory is mapped twice back to back so the contents of the ring buffer are always available as a single contiguous slice.
+
+WebAssembly has no virtual memory to mirror, on `wasm32` targets the ring buffer falls back to allocating twice its capacity
+and copies its elements back to the start when they wrap around.
+The `fallback-alloc` feature uses the same fallback on other targets without a mirrored backend instead of failing to compile.
+The shared memory, file backed and concurrent ring buffers are not available with the fallback.
+Miri runs with the fallback as well, it cannot map memory twice.
+
+The memory comes from the operating system through [`OsMemory`], implement [`MirroredMemory`] to back ring buffers with memory from elsewhere.
+Targets without virtual memory use [`StaticRingBuffer`] instead, a fixed capacity ring buffer stored inline whose elements wrap around as two slices,
+both implement [`Ring`] for code which runs on either.
+
+# Capacity limits
+
+Every ring buffer takes twice its capacity in contiguous address space and the capacity in bytes must stay below `isize::MAX / 2`.
+On 64-bit targets the address space is practically unlimited and the capacity is bounded by the memory the system is willing to commit.
+On 32-bit targets the capacity cannot exceed 1 GiB and the 2 to 4 GiB of address space of a process is fragmented by libraries and other allocations,
+ring buffers of more than a few hundred MiB are likely to fail to allocate.
+Growing falls back to the required capacity if the capacity of the [growth policy](GrowthPolicy) fails to allocate,
+use the `try_` methods such as [`try_reserve`](RingBuffer::try_reserve) to handle running out of address space.
 
+# Features
+
+* `bytemuck`: Adds `RingBuffer::<u8>::{as_pod_slice, pop_pod, push_pod}` to read and write plain old data types.
+* `capi`: Adds the [`capi`] module of `extern "C"` functions over byte ring buffers for C and C++, declared in `include/vringbuf.h`.
+* `bytes`: Implements [`bytes::Buf`](::bytes::Buf) and [`bytes::BufMut`](::bytes::BufMut) for `RingBuffer<u8>`.
+* `fallback-alloc`: Falls back to heap memory on targets without a mirrored backend, see above.
+* `futures`: Implements `Stream` for [`Consumer`](concurrent::Consumer) and `Sink` for [`Producer`](concurrent::Producer).
+* `io-uring`: Adds `RingBuffer::<u8>::{fixed_buffer, register_fixed, read_fixed, write_fixed}` to use the mirrored mapping as an io_uring fixed buffer, Linux only.
+* `cobs`: Adds `RingBuffer::<u8>::pop_cobs_frame` to extract COBS encoded frames.
+* `nightly`: Adds `RingBuffer::<u8>::{fill_reserved, read_buf_from}` to fill the spare capacity through `BorrowedCursor`, requires a nightly compiler.
+* `rayon`: Implements rayon's parallel iterator traits for `RingBuffer<T>`.
+* `sanitize`: Poisons the memory outside of the elements and the room reserved for more with AddressSanitizer,
+  out of bounds accesses through raw pointers are reported in test runs with `-Zsanitizer=address`, which the feature requires to link.
+* `std` (default): Adds the file backed, shared memory and concurrent ring buffers, pools, [`LazyRing`] and everything else which needs the standard library.
+  Without it the crate is `no_std` and needs only `alloc`, ring buffers are available with the Unix backend on Linux and the BSDs and with custom [`MirroredMemory`] backends.
+* `slip`: Adds `RingBuffer::<u8>::pop_slip_frame` to extract SLIP encoded frames.
+* `winnow`: Adds `RingBuffer::<u8>::parse_partial` for incremental parsing with [winnow](https://docs.rs/winnow).
+* `zeroize`: Wipes the memory of elements when they are popped, removed or dropped and the whole mapping before it is freed,
+  implements [`Zeroize`](::zeroize::Zeroize) for `RingBuffer<T>` for ring buffers holding secrets.
+  Frames borrowed from `take_frame` and `parse_partial`, records and lines borrowed from `records` and `lines` and bytes spliced into pipes stay in place until they are overwritten or the mapping is freed,
+  the pages of mappings shared with other ring buffers are wiped for all of them.
 */
 
-use std::{borrow, cmp, hint, iter, mem, ops, ptr, slice};
-use std::ptr::NonNull;
+extern crate alloc;
+
+use core::{borrow, cmp, hint, iter, mem, ops, ptr, slice};
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::io;
 
 mod platform;
+#[cfg(all(feature = "std", not(any(target_os = "macos", target_os = "ios", target_os = "fuchsia", heap_fallback))))]
+mod backing;
+mod bytes;
+#[cfg(all(feature = "std", unix, not(heap_fallback)))]
+mod splice;
+#[cfg(all(feature = "std", not(heap_fallback)))]
+mod file;
+#[cfg(all(feature = "std", not(heap_fallback)))]
+pub use self::file::FileRingBuffer;
+
+#[cfg(feature = "std")]
+mod header;
+#[cfg(feature = "std")]
+pub use self::header::HeaderError;
+
+mod error;
+pub use self::error::{InvariantError, PlatformError, TryReserveError};
+
+mod growth;
+pub use self::growth::GrowthPolicy;
+
+mod memory;
+pub use self::memory::{MirroredMemory, OsMemory};
+
+mod builder;
+pub use self::builder::RingBufferBuilder;
+
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+pub use self::pool::{PoolStats, RingBufferPool};
+
+mod fixed;
+pub use self::fixed::{Ring, StaticRingBuffer};
+
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+pub use self::lazy::LazyRing;
+
+#[cfg(all(feature = "std", not(heap_fallback)))]
+pub mod concurrent;
+pub use self::bytes::{Lines, Records};
+
+#[cfg(feature = "bytes")]
+mod codec;
+
+mod frame;
+pub use self::frame::{FrameError, LengthPrefix};
+
+mod drain;
+pub use self::drain::Drain;
+
+#[cfg(all(feature = "std", not(heap_fallback)))]
+mod shared;
+#[cfg(all(feature = "std", not(heap_fallback)))]
+pub use self::shared::{SharedHeader, SharedRingBuffer};
+
+#[cfg(any(feature = "cobs", feature = "slip"))]
+mod serial;
+
+#[cfg(feature = "winnow")]
+mod parse;
+
+#[cfg(feature = "bytemuck")]
+mod pod;
+
+#[cfg(feature = "rayon")]
+mod par;
+
+#[cfg(all(feature = "io-uring", feature = "std", target_os = "linux", not(heap_fallback)))]
+mod uring;
+
+#[cfg(feature = "nightly")]
+mod cursor;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "zeroize")]
+mod wipe;
+
+#[cfg(feature = "sanitize")]
+mod sanitize;
+
+/// Returns the number of times mapping a ring buffer lost the race for its address to another thread and was retried.
+///
+/// Only Windows before Windows 10 version 1803 maps ring buffers in a way which races, see [`RingBufferBuilder::map_attempts`].
+/// Always 0 on other platforms.
+#[inline]
+pub fn map_retries() -> u64 {
+	platform::map_retries()
+}
 
 /// Ring buffer backed by mirrored virtual memory.
+///
+/// The memory comes from the operating system unless the ring buffer is constructed [with another backend](Self::new_in).
+///
+/// The mapping is aligned to the page size, element types aligned to more than 4 KiB are rejected at compile time.
 #[derive(Debug)]
-pub struct RingBuffer<T> {
+pub struct RingBuffer<T, M: MirroredMemory = OsMemory> {
 	ptr: NonNull<T>,
 	// Capacity of the ring buffer in bytes.
 	// Twice as much virtual memory has been allocated.
@@ -20,6 +168,15 @@ pub struct RingBuffer<T> {
 	base: usize,
 	// Number of valid elements in the ring buffer (in # elements).
 	len: usize,
+	// Memory object backing the mapping, kept open so the mapping can be shared.
+	// None if nothing has been allocated.
+	handle: Option<M::Handle>,
+	// How much to grow when reserving more room.
+	growth: GrowthPolicy,
+	// Maximum capacity to grow to (in # elements).
+	limit: usize,
+	// Backend mapping the memory, the OS backend keeps the options for allocating the mapping when reallocating.
+	memory: M,
 }
 
 impl<T> RingBuffer<T> {
@@ -28,7 +185,7 @@ impl<T> RingBuffer<T> {
 	/// The ring buffer will not allocate until elements are pushed onto it.
 	#[inline]
 	pub const fn new() -> RingBuffer<T> {
-		RingBuffer { ptr: NonNull::dangling(), cap: 0, base: 0, len: 0 }
+		RingBuffer::new_in(OsMemory::new())
 	}
 
 	/// Constructs a new, empty `RingBuffer<T>` with the specified capacity.
@@ -44,14 +201,272 @@ impl<T> RingBuffer<T> {
 	/// Panics if the capacity exceeds system limits or there is not enough contigious memory for 2x the requested capacity.
 	#[inline]
 	pub fn with_capacity(capacity: usize) -> RingBuffer<T> {
-		if capacity == 0 {
-			return RingBuffer::new();
+		match RingBuffer::try_with_capacity(capacity) {
+			Ok(rb) => rb,
+			Err(err) => platform::alloc_failed(err),
 		}
+	}
 
-		let (ptr, cap) = unsafe { platform::allocate(capacity, mem::size_of::<T>()) };
-		let ptr = ptr.cast();
+	/// Constructs a new, empty `RingBuffer<T>` with the specified capacity.
+	///
+	/// Like [`with_capacity`](Self::with_capacity) but returns an error instead of panicking if the allocation fails.
+	#[inline]
+	pub fn try_with_capacity(capacity: usize) -> Result<RingBuffer<T>, TryReserveError> {
+		RingBuffer::try_with_capacity_in(capacity, OsMemory::new())
+	}
 
-		RingBuffer { ptr, cap, base: 0, len: 0 }
+	/// Decomposes a ring buffer into its raw parts without freeing its mapping.
+	///
+	/// Returns the pointer to the mapping, its capacity in bytes, the offset of the first element in bytes, the number of elements
+	/// and the memory object backing the mapping, `None` if nothing has been allocated.
+	/// Reconstruct the ring buffer with [`from_raw_parts`](Self::from_raw_parts), or free the elements and the mapping by other means.
+	///
+	/// The growth policy, capacity limit and the options the ring buffer was [built](Self::builder) with are not part of the raw parts,
+	/// use [`into_raw_parts_with_memory`](Self::into_raw_parts_with_memory) to keep the options.
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::<u32>::new();
+	/// rbuf.extend(0..10);
+	/// rbuf.remove_tail(3);
+	///
+	/// let (ptr, cap, base, len, handle) = rbuf.into_raw_parts();
+	/// let rbuf = unsafe { RingBuffer::from_raw_parts(ptr, cap, base, len, handle) };
+	/// assert_eq!(rbuf.as_slice(), &[3, 4, 5, 6, 7, 8, 9]);
+	/// ```
+	#[inline]
+	pub fn into_raw_parts(self) -> (*mut T, usize, usize, usize, Option<platform::Handle>) {
+		let (ptr, cap, base, len, handle, _) = self.into_raw_parts_with_memory();
+		(ptr, cap, base, len, handle)
+	}
+
+	/// Reconstructs a ring buffer from its raw parts, see [`into_raw_parts`](Self::into_raw_parts).
+	///
+	/// The ring buffer has the default growth policy and options.
+	///
+	/// # Safety
+	///
+	/// The raw parts must have been returned by `into_raw_parts` of a ring buffer of the same element type,
+	/// with the elements still initialized, and are not used to reconstruct another ring buffer.
+	/// The ring buffer must not have been built with guard pages, their memory would not be freed.
+	#[inline]
+	pub unsafe fn from_raw_parts(ptr: *mut T, cap: usize, base: usize, len: usize, handle: Option<platform::Handle>) -> RingBuffer<T> {
+		RingBuffer::from_raw_parts_in(ptr, cap, base, len, handle, OsMemory::new())
+	}
+
+	/// Constructs a new, empty `RingBuffer<T>` on top of mirrored memory the caller already mapped, such as a region double mapped by a driver or hypervisor.
+	///
+	/// The ring buffer never unmaps the memory. When it grows it moves its elements to memory mapped by the operating system and stops using the caller's memory.
+	/// To free the memory when the ring buffer is dropped implement [`MirroredMemory`] for it instead,
+	/// and pass its handle to [`from_raw_parts_in`](RingBuffer::from_raw_parts_in).
+	///
+	/// # Safety
+	///
+	/// `ptr` is aligned for `T` and points to `2 * cap` readable and writable bytes where the second half mirrors the first,
+	/// as [`MirroredMemory`] requires, which stay valid for as long as the ring buffer uses them.
+	/// `cap` is non-zero and below `isize::MAX / 2`.
+	#[inline]
+	pub unsafe fn from_mirrored_ptr(ptr: *mut T, cap: usize) -> RingBuffer<T> {
+		RingBuffer::from_raw_parts(ptr, cap, 0, 0, None)
+	}
+
+	/// Returns the size of the pages backing the ring buffer.
+	///
+	/// Larger than the allocation granularity if the ring buffer was [built](Self::builder) with huge pages and the system had them available.
+	#[inline]
+	pub fn page_size(&self) -> usize {
+		platform::page_size(self.ptr.cast(), self.raw_handle())
+	}
+
+	/// Returns the system's allocation granularity, the capacity of ring buffers in bytes is rounded up to a multiple of it.
+	///
+	/// The page size on Unix and 64 KiB on Windows.
+	/// Ring buffers [built](Self::builder) with huge pages are rounded up to the huge page size instead.
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// // Exactly one page of u16s
+	/// let capacity = RingBuffer::<u16>::allocation_granularity() / 2;
+	/// assert_eq!(RingBuffer::<u16>::effective_capacity_for(capacity), Some(capacity));
+	/// assert_eq!(RingBuffer::<u16>::with_capacity(capacity).capacity(), capacity);
+	/// ```
+	#[inline]
+	pub fn allocation_granularity() -> usize {
+		platform::granularity()
+	}
+
+	/// Returns the capacity a ring buffer ends up with when asked for `n` elements, see [`with_capacity`](Self::with_capacity).
+	///
+	/// Returns `None` if the capacity exceeds system limits.
+	#[inline]
+	pub fn effective_capacity_for(n: usize) -> Option<usize> {
+		if n == 0 {
+			return Some(0);
+		}
+		platform::try_round_capacity(n, mem::size_of::<T>(), platform::granularity()).map(|cap| cap / mem::size_of::<T>())
+	}
+
+	/// Returns the name the ring buffer was [built](RingBuffer::builder) with, see [`name`](RingBufferBuilder::name).
+	#[inline]
+	pub fn name(&self) -> Option<&str> {
+		self.memory.options.name.as_deref()
+	}
+
+	/// Returns whether the mapping has the transparent huge page hint applied.
+	///
+	/// False unless the ring buffer was [built](RingBuffer::builder) with [`transparent_huge_pages`](RingBufferBuilder::transparent_huge_pages)
+	/// and the system accepted the hint, the kernel may still back it with regular pages.
+	#[inline]
+	pub fn transparent_huge_pages(&self) -> bool {
+		platform::huge_page_hint(self.ptr.cast(), self.raw_handle())
+	}
+
+	/// Locks the memory of the ring buffer into physical memory, pushing and popping elements never page faults.
+	///
+	/// The ring buffer stays locked when it reallocates, growing returns an error if the new mapping cannot be locked.
+	/// Locking is limited by `RLIMIT_MEMLOCK` on Unix and the minimum working set size of the process on Windows.
+	/// Both views of the mirrored mapping count towards these limits.
+	#[cfg(feature = "std")]
+	pub fn lock_memory(&mut self) -> io::Result<()> {
+		if self.cap != 0 {
+			unsafe { platform::lock(self.ptr.cast(), self.cap)?; }
+		}
+		self.memory.options.lock_memory = true;
+		Ok(())
+	}
+
+	/// Unlocks the memory of the ring buffer, see [`lock_memory`](Self::lock_memory).
+	pub fn unlock_memory(&mut self) {
+		if self.memory.options.lock_memory && self.cap != 0 {
+			unsafe { platform::unlock(self.ptr.cast(), self.cap); }
+		}
+		self.memory.options.lock_memory = false;
+	}
+
+	/// Faults in all pages of the ring buffer so pushing and popping elements does not page fault on first use.
+	///
+	/// Pages can be faulted out again unless the memory is [locked](Self::lock_memory),
+	/// build the ring buffer with [`prefault`](RingBufferBuilder::prefault) to fault in the pages whenever it reallocates.
+	pub fn prefault(&mut self) {
+		if self.cap != 0 {
+			// Inaccessible pages cannot be faulted in
+			self.protect(self.reserved_len());
+			unsafe { platform::prefault(self.ptr.cast(), self.cap); }
+		}
+	}
+
+	/// Returns whether the memory of the ring buffer is locked, see [`lock_memory`](Self::lock_memory).
+	#[inline]
+	pub fn is_memory_locked(&self) -> bool {
+		self.memory.options.lock_memory
+	}
+
+	// Memory object backing the mapping, the null handle if nothing has been allocated
+	#[inline]
+	fn raw_handle(&self) -> platform::Handle {
+		self.handle.unwrap_or(platform::NULL_HANDLE)
+	}
+}
+
+impl<T, M: MirroredMemory> RingBuffer<T, M> {
+	/// Constructs a new, empty `RingBuffer<T, M>` which maps its memory with the given backend.
+	///
+	/// The ring buffer will not allocate until elements are pushed onto it.
+	#[inline]
+	pub const fn new_in(memory: M) -> RingBuffer<T, M> {
+		RingBuffer { ptr: NonNull::dangling(), cap: 0, base: 0, len: 0, handle: None, growth: GrowthPolicy::Doubling, limit: usize::MAX, memory }
+	}
+
+	/// Constructs a new, empty `RingBuffer<T, M>` with the specified capacity which maps its memory with the given backend.
+	///
+	/// The capacity in bytes is rounded up to the granularity of the backend, see [`with_capacity`](RingBuffer::with_capacity).
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits or the backend fails to allocate.
+	#[inline]
+	pub fn with_capacity_in(capacity: usize, memory: M) -> RingBuffer<T, M> {
+		match RingBuffer::try_with_capacity_in(capacity, memory) {
+			Ok(rb) => rb,
+			Err(err) => platform::alloc_failed(err),
+		}
+	}
+
+	/// Constructs a new, empty `RingBuffer<T, M>` with the specified capacity which maps its memory with the given backend.
+	///
+	/// Like [`with_capacity_in`](Self::with_capacity_in) but returns an error instead of panicking if the allocation fails.
+	pub fn try_with_capacity_in(capacity: usize, memory: M) -> Result<RingBuffer<T, M>, TryReserveError> {
+		let mut rb = RingBuffer::new_in(memory);
+		if capacity != 0 {
+			let (ptr, cap, handle) = rb.allocate(capacity)?;
+			rb.ptr = ptr.cast();
+			rb.cap = cap;
+			rb.handle = Some(handle);
+		}
+		Ok(rb)
+	}
+
+	/// Decomposes a ring buffer into its raw parts and its backend without freeing its mapping, see [`into_raw_parts`](RingBuffer::into_raw_parts).
+	///
+	/// Access to all of the capacity is restored if the backend [protects](MirroredMemory::protect) the unused capacity.
+	/// Reconstruct the ring buffer with [`from_raw_parts_in`](Self::from_raw_parts_in).
+	#[allow(clippy::type_complexity)]
+	pub fn into_raw_parts_with_memory(self) -> (*mut T, usize, usize, usize, Option<M::Handle>, M) {
+		let rbuf = mem::ManuallyDrop::new(self);
+		// The owner of the raw parts may access all of the capacity
+		rbuf.protect(rbuf.reserved_len());
+		let memory = unsafe { ptr::read(&rbuf.memory) };
+		(rbuf.ptr.as_ptr(), rbuf.cap, rbuf.base, rbuf.len, rbuf.handle, memory)
+	}
+
+	/// Reconstructs a ring buffer from its raw parts and its backend, see [`into_raw_parts_with_memory`](Self::into_raw_parts_with_memory).
+	///
+	/// The ring buffer has the default growth policy and no capacity limit.
+	///
+	/// # Safety
+	///
+	/// The raw parts must have been returned by `into_raw_parts_with_memory` of a ring buffer of the same element type and backend,
+	/// with the elements still initialized, and are not used to reconstruct another ring buffer.
+	pub unsafe fn from_raw_parts_in(ptr: *mut T, cap: usize, base: usize, len: usize, handle: Option<M::Handle>, memory: M) -> RingBuffer<T, M> {
+		let rbuf = RingBuffer {
+			ptr: NonNull::new_unchecked(ptr),
+			cap,
+			base,
+			len,
+			handle,
+			growth: GrowthPolicy::Doubling,
+			limit: usize::MAX,
+			memory,
+		};
+		rbuf.assert_invariants();
+		rbuf
+	}
+
+	/// Consumes and leaks the ring buffer, returning a mutable reference to its contents.
+	///
+	/// Like [`Vec::leak`](alloc::vec::Vec::leak) the mapping and the memory object backing it are never freed,
+	/// for data which lives for the remainder of the program.
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::<u8>::new();
+	/// rbuf.extend_from_slice(b"config");
+	/// let config: &'static mut [u8] = rbuf.leak();
+	/// assert_eq!(config, b"config");
+	/// ```
+	#[inline]
+	pub fn leak<'a>(self) -> &'a mut [T] where M: 'a {
+		let mut rbuf = mem::ManuallyDrop::new(self);
+		unsafe { slice::from_raw_parts_mut(rbuf.as_mut_ptr(), rbuf.len) }
+	}
+
+	/// Returns the backend mapping the memory of the ring buffer.
+	#[inline]
+	pub fn memory(&self) -> &M {
+		&self.memory
 	}
 
 	/// Returns the number of elements the ring buffer can hold without reallocating.
@@ -60,6 +475,35 @@ impl<T> RingBuffer<T> {
 		self.cap / mem::size_of::<T>()
 	}
 
+	/// Returns how the ring buffer grows when it runs out of room.
+	#[inline]
+	pub fn growth_policy(&self) -> GrowthPolicy {
+		self.growth
+	}
+
+	/// Sets how the ring buffer grows when it runs out of room, the default doubles the capacity.
+	#[inline]
+	pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+		self.growth = policy;
+	}
+
+	/// Returns the maximum number of elements the ring buffer may grow to hold, `usize::MAX` if unbounded.
+	#[inline]
+	pub fn capacity_limit(&self) -> usize {
+		self.limit
+	}
+
+	/// Limits the number of elements the ring buffer may grow to hold, to enforce a memory budget.
+	///
+	/// Growing beyond the limit fails, the `try_` methods like [`try_push`](Self::try_push) return [`TryReserveError::LimitExceeded`] and the other methods panic.
+	/// The capacity is rounded to the allocation granularity without exceeding the limit, a limit below the granularity prevents allocating at all.
+	///
+	/// A ring buffer which already holds more capacity keeps it.
+	#[inline]
+	pub fn set_capacity_limit(&mut self, limit: usize) {
+		self.limit = limit;
+	}
+
 	/// Returns the number of elements in the ring buffer, also referred to as its ‘length’.
 	#[inline]
 	pub fn len(&self) -> usize {
@@ -126,10 +570,11 @@ impl<T> RingBuffer<T> {
 	/// The returned slice can be used to fill the vector with data (e.g. by reading from a socket)
 	/// before marking the data as initialized using the [`add_len`](Self::add_len) method.
 	pub fn reserved_mut(&mut self) -> &mut [mem::MaybeUninit<T>] {
+		self.protect(self.reserved_len());
 		unsafe {
 			let ptr = self.reserved_ptr();
 			let len = self.reserved_len();
-			mem::transmute(ptr::slice_from_raw_parts_mut(ptr, len))
+			&mut *(ptr::slice_from_raw_parts_mut(ptr, len) as *mut [mem::MaybeUninit<T>])
 		}
 	}
 
@@ -145,6 +590,8 @@ impl<T> RingBuffer<T> {
 	#[inline]
 	pub unsafe fn add_len(&mut self, additional: usize) {
 		self.len += additional;
+		self.unpoison();
+		self.assert_invariants();
 	}
 
 	/// Forces the length of the ring buffer to `new_len`.
@@ -159,17 +606,37 @@ impl<T> RingBuffer<T> {
 	#[inline]
 	pub unsafe fn set_len(&mut self, new_len: usize) {
 		self.len = new_len;
+		self.unpoison();
+		self.assert_invariants();
+	}
+
+	/// Checks the internal invariants of the ring buffer.
+	///
+	/// Code using [`set_len`](Self::set_len) and [`add_len`](Self::add_len) can sanity check its bookkeeping during development,
+	/// debug builds of this crate check the invariants whenever the length or the mapping changes and panic on violations.
+	/// Returns an error if the length exceeds the capacity or the start of the ring buffer lies outside the first half of the mapping.
+	pub fn validate(&self) -> Result<(), InvariantError> {
+		if self.len > self.capacity() {
+			return Err(InvariantError::Length { len: self.len, capacity: self.capacity() });
+		}
+		if self.base >= cmp::max(self.cap, 1) {
+			return Err(InvariantError::Base { base: self.base, size: self.cap });
+		}
+		Ok(())
 	}
 
 	/// Clears the ring buffer, removing all values.
 	///
-	/// Note that this method has no effect on the allocated capacity of the ring buffer.
+	/// Note that this method has no effect on the allocated capacity of the ring buffer,
+	/// [`decommit`](Self::decommit) or [`shrink_to`](Self::shrink_to) afterwards to return the memory of a large ring buffer.
 	pub fn clear(&mut self) {
 		let len = self.len;
 		self.len = 0;
 		unsafe {
 			ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), len).drop_in_place();
 		}
+		self.wipe(self.base, len * mem::size_of::<T>());
+		self.assert_invariants();
 	}
 
 	/// Shortens the ring buffer, keeping the first `len` elements and dropping the rest.
@@ -187,24 +654,34 @@ impl<T> RingBuffer<T> {
 			let s = ptr::slice_from_raw_parts_mut(self.as_mut_ptr().add(len), remaining_len);
 			self.len = len;
 			s.drop_in_place();
+			self.wipe(self.base + len * mem::size_of::<T>(), remaining_len * mem::size_of::<T>());
 		}
+		self.assert_invariants();
 	}
 
 	/// Removes `n` elements from the tail.
+	#[inline]
 	pub fn remove_tail(&mut self, n: usize) {
+		self.discard(n, true);
+	}
+
+	// Removes `n` elements from the tail, their bytes stay in place for frames borrowed from the ring buffer unless they are wiped
+	fn discard(&mut self, n: usize, wipe: bool) {
 		// Keep the method safe by removing max of `len` elements
 		let n = cmp::min(self.len, n);
 		// Pointer to the removed elements
 		let s = ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), n);
 		// Remove the elements first
+		let base = self.base;
 		self.len -= n;
 		self.base += n * mem::size_of::<T>();
 		// Drop the elements
 		unsafe { s.drop_in_place(); }
-		// Adjust the base offset
-		if self.base >= self.cap {
-			self.base -= self.cap;
+		if wipe {
+			self.wipe(base, n * mem::size_of::<T>());
 		}
+		// Adjust the base offset
+		self.wrap_base();
 	}
 
 	/// Appends an element to the front.
@@ -219,6 +696,69 @@ impl<T> RingBuffer<T> {
 			self.as_mut_ptr().add(self.len).write(value);
 			self.len += 1;
 		}
+		self.assert_invariants();
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Like [`push`](Self::push) but returns an error instead of panicking if the ring buffer cannot grow, the element is dropped on error.
+	#[inline]
+	pub fn try_push(&mut self, value: T) -> Result<(), TryReserveError> {
+		self.try_reserve(1)?;
+		unsafe {
+			self.as_mut_ptr().add(self.len).write(value);
+			self.len += 1;
+		}
+		self.assert_invariants();
+		Ok(())
+	}
+
+	/// Appends an element to the front which `f` constructs in place.
+	///
+	/// Large values are written directly into the spare capacity instead of being moved through the stack like with [`push`](Self::push).
+	///
+	/// ```
+	/// use std::mem::MaybeUninit;
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::<[u8; 4096]>::new();
+	/// unsafe {
+	/// 	rbuf.push_with(|slot: &mut MaybeUninit<[u8; 4096]>| {
+	/// 		slot.as_mut_ptr().write_bytes(1, 1);
+	/// 	});
+	/// }
+	/// assert!(rbuf[0].iter().all(|&byte| byte == 1));
+	/// ```
+	///
+	/// # Safety
+	///
+	/// `f` must initialize the element, nothing is appended if it panics.
+	///
+	/// # Panics
+	///
+	/// Panics if the new capacity fails to allocate.
+	#[inline]
+	pub unsafe fn push_with<F: FnOnce(&mut mem::MaybeUninit<T>)>(&mut self, f: F) {
+		self.reserve(1);
+		f(&mut *(self.reserved_ptr() as *mut mem::MaybeUninit<T>));
+		self.len += 1;
+		self.assert_invariants();
+	}
+
+	/// Appends an element to the front which `f` constructs in place.
+	///
+	/// Like [`push_with`](Self::push_with) but returns an error instead of panicking if the ring buffer cannot grow, `f` is not called on error.
+	///
+	/// # Safety
+	///
+	/// `f` must initialize the element, nothing is appended if it panics.
+	#[inline]
+	pub unsafe fn try_push_with<F: FnOnce(&mut mem::MaybeUninit<T>)>(&mut self, f: F) -> Result<(), TryReserveError> {
+		self.try_reserve(1)?;
+		f(&mut *(self.reserved_ptr() as *mut mem::MaybeUninit<T>));
+		self.len += 1;
+		self.assert_invariants();
+		Ok(())
 	}
 
 	/// Returns the back element from a ring buffer and returns it, or [`None`] if it is empty.
@@ -232,10 +772,9 @@ impl<T> RingBuffer<T> {
 			self.len -= 1;
 			self.base += mem::size_of::<T>();
 			let value = s.read();
+			self.wipe(self.base - mem::size_of::<T>(), mem::size_of::<T>());
 			// Adjust the base offset
-			if self.base >= self.cap {
-				self.base -= self.cap;
-			}
+			self.wrap_base();
 			Some(value)
 		}
 	}
@@ -251,6 +790,103 @@ impl<T> RingBuffer<T> {
 			other.as_ptr().copy_to_nonoverlapping(self.as_mut_ptr().add(self.len), other.len());
 			self.len += other.len();
 		}
+		self.assert_invariants();
+	}
+
+	/// Copies and appends all elements in a slice to the `RingBuffer`.
+	///
+	/// Like [`extend_from_slice`](Self::extend_from_slice) but returns an error instead of panicking if the ring buffer cannot grow.
+	/// Nothing is appended on error.
+	#[inline]
+	pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError> where T: Copy {
+		self.try_reserve(other.len())?;
+		unsafe {
+			other.as_ptr().copy_to_nonoverlapping(self.as_mut_ptr().add(self.len), other.len());
+			self.len += other.len();
+		}
+		self.assert_invariants();
+		Ok(())
+	}
+
+	/// Copies elements from the tail into `dst` and removes them, the counterpart of [`extend_from_slice`](Self::extend_from_slice).
+	///
+	/// Returns the number of elements copied, the smaller of [`len`](Self::len) and `dst.len()`.
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::new();
+	/// rbuf.extend_from_slice(b"hello");
+	/// let mut buf = [0; 4];
+	/// assert_eq!(rbuf.read_into(&mut buf), 4);
+	/// assert_eq!(&buf, b"hell");
+	/// assert_eq!(rbuf.read_into(&mut buf), 1);
+	/// assert_eq!(buf[0], b'o');
+	/// ```
+	#[inline]
+	pub fn read_into(&mut self, dst: &mut [T]) -> usize where T: Copy {
+		let n = self.copy_to_slice(dst);
+		self.remove_tail(n);
+		n
+	}
+
+	/// Copies elements from the tail into `dst` without removing them.
+	///
+	/// Returns the number of elements copied, the smaller of [`len`](Self::len) and `dst.len()`.
+	/// Protocols peek at the header of a message before deciding whether all of it is available to [read](Self::read_into).
+	///
+	/// ```
+	/// use vringbuf::RingBuffer;
+	///
+	/// let mut rbuf = RingBuffer::new();
+	/// rbuf.extend_from_slice(&[0, 3, 1, 2]);
+	/// let mut header = [0; 2];
+	/// assert_eq!(rbuf.copy_to_slice(&mut header), 2);
+	/// let len = u16::from_be_bytes(header) as usize;
+	/// assert!(rbuf.len() < 2 + len);
+	/// ```
+	#[inline]
+	pub fn copy_to_slice(&self, dst: &mut [T]) -> usize where T: Copy {
+		let n = cmp::min(self.len, dst.len());
+		dst[..n].copy_from_slice(&self.as_slice()[..n]);
+		n
+	}
+
+	/// Clones and appends all elements in a slice to the `RingBuffer`.
+	///
+	/// Like [`extend_from_slice`](Self::extend_from_slice) for elements which are not `Copy`, such as `String` or `Arc<T>`.
+	/// If cloning an element panics the elements cloned before it are kept.
+	///
+	/// # Panics
+	///
+	/// Panics if the new capacity fails to allocate.
+	#[inline]
+	pub fn extend_from_slice_cloned(&mut self, other: &[T]) where T: Clone {
+		self.reserve(other.len());
+		unsafe { self.write_cloned(other); }
+	}
+
+	/// Clones and appends all elements in a slice to the `RingBuffer`.
+	///
+	/// Like [`extend_from_slice_cloned`](Self::extend_from_slice_cloned) but returns an error instead of panicking if the ring buffer cannot grow.
+	/// Nothing is appended on error.
+	#[inline]
+	pub fn try_extend_from_slice_cloned(&mut self, other: &[T]) -> Result<(), TryReserveError> where T: Clone {
+		self.try_reserve(other.len())?;
+		unsafe { self.write_cloned(other); }
+		Ok(())
+	}
+
+	// Clones the elements into the reserved room, which must fit them
+	unsafe fn write_cloned(&mut self, other: &[T]) where T: Clone {
+		let ptr = self.reserved_ptr();
+		let mut len = SetLenOnDrop::new(&mut self.len);
+		for (i, value) in other.iter().enumerate() {
+			ptr.add(i).write(value.clone());
+			len.local_len += 1;
+		}
+		drop(len);
+		self.assert_invariants();
 	}
 
 	/// Resizes the `RingBuffer` in-place so that `len` is equal to `new_len`.
@@ -269,19 +905,59 @@ impl<T> RingBuffer<T> {
 			let additional = new_len - self.len();
 			self.reserve(additional);
 			unsafe {
-				let mut ptr = self.reserved_ptr();
-				for _ in 0..additional {
-					ptr.write(f());
-					ptr = ptr.add(1);
+				let ptr = self.reserved_ptr();
+				let mut len = SetLenOnDrop::new(&mut self.len);
+				for i in 0..additional {
+					ptr.add(i).write(f());
+					len.local_len += 1;
 				}
-				self.set_len(new_len);
 			}
+			self.assert_invariants();
 		}
 	}
 
+	/// Resizes the `RingBuffer` in-place so that `len` is equal to `new_len`, appending default values.
+	///
+	/// Like [`resize`](Self::resize) for elements which are not `Clone`.
+	#[inline]
+	pub fn resize_default(&mut self, new_len: usize) where T: Default {
+		self.resize_with(new_len, T::default)
+	}
+
+	/// Resizes the `RingBuffer` in-place so that `len` is equal to `new_len`.
+	///
+	/// Like [`resize`](Self::resize) but returns an error instead of panicking if the ring buffer cannot grow.
+	/// Nothing is appended on error.
+	#[inline]
+	pub fn try_resize(&mut self, new_len: usize, value: T) -> Result<(), TryReserveError> where T: Clone {
+		self.try_resize_with(new_len, move || value.clone())
+	}
+
+	/// Resizes the `RingBuffer` in-place so that `len` is equal to `new_len`.
+	///
+	/// Like [`resize_with`](Self::resize_with) but returns an error instead of panicking if the ring buffer cannot grow.
+	/// Nothing is appended and `f` is not called on error.
+	#[inline]
+	pub fn try_resize_with<F: FnMut() -> T>(&mut self, new_len: usize, f: F) -> Result<(), TryReserveError> {
+		if new_len > self.len() {
+			self.try_reserve(new_len - self.len())?;
+		}
+		self.resize_with(new_len, f);
+		Ok(())
+	}
+
+	/// Resizes the `RingBuffer` in-place so that `len` is equal to `new_len`, appending default values.
+	///
+	/// Like [`resize_default`](Self::resize_default) but returns an error instead of panicking if the ring buffer cannot grow.
+	/// Nothing is appended on error.
+	#[inline]
+	pub fn try_resize_default(&mut self, new_len: usize) -> Result<(), TryReserveError> where T: Default {
+		self.try_resize_with(new_len, T::default)
+	}
+
 	/// Reserves capacity for at least `additional` more elements to be inserted in the given `RingBuffer<T>`.
 	///
-	/// The collection may reserve more space to avoid frequent reallocations.
+	/// The collection may reserve more space to avoid frequent reallocations as decided by its [growth policy](Self::set_growth_policy).
 	/// After calling `reserve`, capacity will be greater than or equal to `self.len() + additional`.
 	/// Does nothing if capacity is already sufficient.
 	///
@@ -292,122 +968,448 @@ impl<T> RingBuffer<T> {
 	pub fn reserve(&mut self, additional: usize) {
 		unsafe {
 			if additional > self.reserved_len() {
-				self.reallocate(additional);
+				if let Err(err) = self.reserve_amortized(additional) {
+					platform::alloc_failed(err);
+				}
 			}
 			// Teach the compiler that there are at least additional extra elements available after this point
 			if additional > self.reserved_len() {
 				hint::unreachable_unchecked()
 			}
 		}
+		self.protect(additional);
+		self.assert_invariants();
 	}
 
-	#[inline(never)]
-	unsafe fn reallocate(&mut self, additional: usize) {
-		let capacity = match self.len.checked_add(additional) {
-			Some(capacity) => capacity,
-			None => platform::invalid_capacity(additional),
+	/// Reserves the minimum capacity for exactly `additional` more elements to be inserted in the given `RingBuffer<T>`.
+	///
+	/// Unlike [`reserve`](Self::reserve) this does not deliberately over-allocate,
+	/// but the capacity is still rounded up to the nearest multiple of the system's allocation granularity.
+	/// After calling `reserve_exact`, capacity will be greater than or equal to `self.len() + additional`.
+	/// Does nothing if capacity is already sufficient.
+	///
+	/// Prefer [`reserve`](Self::reserve) if future insertions are expected.
+	///
+	/// # Panics
+	///
+	/// Panics if the new capacity fails to allocate.
+	#[inline]
+	pub fn reserve_exact(&mut self, additional: usize) {
+		if let Err(err) = self.try_reserve_exact(additional) {
+			platform::alloc_failed(err);
+		}
+	}
+
+	/// Tries to reserve capacity for at least `additional` more elements to be inserted in the given `RingBuffer<T>`.
+	///
+	/// Like [`reserve`](Self::reserve) but returns an error instead of panicking if the allocation fails.
+	/// The ring buffer is left unchanged on error.
+	#[inline]
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+		if additional > self.reserved_len() {
+			unsafe { self.reserve_amortized(additional)?; }
+		}
+		self.protect(additional);
+		self.assert_invariants();
+		Ok(())
+	}
+
+	/// Tries to reserve the minimum capacity for exactly `additional` more elements to be inserted in the given `RingBuffer<T>`.
+	///
+	/// Like [`reserve_exact`](Self::reserve_exact) but returns an error instead of panicking if the allocation fails.
+	/// The ring buffer is left unchanged on error.
+	#[inline]
+	pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+		if additional > self.reserved_len() {
+			unsafe { self.reallocate(self.required(additional)?)?; }
+		}
+		self.protect(additional);
+		self.assert_invariants();
+		Ok(())
+	}
+
+	/// Shrinks the capacity of the ring buffer as much as possible.
+	///
+	/// See [`shrink_to`](Self::shrink_to).
+	#[inline]
+	pub fn shrink_to_fit(&mut self) {
+		self.shrink_to(0);
+	}
+
+	/// Shrinks the capacity of the ring buffer with a lower bound.
+	///
+	/// The capacity will remain at least as large as both the length and the supplied value, rounded up to the allocation granularity.
+	/// The elements are moved to a smaller mapping if that saves memory, otherwise the unused capacity is [decommitted](Self::decommit).
+	pub fn shrink_to(&mut self, min_capacity: usize) {
+		let capacity = cmp::max(self.len, min_capacity);
+		if capacity == 0 {
+			if let Some(handle) = self.handle.take() {
+				self.reset_mapping();
+				unsafe { self.memory.free(self.ptr.cast(), self.cap, handle); }
+			}
+			self.ptr = NonNull::dangling();
+			self.cap = 0;
+			self.base = 0;
+			return;
+		}
+		let shrinks = match platform::try_round_capacity(capacity, mem::size_of::<T>(), self.memory.granularity()) {
+			Some(cap) => cap < self.cap,
+			None => false,
 		};
+		if !shrinks || unsafe { self.reallocate(capacity).is_err() } {
+			self.decommit();
+		}
+		self.assert_invariants();
+	}
+
+	/// Returns the physical memory backing the unused capacity to the operating system without unmapping it.
+	///
+	/// Only whole pages of the allocation granularity are released, the capacity is unchanged.
+	/// Large ring buffers which sit idle after a burst keep their address space but not their memory,
+	/// the pages are faulted back in when elements are pushed.
+	/// Does nothing if the memory is [locked](RingBuffer::lock_memory) or the backend does not support decommitting memory.
+	pub fn decommit(&mut self) {
+		// Memory provided by the caller is left alone
+		if self.cap == 0 || self.handle.is_none() {
+			return;
+		}
+		let g = self.memory.granularity();
+		let start = (self.base + self.len * mem::size_of::<T>() + g - 1) & !(g - 1);
+		let end = (self.base + self.cap) & !(g - 1);
+		if start >= end {
+			return;
+		}
+		// Both views map the same pages, release them through the first view
+		let ptr = self.ptr.cast::<u8>();
+		unsafe {
+			if start < self.cap {
+				self.memory.decommit(NonNull::new_unchecked(ptr.as_ptr().add(start)), cmp::min(end, self.cap) - start);
+			}
+			if end > self.cap {
+				let start = cmp::max(start, self.cap) - self.cap;
+				self.memory.decommit(NonNull::new_unchecked(ptr.as_ptr().add(start)), end - self.cap - start);
+			}
+		}
+	}
+
+	// Moves the base offset back into the lower half of the virtual memory
+	#[inline]
+	fn wrap_base(&mut self) {
+		if self.base >= self.cap {
+			self.base -= self.cap;
+			unsafe { self.memory.wrap(self.ptr.cast(), self.cap, self.base, self.len * mem::size_of::<T>()); }
+		}
+		self.assert_invariants();
+	}
+
+	// Restricts access to the elements and the room for `additional` more, backends detecting overruns revoke access to the rest
+	#[inline]
+	fn protect(&self, additional: usize) {
+		if self.cap != 0 {
+			let len = (self.len + additional) * mem::size_of::<T>();
+			unsafe { self.memory.protect(self.ptr.cast(), self.cap, self.base, len); }
+			#[cfg(feature = "sanitize")]
+			unsafe { sanitize::poison(self.ptr.cast(), self.cap, self.base, len); }
+		}
+	}
+
+	// Unpoisons the elements claimed by the caller with the sanitize feature
+	#[inline]
+	fn unpoison(&self) {
+		#[cfg(feature = "sanitize")]
+		if self.cap != 0 {
+			unsafe { sanitize::unpoison(self.ptr.cast(), self.cap, self.base, self.len * mem::size_of::<T>()); }
+		}
+	}
+
+	// Overwrites the bytes at the offset with zeroes after the elements there are gone, with the zeroize feature
+	#[inline]
+	fn wipe(&self, offset: usize, len: usize) {
+		#[cfg(feature = "zeroize")]
+		unsafe { wipe::wipe(self.ptr.cast::<u8>().as_ptr().add(offset), len); }
+		let _ = (offset, len);
+	}
+
+	// Restores access to the whole mapping before it is freed and overwrites it with zeroes with the zeroize feature
+	// The halves of the heap fallback are separate memory
+	fn reset_mapping(&self) {
+		self.protect(self.reserved_len());
+		if cfg!(feature = "zeroize") {
+			self.wipe(0, if cfg!(heap_fallback) { self.cap * 2 } else { self.cap });
+		}
+	}
+
+	// Panics if the invariants are violated in debug builds
+	#[inline]
+	fn assert_invariants(&self) {
+		#[cfg(debug_assertions)]
+		if let Err(err) = self.validate() {
+			panic!("{}", err);
+		}
+	}
+
+	// Capacity needed to hold `additional` more elements
+	#[inline]
+	fn required(&self, additional: usize) -> Result<usize, TryReserveError> {
+		let required = self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+		if required > self.max_capacity() {
+			return Err(TryReserveError::LimitExceeded);
+		}
+		Ok(required)
+	}
+
+	// Capacity to grow to for `additional` more elements according to the growth policy
+	#[inline]
+	fn amortized(&self, additional: usize) -> Result<usize, TryReserveError> {
+		let required = self.required(additional)?;
+		let capacity = self.growth.grow(self.capacity(), required, mem::size_of::<T>(), self.memory.granularity());
+		Ok(cmp::max(required, cmp::min(capacity, self.max_capacity())))
+	}
+
+	// Grows according to the growth policy, falls back to the required capacity if that fails to allocate
+	// Twice the grown capacity may not fit in the address space left on 32-bit targets when the required capacity still does
+	unsafe fn reserve_amortized(&mut self, additional: usize) -> Result<(), TryReserveError> {
+		let capacity = self.amortized(additional)?;
+		match self.reallocate(capacity) {
+			Err(TryReserveError::AllocError(_)) if capacity > self.len + additional => self.reallocate(self.len + additional),
+			result => result,
+		}
+	}
 
-		// Allocate new RingBuffer
-		let (ptr, cap) = platform::allocate(capacity, mem::size_of::<T>());
-		let ptr = ptr.cast();
+	// Largest capacity within the limit which stays within it after rounding to the allocation granularity
+	#[inline]
+	fn max_capacity(&self) -> usize {
+		let size_of = mem::size_of::<T>();
+		(self.limit.saturating_mul(size_of) & !(self.memory.granularity() - 1)) / size_of
+	}
 
-		// Construct new RingBuffer
-		let mut rb = RingBuffer { ptr, cap, base: 0, len: 0 };
+	// Moves the elements into a new allocation with room for `capacity` elements
+	#[inline(never)]
+	unsafe fn reallocate(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+		if self.grow(capacity) {
+			return Ok(());
+		}
 
-		// Copy over the elements from the old ring buffer
-		self.as_ptr().copy_to_nonoverlapping(rb.as_mut_ptr(), self.len);
+		// Allocate the new mapping
+		let (ptr, cap, handle) = self.allocate(capacity)?;
 
-		// Copy over the length and empty the current ring buffer
+		// Move the elements over to the start of the new mapping
 		// No destructors are ran since the elements are moved over
-		rb.len = self.len;
-		self.len = 0;
+		self.as_ptr().copy_to_nonoverlapping(ptr.as_ptr() as *mut T, self.len);
+
+		// Free the old mapping and replace it with the new one
+		if self.handle.is_some() {
+			self.reset_mapping();
+		}
+		if let Some(handle) = self.handle.replace(handle) {
+			self.memory.free(self.ptr.cast(), self.cap, handle);
+		}
+		self.ptr = ptr.cast();
+		self.cap = cap;
+		self.base = 0;
+		self.assert_invariants();
+		Ok(())
+	}
+
+	// Mappings are only guaranteed to be aligned to the smallest page size
+	const ALIGNED: () = assert!(mem::align_of::<T>() <= platform::MIN_PAGE_SIZE, "ring buffer elements cannot be aligned to more than 4 KiB");
+
+	// Allocates a mapping with room for `capacity` elements, rounded up to the granularity of the backend
+	#[inline]
+	fn allocate(&self, capacity: usize) -> Result<(NonNull<u8>, usize, M::Handle), TryReserveError> {
+		let () = Self::ALIGNED;
+		let size = platform::try_round_capacity(capacity, mem::size_of::<T>(), self.memory.granularity()).ok_or(TryReserveError::CapacityOverflow)?;
+		self.memory.allocate(size)
+	}
+
+	// Grows the mapping in place if the platform supports it, only the elements which wrapped around are moved
+	unsafe fn grow(&mut self, capacity: usize) -> bool {
+		let handle = match self.handle {
+			Some(handle) => handle,
+			None => return false,
+		};
+		let size = match platform::try_round_capacity(capacity, mem::size_of::<T>(), self.memory.granularity()) {
+			Some(size) => size,
+			None => return false,
+		};
+		let (ptr, cap) = match self.memory.grow(self.ptr.cast(), self.cap, handle, size) {
+			Some(mapping) => mapping,
+			None => return false,
+		};
+		let old_cap = self.cap;
+		let ptr = ptr.as_ptr();
+
+		// The bytes keep their offsets, the ones past the old end wrapped around to the start
+		let bytes = self.len * mem::size_of::<T>();
+		let head = old_cap - self.base;
+		if bytes > head {
+			let wrapped = bytes - head;
+			if wrapped <= head && old_cap + wrapped <= cap {
+				// Move the wrapped bytes after the old end
+				ptr.copy_to_nonoverlapping(ptr.add(old_cap), wrapped);
+			}
+			else {
+				// Move the bytes before the old end to the new end
+				let base = cap - head;
+				ptr.add(self.base).copy_to(ptr.add(base), head);
+				self.base = base;
+			}
+		}
 
-		// Drop self and replace with reallocated ring buffer
-		*self = rb;
+		self.ptr = NonNull::new_unchecked(ptr).cast();
+		self.cap = cap;
+		self.assert_invariants();
+		true
 	}
 }
 
-impl<T> Drop for RingBuffer<T> {
+impl<T, M: MirroredMemory> Drop for RingBuffer<T, M> {
 	fn drop(&mut self) {
 		unsafe {
 			let len = self.len;
 			self.len = 0;
 			ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), len).drop_in_place();
-			platform::free(self.ptr.cast(), self.cap);
+			if let Some(handle) = self.handle {
+				self.reset_mapping();
+				self.memory.free(self.ptr.cast(), self.cap, handle);
+			}
 		}
 	}
 }
 
-impl<T> ops::Deref for RingBuffer<T> {
+impl<T, M: MirroredMemory + Default> Default for RingBuffer<T, M> {
+	#[inline]
+	fn default() -> RingBuffer<T, M> {
+		RingBuffer::new_in(M::default())
+	}
+}
+
+impl<T, M: MirroredMemory> ops::Deref for RingBuffer<T, M> {
 	type Target = [T];
 	#[inline]
 	fn deref(&self) -> &[T] {
 		self.as_slice()
 	}
 }
-impl<T> ops::DerefMut for RingBuffer<T> {
+impl<T, M: MirroredMemory> ops::DerefMut for RingBuffer<T, M> {
 	#[inline]
 	fn deref_mut(&mut self) -> &mut [T] {
 		self.as_mut_slice()
 	}
 }
-impl<T> AsRef<[T]> for RingBuffer<T> {
+impl<T, M: MirroredMemory> AsRef<[T]> for RingBuffer<T, M> {
 	#[inline]
 	fn as_ref(&self) -> &[T] {
 		self.as_slice()
 	}
 }
-impl<T> AsMut<[T]> for RingBuffer<T> {
+impl<T, M: MirroredMemory> AsMut<[T]> for RingBuffer<T, M> {
 	#[inline]
 	fn as_mut(&mut self) -> &mut [T] {
 		self.as_mut_slice()
 	}
 }
-impl<T> borrow::Borrow<[T]> for RingBuffer<T> {
+impl<T, M: MirroredMemory> borrow::Borrow<[T]> for RingBuffer<T, M> {
 	#[inline]
 	fn borrow(&self) -> &[T] {
 		self.as_slice()
 	}
 }
-impl<T> borrow::BorrowMut<[T]> for RingBuffer<T> {
+impl<T, M: MirroredMemory> borrow::BorrowMut<[T]> for RingBuffer<T, M> {
 	#[inline]
 	fn borrow_mut(&mut self) -> &mut [T] {
 		self.as_mut_slice()
 	}
 }
 
-impl<T> Extend<T> for RingBuffer<T> {
+impl<T, M: MirroredMemory> Extend<T> for RingBuffer<T, M> {
 	#[inline]
 	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-		// Not very efficient...
-		let iter = iter.into_iter();
-		self.reserve(iter.size_hint().0);
-		for value in iter {
-			self.push(value);
+		let mut iter = iter.into_iter();
+		// Iterators of exact size are written in a single batch after this
+		let lower = iter.size_hint().0;
+		if lower > self.reserved_len() {
+			self.reserve(lower);
+		}
+		loop {
+			// Write the elements straight into all of the reserved room
+			let room = self.reserved_len();
+			self.protect(room);
+			unsafe {
+				let ptr = self.reserved_ptr();
+				let mut len = SetLenOnDrop::new(&mut self.len);
+				for i in 0..room {
+					match iter.next() {
+						Some(value) => {
+							ptr.add(i).write(value);
+							len.local_len += 1;
+						},
+						None => {
+							drop(len);
+							self.assert_invariants();
+							return;
+						},
+					}
+				}
+			}
+			// Out of room, grow by the growth policy only if there are more elements
+			let value = match iter.next() {
+				Some(value) => value,
+				None => {
+					self.assert_invariants();
+					return;
+				},
+			};
+			self.reserve(iter.size_hint().0.saturating_add(1));
+			unsafe {
+				self.reserved_ptr().write(value);
+				self.len += 1;
+			}
 		}
 	}
 }
-impl<T> iter::FromIterator<T> for RingBuffer<T> {
+impl<T, M: MirroredMemory + Default> iter::FromIterator<T> for RingBuffer<T, M> {
 	#[inline]
-	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> RingBuffer<T> {
-		// Not very efficient...
+	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> RingBuffer<T, M> {
 		let iter = iter.into_iter();
-		let mut rb = RingBuffer::with_capacity(iter.size_hint().0);
-		for value in iter {
-			rb.push(value);
-		}
+		// Allocates once for iterators of exact size, the rest grow by the growth policy
+		let mut rb = RingBuffer::with_capacity_in(iter.size_hint().0, M::default());
+		rb.extend(iter);
 		rb
 	}
 }
-impl<T: Clone> Clone for RingBuffer<T> {
+impl<T: Clone, M: MirroredMemory + Clone> Clone for RingBuffer<T, M> {
 	#[inline]
-	fn clone(&self) -> RingBuffer<T> {
-		self.as_slice().iter().cloned().collect()
+	fn clone(&self) -> RingBuffer<T, M> {
+		// Allocate with the same options, the limit may have been lowered below the length
+		let mut rb = RingBuffer::new_in(self.memory.clone());
+		rb.growth = self.growth;
+		rb.extend(self.as_slice().iter().cloned());
+		rb.limit = self.limit;
+		rb
 	}
 }
 
 // Safe because it is possible to free this from a different thread
-unsafe impl<T: Send> Send for RingBuffer<T> {}
+unsafe impl<T: Send, M: MirroredMemory + Send> Send for RingBuffer<T, M> {}
 // Safe because this doesn't use any kind of interior mutability
-unsafe impl<T: Sync> Sync for RingBuffer<T> {}
+unsafe impl<T: Sync, M: MirroredMemory + Sync> Sync for RingBuffer<T, M> {}
+
+// Commits the length of the elements written so far, also when unwinding from a panic while writing the next element
+struct SetLenOnDrop<'a> {
+	len: &'a mut usize,
+	local_len: usize,
+}
+impl<'a> SetLenOnDrop<'a> {
+	#[inline]
+	fn new(len: &'a mut usize) -> SetLenOnDrop<'a> {
+		let local_len = *len;
+		SetLenOnDrop { len, local_len }
+	}
+}
+impl<'a> Drop for SetLenOnDrop<'a> {
+	#[inline]
+	fn drop(&mut self) {
+		*self.len = self.local_len;
+	}
+}
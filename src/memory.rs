@@ -0,0 +1,169 @@
+/*!
+Backends providing the mirrored memory of ring buffers.
+*/
+
+use core::ptr::NonNull;
+
+use super::{platform, TryReserveError};
+
+/// Backend providing the mirrored memory of a [`RingBuffer`](super::RingBuffer).
+///
+/// Ring buffers are generic over their backend and default to [`OsMemory`], which maps memory from the operating system.
+/// Other backends supply memory the crate cannot map itself, such as a page shared with a hypervisor or a GPU staging buffer,
+/// or stand in for the operating system in tests. [`RingBuffer::new_in`](super::RingBuffer::new_in) constructs a ring buffer with a backend.
+///
+/// # Safety
+///
+/// [`allocate`](Self::allocate) must return a mapping of twice its size in bytes where the second half mirrors the first,
+/// writing a byte at `ptr + i` changes the byte at `ptr + size + i` and vice versa.
+/// The mapping must be aligned to at least 4096 bytes, readable and writable
+/// and stay valid until it is [freed](Self::free), from any thread the backend can be sent to.
+pub unsafe trait MirroredMemory {
+	/// Memory object backing a mapping, handed back to the backend to free the mapping.
+	type Handle: Copy;
+
+	/// Returns the granularity of mappings in bytes, a power of two.
+	///
+	/// Ring buffers round their capacity in bytes up to a multiple of it.
+	fn granularity(&self) -> usize;
+
+	/// Maps at least `size` bytes twice back to back, `size` is a non-zero multiple of the granularity.
+	///
+	/// Returns the mapping, its size which is a multiple of the granularity and the handle to free it with.
+	fn allocate(&self, size: usize) -> Result<(NonNull<u8>, usize, Self::Handle), TryReserveError>;
+
+	/// Unmaps a mapping returned by [`allocate`](Self::allocate) or [`grow`](Self::grow).
+	///
+	/// # Safety
+	///
+	/// The mapping must have been returned by this backend and is not used afterwards.
+	unsafe fn free(&self, ptr: NonNull<u8>, size: usize, handle: Self::Handle);
+
+	/// Grows a mapping to at least `new_size` bytes keeping the bytes at their offsets, the old mapping is unmapped.
+	///
+	/// Returns `None` if the mapping cannot be grown, which is the default, the ring buffer allocates a new mapping and moves its elements instead.
+	///
+	/// # Safety
+	///
+	/// The mapping must have been returned by this backend, it keeps its handle.
+	#[inline]
+	unsafe fn grow(&self, ptr: NonNull<u8>, size: usize, handle: Self::Handle, new_size: usize) -> Option<(NonNull<u8>, usize)> {
+		let _ = (ptr, size, handle, new_size);
+		None
+	}
+
+	/// Releases the physical pages of a granularity aligned range within the first half of a mapping, their contents become undefined.
+	///
+	/// Does nothing by default.
+	///
+	/// # Safety
+	///
+	/// The range must lie within a mapping returned by this backend.
+	#[inline]
+	unsafe fn decommit(&self, ptr: NonNull<u8>, len: usize) {
+		let _ = (ptr, len);
+	}
+
+	/// Restricts access to the `len` bytes at `offset` of a mapping, wrapping around at the end of its first half.
+	///
+	/// Called whenever the ring buffer reserves room, `offset` is its base offset and `len` covers its elements and the reserved room.
+	/// Backends detecting overruns revoke access to the other pages in both halves of the mapping, which does nothing by default.
+	///
+	/// # Safety
+	///
+	/// The mapping must have been returned by this backend, neither `offset` nor `len` exceed its size.
+	#[inline]
+	unsafe fn protect(&self, ptr: NonNull<u8>, size: usize, offset: usize, len: usize) {
+		let _ = (ptr, size, offset, len);
+	}
+
+	/// Called when the start of a ring buffer of `len` bytes wrapped around to `base`.
+	///
+	/// Mirrored memory wraps around by itself and this does nothing by default.
+	/// Backends which cannot mirror memory, like the heap fallback on WebAssembly, copy the bytes at `base + size` back to `base`.
+	///
+	/// # Safety
+	///
+	/// The mapping must have been returned by this backend, `base + len` does not exceed its size.
+	#[inline]
+	unsafe fn wrap(&self, ptr: NonNull<u8>, size: usize, base: usize, len: usize) {
+		let _ = (ptr, size, base, len);
+	}
+}
+
+/// Backend mapping the memory of ring buffers from the operating system.
+///
+/// The default backend of [`RingBuffer`](super::RingBuffer), holds the options it was [built](super::RingBuffer::builder) with.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OsMemory {
+	pub(crate) options: platform::Options,
+}
+
+impl OsMemory {
+	/// Constructs the backend with the default options.
+	#[inline]
+	pub const fn new() -> OsMemory {
+		OsMemory { options: platform::Options::new() }
+	}
+}
+
+unsafe impl MirroredMemory for OsMemory {
+	type Handle = platform::Handle;
+
+	#[inline]
+	fn granularity(&self) -> usize {
+		self.options.granularity()
+	}
+
+	fn allocate(&self, size: usize) -> Result<(NonNull<u8>, usize, platform::Handle), TryReserveError> {
+		unsafe {
+			let (ptr, size, handle) = platform::allocate(size, 1, &self.options)?;
+			if self.options.prefault {
+				platform::prefault(ptr, size);
+			}
+			if self.options.lock_memory {
+				if let Err(err) = platform::lock(ptr, size) {
+					self.free(ptr, size, handle);
+					return Err(TryReserveError::AllocError(err));
+				}
+			}
+			Ok((ptr, size, handle))
+		}
+	}
+
+	#[inline]
+	unsafe fn free(&self, ptr: NonNull<u8>, size: usize, handle: platform::Handle) {
+		platform::free(ptr, size, handle);
+		if self.options.guard_pages {
+			platform::free_guards(ptr, size);
+		}
+	}
+
+	unsafe fn grow(&self, ptr: NonNull<u8>, size: usize, handle: platform::Handle, new_size: usize) -> Option<(NonNull<u8>, usize)> {
+		let (ptr, size) = platform::grow(ptr, size, handle, new_size, 1, &self.options)?;
+		if self.options.prefault {
+			platform::prefault(ptr, size);
+		}
+		Some((ptr, size))
+	}
+
+	// Locked memory stays committed
+	#[inline]
+	unsafe fn decommit(&self, ptr: NonNull<u8>, len: usize) {
+		if !self.options.lock_memory {
+			platform::decommit(ptr, len);
+		}
+	}
+
+	#[inline]
+	unsafe fn protect(&self, ptr: NonNull<u8>, size: usize, offset: usize, len: usize) {
+		if self.options.overrun_detection {
+			platform::protect(ptr, size, self.granularity(), offset, len);
+		}
+	}
+
+	#[inline]
+	unsafe fn wrap(&self, ptr: NonNull<u8>, size: usize, base: usize, len: usize) {
+		platform::wrap(ptr, size, base, len);
+	}
+}
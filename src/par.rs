@@ -0,0 +1,71 @@
+/*!
+Parallel iteration with rayon.
+
+The elements are a contiguous slice, so the [`ParallelSlice`](rayon::slice::ParallelSlice) methods such as `par_chunks` are available through deref.
+*/
+
+use alloc::vec::Vec;
+
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+use super::RingBuffer;
+
+impl<'a, T: Sync> IntoParallelIterator for &'a RingBuffer<T> {
+	type Iter = rayon::slice::Iter<'a, T>;
+	type Item = &'a T;
+
+	#[inline]
+	fn into_par_iter(self) -> Self::Iter {
+		self.as_slice().into_par_iter()
+	}
+}
+
+impl<'a, T: Send> IntoParallelIterator for &'a mut RingBuffer<T> {
+	type Iter = rayon::slice::IterMut<'a, T>;
+	type Item = &'a mut T;
+
+	#[inline]
+	fn into_par_iter(self) -> Self::Iter {
+		self.as_mut_slice().into_par_iter()
+	}
+}
+
+impl<T: Send> IntoParallelIterator for RingBuffer<T> {
+	type Iter = rayon::vec::IntoIter<T>;
+	type Item = T;
+
+	/// Moves the elements into a `Vec` to be split up between the threads.
+	fn into_par_iter(mut self) -> Self::Iter {
+		let len = self.len;
+		let mut vec: Vec<T> = Vec::with_capacity(len);
+		unsafe {
+			vec.as_mut_ptr().copy_from_nonoverlapping(self.as_ptr(), len);
+			vec.set_len(len);
+		}
+		self.len = 0;
+		vec.into_par_iter()
+	}
+}
+
+impl<T: Send> ParallelExtend<T> for RingBuffer<T> {
+	/// Collects the elements in parallel and appends them to the front in order.
+	fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+		let mut vec: Vec<T> = par_iter.into_par_iter().collect();
+		let len = vec.len();
+		self.reserve(len);
+		unsafe {
+			vec.set_len(0);
+			vec.as_ptr().copy_to_nonoverlapping(self.as_mut_ptr().add(self.len), len);
+		}
+		self.len += len;
+	}
+}
+
+impl<T: Send> FromParallelIterator<T> for RingBuffer<T> {
+	#[inline]
+	fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> RingBuffer<T> {
+		let mut rbuf = RingBuffer::new();
+		rbuf.par_extend(par_iter);
+		rbuf
+	}
+}
@@ -0,0 +1,62 @@
+/*!
+Incremental parsing with winnow.
+*/
+
+use core::slice;
+
+use winnow::Parser;
+use winnow::stream::{Offset, Partial};
+
+use super::RingBuffer;
+
+impl RingBuffer<u8> {
+	/// Returns a partial input stream over the contents of the ring buffer.
+	///
+	/// Parsers running on a partial stream report that more input is needed instead of failing when they run out of data.
+	/// After parsing, remove the parsed bytes with [`consume`](Self::consume).
+	#[inline]
+	pub fn partial_input(&self) -> Partial<&[u8]> {
+		Partial::new(self.as_slice())
+	}
+
+	/// Consumes `parsed_len` bytes from the tail.
+	///
+	/// # Panics
+	///
+	/// Panics if `parsed_len` is greater than the length of the ring buffer.
+	#[inline]
+	pub fn consume(&mut self, parsed_len: usize) {
+		assert!(parsed_len <= self.len(), "cannot consume more than the length: {} <= {}", parsed_len, self.len());
+		self.remove_tail(parsed_len);
+	}
+
+	/// Runs the parser on the contents of the ring buffer and consumes the parsed bytes on success.
+	///
+	/// Nothing is consumed if the parser fails or needs more input.
+	/// The output may borrow the parsed bytes, they remain valid until the ring buffer is modified again.
+	///
+	/// ```
+	/// use winnow::binary::{length_take, u8};
+	/// use winnow::error::{ContextError, ErrMode};
+	///
+	/// type Error = ErrMode<ContextError>;
+	///
+	/// let mut rbuf = vringbuf::RingBuffer::<u8>::new();
+	/// rbuf.extend_from_slice(&[3, b'a', b'b']);
+	/// assert!(rbuf.parse_partial(length_take::<_, _, Error, _>(u8)).is_err());
+	///
+	/// rbuf.extend_from_slice(&[b'c', 1]);
+	/// assert_eq!(rbuf.parse_partial(length_take::<_, _, Error, _>(u8)), Ok(&b"abc"[..]));
+	/// assert_eq!(rbuf.as_slice(), &[1]);
+	/// ```
+	pub fn parse_partial<'a, O, E, P>(&'a mut self, mut parser: P) -> Result<O, E> where P: Parser<Partial<&'a [u8]>, O, E> {
+		// The parsed bytes are not touched by discarding them without wiping,
+		// they stay in place for as long as the ring buffer is borrowed
+		let data = unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) };
+		let start = Partial::new(data);
+		let mut input = start;
+		let output = parser.parse_next(&mut input)?;
+		self.discard(input.offset_from(&start), false);
+		Ok(output)
+	}
+}
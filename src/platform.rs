@@ -1,12 +1,89 @@
 // Implement mirrored memory for the right platform
 //
 // Each platform specific module must export:
+// * pub type Handle; the memory object backing a mapping
+// * pub const NULL_HANDLE: Handle;
 // * pub fn granularity() -> usize;
-// * pub unsafe fn allocate(cap: usize) -> (NonNull<u8>, usize);
-// * pub unsafe fn free(ptr: *mut u8, cap: usize);
+// * pub fn huge_page_size() -> Option<usize>; None if huge pages are not supported
+// * pub fn page_size(ptr: NonNull<u8>, handle: Handle) -> usize; size of the pages backing a mapping
+// * pub fn huge_page_hint(ptr: NonNull<u8>, handle: Handle) -> bool; true if the mapping has the transparent huge page hint applied
+// * pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError>;
+//   falls back to regular pages if huge pages cannot be allocated, system calls which fail report a PlatformError
+// * pub unsafe fn free(ptr: NonNull<u8>, cap: usize, handle: Handle);
+// * pub unsafe fn grow(ptr: NonNull<u8>, cap: usize, handle: Handle, new_cap: usize, size_of: usize, options: &Options) -> Option<(NonNull<u8>, usize)>;
+//   grows the mapping keeping the bytes at their offsets, None if unsupported or it fails
+// * pub unsafe fn decommit(ptr: NonNull<u8>, len: usize);
+//   releases the physical pages of a granularity aligned range within one of the two views
+// * pub unsafe fn lock(ptr: NonNull<u8>, cap: usize) -> Result<(), Error>;
+//   locks both views into physical memory, grow locks the new mapping if the options ask for it
+// * pub unsafe fn unlock(ptr: NonNull<u8>, cap: usize);
+// * pub unsafe fn prefault(ptr: NonNull<u8>, cap: usize);
+//   faults in the pages of both views without changing their contents
+// * pub unsafe fn free_guards(ptr: NonNull<u8>, cap: usize);
+//   unmaps the guard pages around a mapping allocated with guard pages after it was freed
+// * pub unsafe fn protect(ptr: NonNull<u8>, cap: usize, page: usize, offset: usize, len: usize);
+//   revokes access to the pages of both views outside of the `len` bytes at `offset`, which wrap around
+//
+// Windows, where mapping the views can race with other threads, additionally exports:
+// * pub fn map_retries() -> u64; number of times mapping lost the race and was retried
+//
+// The heap fallback for targets which cannot mirror memory and Miri additionally exports:
+// * pub unsafe fn wrap(ptr: NonNull<u8>, cap: usize, base: usize, len: usize);
+//   moves the `len` bytes at `base + cap` to `base` after the base offset wrapped around
+//
+// Platforms where the handle is a file descriptor or section additionally export:
+// * pub unsafe fn map_backing(handle: Handle) -> io::Result<(NonNull<u8>, usize)>;
+//
+// Named shared memory is implemented for Unix (shm) and Windows, both export:
+// * pub type Security<'a>; permissions of newly created shared memory
+// * pub const DEFAULT_SECURITY: Security<'static>;
+// * pub unsafe fn create_shared(name: &str, header: usize, cap: usize, size_of: usize, security: Security) -> io::Result<(NonNull<u8>, usize, Object)>;
+// * pub unsafe fn open_shared(name: &str, header: usize) -> io::Result<(NonNull<u8>, usize, Object)>;
+// * pub unsafe fn map_shared(object: Object, header: usize) -> io::Result<(NonNull<u8>, usize)>;
+// * pub unsafe fn unlink_shared(name: &str) -> io::Result<()>;
+// * pub unsafe fn free_shared(ptr: NonNull<u8>, header: usize, cap: usize);
+// Where the object is a RawFd (Unix) or a section HANDLE (Windows), Unix additionally exports:
+// * pub unsafe fn send_fd(socket: RawFd, fd: RawFd, data: &[u8]) -> io::Result<()>;
+// * pub unsafe fn recv_fd(socket: RawFd, data: &mut [u8]) -> io::Result<RawFd>;
+// And Windows:
+// * pub unsafe fn duplicate_handle(map: HANDLE, process: HANDLE) -> io::Result<HANDLE>;
+//
+// Waiting on words in shared memory is implemented in the wait module:
+// * pub fn wait_on(word: &AtomicU32, expected: u32, timeout: Option<Duration>);
+// * pub fn wake_all(word: &AtomicU32);
+//
+// Files are mapped like named shared memory, each platform except the heap fallback must export:
+// * pub unsafe fn map_file(file: &File, header: usize, cap: usize) -> io::Result<NonNull<u8>>;
+// * pub unsafe fn flush(ptr: NonNull<u8>, header: usize, cap: usize) -> io::Result<()>;
+//
+// Without the `std` feature only the Unix backend is available.
+// Named shared memory, files and waiting on words are not available.
+
+#[cfg(not(heap_fallback))]
+use core::ptr::NonNull;
+#[cfg(not(heap_fallback))]
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(all(feature = "std", not(heap_fallback)))]
+use std::{io, fs::File};
+
+use core::ops::Range;
+use core::time::Duration;
+use alloc::boxed::Box;
+
+use super::{PlatformError, TryReserveError};
+
+// Errors of the system calls made by the platform layer
+pub type Error = PlatformError;
 
 cfg_if::cfg_if! {
-	if #[cfg(windows)] {
+	if #[cfg(all(not(feature = "std"), any(heap_fallback, windows, target_os = "macos", target_os = "ios", target_os = "fuchsia", target_os = "android")))] {
+		compile_error!("only the Unix backend supports no_std, enable the `std` feature!");
+	}
+	else if #[cfg(heap_fallback)] {
+		mod heap;
+		pub use self::heap::*;
+	}
+	else if #[cfg(windows)] {
 		mod windows;
 		pub use self::windows::*;
 	}
@@ -14,30 +91,213 @@ cfg_if::cfg_if! {
 		mod macos;
 		pub use self::macos::*;
 	}
-	else if #[cfg(unix)] {
+	else if #[cfg(target_os = "fuchsia")] {
+		mod fuchsia;
+		pub use self::fuchsia::*;
+	}
+	else if #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "illumos", target_os = "solaris"))] {
 		mod linux;
 		pub use self::linux::*;
 	}
 	else {
-		compile_error!("unsupported platform!")
+		compile_error!("unsupported platform, enable the `fallback-alloc` feature to fall back to heap memory!")
+	}
+}
+
+#[cfg(all(unix, not(heap_fallback)))]
+mod shm;
+#[cfg(all(feature = "std", unix, not(heap_fallback)))]
+pub use self::shm::*;
+
+#[cfg(all(feature = "std", not(heap_fallback)))]
+mod wait;
+#[cfg(all(feature = "std", not(heap_fallback)))]
+pub use self::wait::*;
+
+// Mapping does not race with other threads
+#[cfg(any(not(windows), heap_fallback))]
+#[inline]
+pub fn map_retries() -> u64 {
+	0
+}
+
+// Guard pages are only reserved by the Unix backend
+#[cfg(any(heap_fallback, windows, target_os = "macos", target_os = "ios", target_os = "fuchsia"))]
+#[inline(always)]
+pub unsafe fn free_guards(_ptr: std::ptr::NonNull<u8>, _cap: usize) {}
+
+// Overruns are only detected by the Unix backend
+#[cfg(any(heap_fallback, windows, target_os = "macos", target_os = "ios", target_os = "fuchsia"))]
+#[inline(always)]
+pub unsafe fn protect(_ptr: std::ptr::NonNull<u8>, _cap: usize, _page: usize, _offset: usize, _len: usize) {}
+
+// The mirrored mapping wraps around by itself
+#[cfg(not(heap_fallback))]
+#[inline(always)]
+pub unsafe fn wrap(_ptr: NonNull<u8>, _cap: usize, _base: usize, _len: usize) {}
+
+// Maps a file holding a header followed by the ring buffer, an empty file is sized to hold `cap` elements first
+#[cfg(all(feature = "std", not(heap_fallback)))]
+pub unsafe fn open_file(file: &File, header: usize, cap: usize, size_of: usize) -> io::Result<(NonNull<u8>, usize)> {
+	let size = file.metadata()?.len() as usize;
+	let cap = if size == 0 {
+		if cap == 0 {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "zero capacity"));
+		}
+		let cap = round_capacity(cap, size_of);
+		file.set_len((header + cap) as u64)?;
+		cap
+	}
+	else if size <= header || (size - header) & (granularity() - 1) != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid file size"));
 	}
+	else {
+		size - header
+	};
+	map_file(file, header, cap).map(|ptr| (ptr, cap))
 }
 
+// Allocation options of a ring buffer
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+	// Back the mapping with huge pages if the system has them available.
+	pub huge_pages: bool,
+	// Hint the system to back the mapping with transparent huge pages.
+	pub transparent_huge_pages: bool,
+	// Lock the mapping into physical memory.
+	pub lock_memory: bool,
+	// Leave the mapping out of core dumps.
+	pub exclude_from_dumps: bool,
+	// Name of the memory object backing the mapping, without nul bytes.
+	pub name: Option<Box<str>>,
+	// Fault in the pages of the mapping when allocating it.
+	pub prefault: bool,
+	// Attempts to map the views where that races with other threads, None for the default.
+	pub map_attempts: Option<u32>,
+	// Time to wait between the attempts.
+	pub map_backoff: Duration,
+	// Range of addresses the mapping must lie within, anywhere if None.
+	pub address_range: Option<Range<usize>>,
+	// Reserve inaccessible guard pages before and after the mapping.
+	pub guard_pages: bool,
+	// Revoke access to the unused capacity of the mapping.
+	pub overrun_detection: bool,
+}
+
+
+impl Options {
+	#[inline]
+	pub const fn new() -> Options {
+		Options {
+			huge_pages: false,
+			transparent_huge_pages: false,
+			lock_memory: false,
+			exclude_from_dumps: false,
+			name: None,
+			prefault: false,
+			map_attempts: None,
+			map_backoff: Duration::from_secs(0),
+			address_range: None,
+			guard_pages: false,
+			overrun_detection: false,
+		}
+	}
+
+	// Granularity the capacity is rounded to
+	#[inline]
+	pub fn granularity(&self) -> usize {
+		match self.huge_pages {
+			true => huge_page_size().unwrap_or_else(granularity),
+			false => granularity(),
+		}
+	}
+}
+
+#[cfg(all(feature = "std", not(heap_fallback)))]
 fn round_capacity(cap: usize, size_of: usize) -> usize {
-	let g = granularity();
-	let cap = match cap.checked_mul(size_of) {
+	match try_round_capacity(cap, size_of, granularity()) {
 		Some(cap) => cap,
 		None => invalid_capacity(cap),
-	};
-	let cap = ((cap - 1) & !(g - 1)) + g;
+	}
+}
+
+// Rounds the capacity in bytes up to the granularity, None if it exceeds system limits
+pub fn try_round_capacity(cap: usize, size_of: usize, g: usize) -> Option<usize> {
+	let cap = cap.checked_mul(size_of)?.checked_add(g - 1)? & !(g - 1);
 	if cap == 0 || cap >= isize::MAX as usize / 2 {
-		invalid_capacity(cap);
+		return None;
 	}
-	cap
+	Some(cap)
 }
 
+#[cfg(all(feature = "std", not(heap_fallback)))]
 #[cold]
 #[track_caller]
 pub fn invalid_capacity(cap: usize) -> ! {
 	panic!("invalid capacity: {:#x}", cap)
 }
+
+// Smallest page size of all supported platforms, every mapping is aligned to it
+pub const MIN_PAGE_SIZE: usize = 4096;
+
+// Faults in the pages of the range by touching every page
+// The atomic no-op keeps the contents intact even if other mappings of the memory write to it concurrently
+#[cfg(not(heap_fallback))]
+unsafe fn touch(ptr: NonNull<u8>, len: usize) {
+	let ptr = ptr.as_ptr();
+	for offset in (0..len).step_by(MIN_PAGE_SIZE) {
+		(*(ptr.add(offset) as *const AtomicU8)).fetch_or(0, Ordering::Relaxed);
+	}
+}
+
+// Addresses within the range aligned to `align` where `len` bytes fit, in steps of `len` rounded up to the alignment
+#[cfg(not(any(heap_fallback, target_os = "fuchsia")))]
+fn candidates(range: &Range<usize>, len: usize, align: usize) -> impl Iterator<Item = usize> {
+	// Mapping at the null address would map anywhere
+	let start = range.start.max(1).checked_add(align - 1).map_or(usize::MAX, |start| start & !(align - 1));
+	let end = range.end.saturating_sub(len);
+	let step = len.checked_add(align - 1).map_or(usize::MAX, |len| len & !(align - 1));
+	(start..=end).step_by(step)
+}
+
+// No room left for the mapping of `size` bytes within its address range
+#[cfg(not(any(heap_fallback, target_os = "fuchsia")))]
+#[cold]
+fn out_of_range(size: usize) -> Error {
+	PlatformError::OutOfRange { size }
+}
+
+// Error of the system call `call` which failed for `size` bytes, from the last OS error
+#[cfg(not(heap_fallback))]
+#[cold]
+pub fn os_error(call: &'static str, size: usize) -> Error {
+	PlatformError::Os { call, code: errno(), size }
+}
+
+// Code of the last OS error
+#[cfg(all(feature = "std", not(heap_fallback)))]
+#[inline]
+pub fn errno() -> i32 {
+	io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+// Without std only Unix is supported, which keeps the code in a thread local variable
+#[cfg(not(feature = "std"))]
+#[inline]
+pub fn errno() -> i32 {
+	#[cfg(target_os = "linux")]
+	let location = unsafe { libc::__errno_location() };
+	#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+	let location = unsafe { libc::__error() };
+	#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+	let location = unsafe { libc::__errno() };
+	#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+	let location = unsafe { libc::___errno() };
+	unsafe { *location }
+}
+
+#[cold]
+#[track_caller]
+pub fn alloc_failed(err: TryReserveError) -> ! {
+	panic!("{}", err)
+}
@@ -0,0 +1,146 @@
+use std::ptr::NonNull;
+
+use crate::{PlatformError, TryReserveError};
+use super::{Error, Options};
+
+// The VMO is mapped twice back to back into a child VMAR of the root VMAR.
+// Unmapping the child VMAR's range destroys it along with both mappings.
+
+#[allow(non_camel_case_types)]
+type zx_status_t = i32;
+#[allow(non_camel_case_types)]
+type zx_handle_t = u32;
+#[allow(non_camel_case_types)]
+type zx_vm_option_t = u32;
+
+const ZX_OK: zx_status_t = 0;
+const ZX_HANDLE_INVALID: zx_handle_t = 0;
+const ZX_VM_PERM_READ: zx_vm_option_t = 1 << 0;
+const ZX_VM_PERM_WRITE: zx_vm_option_t = 1 << 1;
+const ZX_VM_SPECIFIC: zx_vm_option_t = 1 << 4;
+const ZX_VM_CAN_MAP_SPECIFIC: zx_vm_option_t = 1 << 6;
+const ZX_VM_CAN_MAP_READ: zx_vm_option_t = 1 << 7;
+const ZX_VM_CAN_MAP_WRITE: zx_vm_option_t = 1 << 8;
+
+#[link(name = "zircon")]
+extern "C" {
+	fn zx_system_get_page_size() -> u32;
+	fn zx_vmar_root_self() -> zx_handle_t;
+	fn zx_vmo_create(size: u64, options: u32, out: *mut zx_handle_t) -> zx_status_t;
+	fn zx_vmar_allocate(parent_vmar: zx_handle_t, options: zx_vm_option_t, offset: usize, size: usize, child_vmar: *mut zx_handle_t, child_addr: *mut usize) -> zx_status_t;
+	fn zx_vmar_map(handle: zx_handle_t, options: zx_vm_option_t, vmar_offset: usize, vmo: zx_handle_t, vmo_offset: u64, len: usize, mapped_addr: *mut usize) -> zx_status_t;
+	fn zx_vmar_unmap(handle: zx_handle_t, addr: usize, len: usize) -> zx_status_t;
+	fn zx_vmar_destroy(handle: zx_handle_t) -> zx_status_t;
+	fn zx_handle_close(handle: zx_handle_t) -> zx_status_t;
+}
+
+// The VMO backing the mapping.
+pub type Handle = zx_handle_t;
+pub const NULL_HANDLE: Handle = ZX_HANDLE_INVALID;
+
+#[inline]
+pub fn granularity() -> usize {
+	unsafe { zx_system_get_page_size() as usize }
+}
+
+// Huge pages are not supported
+#[inline]
+pub fn huge_page_size() -> Option<usize> {
+	None
+}
+
+#[inline]
+pub fn page_size(_ptr: NonNull<u8>, _handle: Handle) -> usize {
+	granularity()
+}
+
+// Transparent huge pages are not supported
+#[inline]
+pub fn huge_page_hint(_ptr: NonNull<u8>, _handle: Handle) -> bool {
+	false
+}
+
+#[inline(never)]
+pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
+	if cap == 0 {
+		return Ok((NonNull::dangling(), 0, NULL_HANDLE));
+	}
+	// Placing the child VMAR at specific addresses is not supported
+	if options.address_range.is_some() {
+		return Err(TryReserveError::AllocError(PlatformError::Unsupported { call: "address_range", size: cap }));
+	}
+
+	// Round capacity to nearest multiple of the system's allocation granularity
+	let cap = super::try_round_capacity(cap, size_of, granularity()).ok_or(TryReserveError::CapacityOverflow)?;
+
+	let mut vmo = ZX_HANDLE_INVALID;
+	let status = zx_vmo_create(cap as u64, 0, &mut vmo);
+	if status != ZX_OK {
+		return Err(error("zx_vmo_create", status, cap));
+	}
+
+	// Reserve a child region for twice the capacity which allows mapping at specific offsets
+	let mut vmar = ZX_HANDLE_INVALID;
+	let mut addr = 0;
+	let options = ZX_VM_CAN_MAP_SPECIFIC | ZX_VM_CAN_MAP_READ | ZX_VM_CAN_MAP_WRITE;
+	let status = zx_vmar_allocate(zx_vmar_root_self(), options, 0, cap + cap, &mut vmar, &mut addr);
+	if status != ZX_OK {
+		zx_handle_close(vmo);
+		return Err(error("zx_vmar_allocate", status, cap + cap));
+	}
+
+	// Map the VMO twice back to back
+	let options = ZX_VM_SPECIFIC | ZX_VM_PERM_READ | ZX_VM_PERM_WRITE;
+	let mut mapped = 0;
+	let mut status = zx_vmar_map(vmar, options, 0, vmo, 0, cap, &mut mapped);
+	if status == ZX_OK {
+		status = zx_vmar_map(vmar, options, cap, vmo, 0, cap, &mut mapped);
+	}
+	if status != ZX_OK {
+		zx_vmar_destroy(vmar);
+		zx_handle_close(vmar);
+		zx_handle_close(vmo);
+		return Err(error("zx_vmar_map", status, cap));
+	}
+
+	// The region stays alive while it holds mappings
+	zx_handle_close(vmar);
+	Ok((NonNull::new_unchecked(addr as *mut u8), cap, vmo))
+}
+
+// The mapping cannot be grown in place, reallocating copies the elements
+#[inline]
+pub unsafe fn grow(_ptr: NonNull<u8>, _cap: usize, _handle: Handle, _new_cap: usize, _size_of: usize, _options: &Options) -> Option<(NonNull<u8>, usize)> {
+	None
+}
+
+#[inline]
+pub unsafe fn free(ptr: NonNull<u8>, cap: usize, vmo: Handle) {
+	zx_vmar_unmap(zx_vmar_root_self(), ptr.as_ptr() as usize, cap + cap);
+	if vmo != NULL_HANDLE {
+		zx_handle_close(vmo);
+	}
+}
+
+// Decommitting is not supported, the pages stay committed
+#[inline]
+pub unsafe fn decommit(_ptr: NonNull<u8>, _len: usize) {}
+
+// Locking memory is not supported
+#[inline]
+pub unsafe fn lock(_ptr: NonNull<u8>, cap: usize) -> Result<(), Error> {
+	Err(PlatformError::Unsupported { call: "lock", size: cap + cap })
+}
+
+#[inline]
+pub unsafe fn unlock(_ptr: NonNull<u8>, _cap: usize) {}
+
+#[inline]
+pub unsafe fn prefault(ptr: NonNull<u8>, cap: usize) {
+	super::touch(ptr, cap + cap);
+}
+
+#[cold]
+fn error(call: &'static str, status: zx_status_t, size: usize) -> TryReserveError {
+	PlatformError::Zircon { call, status, size }.into()
+}
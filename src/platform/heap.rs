@@ -0,0 +1,101 @@
+use std::alloc::{self, Layout};
+use std::ptr::NonNull;
+
+use crate::{PlatformError, TryReserveError};
+use super::{Error, Options};
+
+// Fallback for WebAssembly, which has no virtual memory to mirror, targets without a mirrored backend and Miri.
+// Both halves are plain heap memory, the elements are kept contiguous within the two halves and copied back
+// to the start of the first half when they wrap around, the second half gives room to push elements until then.
+
+// Nothing backs the allocation
+#[derive(Copy, Clone, Debug)]
+pub struct Handle;
+pub const NULL_HANDLE: Handle = Handle;
+
+// The allocation comes from the global allocator, not tied to the page size of the system
+#[inline]
+pub fn granularity() -> usize {
+	4096
+}
+
+// Huge pages are not supported
+#[inline]
+pub fn huge_page_size() -> Option<usize> {
+	None
+}
+
+#[inline]
+pub fn page_size(_ptr: NonNull<u8>, _handle: Handle) -> usize {
+	granularity()
+}
+
+// Transparent huge pages are not supported
+#[inline]
+pub fn huge_page_hint(_ptr: NonNull<u8>, _handle: Handle) -> bool {
+	false
+}
+
+#[inline]
+fn layout(cap: usize) -> Layout {
+	unsafe { Layout::from_size_align_unchecked(cap + cap, granularity()) }
+}
+
+#[inline(never)]
+pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
+	if cap == 0 {
+		return Ok((NonNull::dangling(), 0, NULL_HANDLE));
+	}
+	// The allocator decides where the memory goes
+	if options.address_range.is_some() {
+		return Err(TryReserveError::AllocError(PlatformError::Unsupported { call: "address_range", size: cap }));
+	}
+
+	// Round capacity to nearest multiple of the allocation granularity
+	let cap = super::try_round_capacity(cap, size_of, granularity()).ok_or(TryReserveError::CapacityOverflow)?;
+
+	match NonNull::new(alloc::alloc(layout(cap))) {
+		Some(ptr) => Ok((ptr, cap, NULL_HANDLE)),
+		None => Err(TryReserveError::AllocError(PlatformError::OutOfMemory { size: cap + cap })),
+	}
+}
+
+// Reallocating copies the elements
+#[inline]
+pub unsafe fn grow(_ptr: NonNull<u8>, _cap: usize, _handle: Handle, _new_cap: usize, _size_of: usize, _options: &Options) -> Option<(NonNull<u8>, usize)> {
+	None
+}
+
+#[inline]
+pub unsafe fn free(ptr: NonNull<u8>, cap: usize, _handle: Handle) {
+	if cap != 0 {
+		alloc::dealloc(ptr.as_ptr(), layout(cap));
+	}
+}
+
+// Moves the `len` bytes at `base + cap` which wrapped around to `base` in the first half
+#[inline]
+pub unsafe fn wrap(ptr: NonNull<u8>, cap: usize, base: usize, len: usize) {
+	let ptr = ptr.as_ptr();
+	ptr.add(base + cap).copy_to(ptr.add(base), len);
+	// Leave no copies of the elements behind
+	#[cfg(feature = "zeroize")]
+	crate::wipe::wipe(ptr.add(base + cap), len);
+}
+
+// Heap memory cannot be decommitted
+#[inline]
+pub unsafe fn decommit(_ptr: NonNull<u8>, _len: usize) {}
+
+// Locking memory is not supported
+#[inline]
+pub unsafe fn lock(_ptr: NonNull<u8>, cap: usize) -> Result<(), Error> {
+	Err(PlatformError::Unsupported { call: "lock", size: cap + cap })
+}
+
+#[inline]
+pub unsafe fn unlock(_ptr: NonNull<u8>, _cap: usize) {}
+
+// Left to the global allocator
+#[inline]
+pub unsafe fn prefault(_ptr: NonNull<u8>, _cap: usize) {}
@@ -1,62 +1,566 @@
-use std::ptr;
-use std::ptr::NonNull;
+use core::{cmp, mem, ptr};
+use core::ops::Range;
+use core::ptr::NonNull;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::{fs, io};
+#[cfg(target_os = "android")]
+use std::sync::OnceLock;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(target_os = "android")]
+use crate::PlatformError;
+use crate::TryReserveError;
+use super::{Error, Options};
+
+// Linux backs the mapping with a memfd, or an unlinked shared memory object where memfds are not available.
+// So does Android unless its seccomp policy doesn't allow memfds, then it falls back to ashmem or an unlinked temporary file.
+// FreeBSD uses an anonymous shared memory object and OpenBSD, NetBSD, illumos and Solaris
+// a shared memory object which is unlinked right after creating it.
+// All are file descriptors which are mapped twice back to back, all but ashmem are resized with ftruncate.
+
+// The memfd backing the mapping.
+pub type Handle = libc::c_int;
+pub const NULL_HANDLE: Handle = -1;
 
 #[inline]
 pub fn granularity() -> usize {
 	unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
 }
 
+// Default size of the huge pages in hugetlbfs
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn huge_page_size() -> Option<usize> {
+	// Cached as 0 if there are no huge pages, usize::MAX until it is read
+	static HUGE_PAGE_SIZE: AtomicUsize = AtomicUsize::new(usize::MAX);
+	let mut size = HUGE_PAGE_SIZE.load(Ordering::Relaxed);
+	if size == usize::MAX {
+		size = read_huge_page_size().unwrap_or(0);
+		HUGE_PAGE_SIZE.store(size, Ordering::Relaxed);
+	}
+	Some(size).filter(|&size| size != 0)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_huge_page_size() -> Option<usize> {
+	let meminfo = read_proc("/proc/meminfo\0")?;
+	let line = meminfo.lines().find(|line| line.starts_with("Hugepagesize:"))?;
+	let kb: usize = line["Hugepagesize:".len()..].trim().strip_suffix("kB")?.trim().parse().ok()?;
+	Some(kb * 1024).filter(|size| size.is_power_of_two())
+}
+
+// Reads a file in procfs, the path is nul terminated for the system calls without std
+#[cfg(feature = "std")]
+fn read_proc(path: &str) -> Option<String> {
+	fs::read_to_string(path.trim_end_matches('\0')).ok()
+}
+
+#[cfg(not(feature = "std"))]
+fn read_proc(path: &str) -> Option<String> {
+	let fd = unsafe { libc::open(path.as_ptr() as *const libc::c_char, libc::O_RDONLY | libc::O_CLOEXEC) };
+	if fd < 0 {
+		return None;
+	}
+	let mut contents = Vec::<u8>::new();
+	let n = loop {
+		contents.reserve(4096);
+		let spare = contents.capacity() - contents.len();
+		let n = unsafe { libc::read(fd, contents.as_mut_ptr().add(contents.len()) as *mut libc::c_void, spare) };
+		if n <= 0 {
+			break n;
+		}
+		unsafe { contents.set_len(contents.len() + n as usize); }
+	};
+	unsafe { libc::close(fd); }
+	if n < 0 {
+		return None;
+	}
+	String::from_utf8(contents).ok()
+}
+
+// Huge pages are only supported through hugetlbfs
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[inline]
+pub fn huge_page_size() -> Option<usize> {
+	None
+}
+
+// Size of the pages backing the memfd, the huge page size for hugetlbfs
+pub fn page_size(_ptr: NonNull<u8>, fd: Handle) -> usize {
+	let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+	if fd == NULL_HANDLE || unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+		return granularity();
+	}
+	cmp::max(unsafe { stat.assume_init() }.st_blksize as usize, granularity())
+}
+
 #[inline(never)]
-pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
+pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
 	if cap == 0 {
-		return (NonNull::dangling(), 0);
+		return Ok((NonNull::dangling(), 0, NULL_HANDLE));
+	}
+
+	// Huge pages must be reserved in hugetlbfs, fall back to regular pages when they run out
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	if options.huge_pages {
+		if let Some(huge) = huge_page_size() {
+			if let Ok(alloc) = allocate_memfd(cap, size_of, huge, libc::MFD_HUGETLB, options) {
+				return Ok(alloc);
+			}
+		}
+	}
+	allocate_memfd(cap, size_of, granularity(), 0, options)
+}
+
+unsafe fn allocate_memfd(cap: usize, size_of: usize, page: usize, flags: libc::c_uint, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
+	// Round capacity to nearest multiple of the page size
+	let cap = super::try_round_capacity(cap, size_of, page).ok_or(TryReserveError::CapacityOverflow)?;
+
+	let fd = create_fd(cap, flags, options).map_err(TryReserveError::AllocError)?;
+
+	// Map the file twice back to back
+	match map_mirrored(fd, cap, alignment(page, options), options) {
+		Ok(ptr) => {
+			advise(ptr, cap, options);
+			Ok((ptr, cap, fd))
+		},
+		Err(err) => {
+			libc::close(fd);
+			Err(TryReserveError::AllocError(err))
+		},
+	}
+}
+
+// Creates the file of `cap` bytes backing the ring buffer, it is kept open so don't leak it into executed programs
+// Kernels before 3.17 don't have memfd_create and seccomp policies of some containers don't allow it, fall back to shared memory objects
+#[cfg(target_os = "linux")]
+unsafe fn create_fd(cap: usize, flags: libc::c_uint, options: &Options) -> Result<libc::c_int, Error> {
+	match create_memfd(cap, flags, options) {
+		// Shared memory objects don't support huge pages, allocating falls back to regular pages first
+		// Nor can they be sealed, map_backing rejects them
+		Err(err) if flags == 0 && matches!(err.raw_os_error(), Some(libc::ENOSYS) | Some(libc::EPERM)) => create_shm(cap),
+		result => result,
+	}
+}
+
+// Seccomp policies before Android 11 don't allow memfd_create, fall back to ashmem and then to an unlinked temporary file
+#[cfg(target_os = "android")]
+unsafe fn create_fd(cap: usize, flags: libc::c_uint, options: &Options) -> Result<libc::c_int, Error> {
+	if android_api_level() >= 30 {
+		match create_memfd(cap, flags, options) {
+			Err(err) if flags == 0 && matches!(err.raw_os_error(), Some(libc::ENOSYS) | Some(libc::EPERM)) => (),
+			result => return result,
+		}
+	}
+	// Neither supports huge pages
+	if flags != 0 {
+		return Err(PlatformError::Os { call: "memfd_create", code: libc::ENOSYS, size: cap });
+	}
+	if let Some(fd) = create_ashmem(cap, options) {
+		return Ok(fd);
+	}
+	create_tmpfile(cap)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn create_memfd(cap: usize, flags: libc::c_uint, options: &Options) -> Result<libc::c_int, Error> {
+	let name = memfd_name(options);
+	let flags = libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING | flags;
+	// The libc function needs API level 30 on Android, older devices fail to load the library even if it is never called
+	#[cfg(target_os = "linux")]
+	let fd = libc::memfd_create(name.as_ptr() as *const libc::c_char, flags);
+	#[cfg(target_os = "android")]
+	let fd = libc::syscall(libc::SYS_memfd_create, name.as_ptr(), flags) as libc::c_int;
+	let fd = sized("memfd_create", fd, cap)?;
+	// Seal the memfd against shrinking, truncating a shared memfd would crash the mappings with SIGBUS
+	// It is not sealed against growing as the ring buffer grows it in place, growing does not affect existing mappings
+	if libc::fcntl(fd, libc::F_ADD_SEALS, libc::F_SEAL_SHRINK | libc::F_SEAL_SEAL) != 0 {
+		let err = super::os_error("fcntl", cap);
+		libc::close(fd);
+		return Err(err);
+	}
+	Ok(fd)
+}
+
+// Android API level of the device, from the system property as the libc function needs API level 29
+#[cfg(target_os = "android")]
+fn android_api_level() -> u32 {
+	static API_LEVEL: OnceLock<u32> = OnceLock::new();
+	*API_LEVEL.get_or_init(|| {
+		let mut value = [0u8; libc::PROP_VALUE_MAX as usize];
+		let len = unsafe { libc::__system_property_get(b"ro.build.version.sdk\0".as_ptr() as *const libc::c_char, value.as_mut_ptr() as *mut libc::c_char) };
+		std::str::from_utf8(&value[..cmp::max(len, 0) as usize]).ok().and_then(|value| value.parse().ok()).unwrap_or(0)
+	})
+}
+
+// ASharedMemory_create is available from API level 26, look it up at runtime to support older devices
+// Ashmem regions are sized when they are created and cannot be resized, growing the ring buffer reallocates
+#[cfg(target_os = "android")]
+unsafe fn create_ashmem(cap: usize, options: &Options) -> Option<libc::c_int> {
+	type Create = unsafe extern "C" fn(name: *const libc::c_char, size: libc::size_t) -> libc::c_int;
+	static CREATE: OnceLock<Option<Create>> = OnceLock::new();
+	let create = (*CREATE.get_or_init(|| {
+		let lib = libc::dlopen(b"libandroid.so\0".as_ptr() as *const libc::c_char, libc::RTLD_NOW);
+		if lib.is_null() {
+			return None;
+		}
+		let sym = libc::dlsym(lib, b"ASharedMemory_create\0".as_ptr() as *const libc::c_char);
+		if sym.is_null() { None } else { Some(mem::transmute::<*mut libc::c_void, Create>(sym)) }
+	}))?;
+	let name = memfd_name(options);
+	let fd = create(name.as_ptr() as *const libc::c_char, cap);
+	if fd < 0 {
+		return None;
+	}
+	libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+	Some(fd)
+}
+
+// Creates a temporary file and unlinks it right away, in $TMPDIR or the shell's temporary directory
+#[cfg(target_os = "android")]
+unsafe fn create_tmpfile(cap: usize) -> Result<libc::c_int, Error> {
+	let dir = std::env::var_os("TMPDIR").unwrap_or_else(|| "/data/local/tmp".into());
+	let mut template = std::path::Path::new(&dir).join("ringbuf.XXXXXX").into_os_string().into_encoded_bytes();
+	template.push(0);
+	let fd = libc::mkostemp(template.as_mut_ptr() as *mut libc::c_char, libc::O_CLOEXEC);
+	if fd >= 0 {
+		libc::unlink(template.as_ptr() as *const libc::c_char);
+	}
+	sized("mkostemp", fd, cap)
+}
+
+// Anonymous shared memory objects have no name and are removed when their last descriptor is closed
+#[cfg(target_os = "freebsd")]
+unsafe fn create_fd(cap: usize, _flags: libc::c_uint, _options: &Options) -> Result<libc::c_int, Error> {
+	sized("shm_open", libc::shm_open(libc::SHM_ANON, libc::O_RDWR | libc::O_CLOEXEC, 0o600), cap)
+}
+
+// Creates a shared memory object with a random name and unlinks it right away
+#[cfg(target_os = "openbsd")]
+unsafe fn create_fd(cap: usize, _flags: libc::c_uint, _options: &Options) -> Result<libc::c_int, Error> {
+	extern "C" {
+		fn shm_mkstemp(template: *mut libc::c_char) -> libc::c_int;
 	}
+	let mut template = *b"/ringbuf.XXXXXXXXXX\0";
+	let fd = shm_mkstemp(template.as_mut_ptr() as *mut libc::c_char);
+	if fd >= 0 {
+		libc::shm_unlink(template.as_ptr() as *const libc::c_char);
+		libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+	}
+	sized("shm_mkstemp", fd, cap)
+}
 
-	// Round capacity to nearest multiple of the system's allocation granularity
-	let cap = super::round_capacity(cap, size_of);
+#[cfg(any(target_os = "netbsd", target_os = "illumos", target_os = "solaris"))]
+#[inline]
+unsafe fn create_fd(cap: usize, _flags: libc::c_uint, _options: &Options) -> Result<libc::c_int, Error> {
+	create_shm(cap)
+}
+
+// Creates a shared memory object with a unique name and unlinks it right away
+#[cfg(any(target_os = "linux", target_os = "netbsd", target_os = "illumos", target_os = "solaris"))]
+unsafe fn create_shm(cap: usize) -> Result<libc::c_int, Error> {
+	use core::sync::atomic::{AtomicUsize, Ordering};
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+	loop {
+		let name = format!("/ringbuf.{}.{}\0", libc::getpid(), COUNTER.fetch_add(1, Ordering::Relaxed));
+		let fd = libc::shm_open(name.as_ptr() as *const libc::c_char, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_CLOEXEC, 0o600);
+		if fd >= 0 {
+			libc::shm_unlink(name.as_ptr() as *const libc::c_char);
+		}
+		// Names left behind by a crashed process with the same pid are skipped
+		if fd >= 0 || super::errno() != libc::EEXIST {
+			return sized("shm_open", fd, cap);
+		}
+	}
+}
 
-	// Create the file backing the ring buffer
-	let fd = libc::syscall(libc::SYS_memfd_create, b"ringbuf\0".as_ptr(), 0) as i32;
+// Sizes a file newly created by `call`, closes it if that fails
+unsafe fn sized(call: &'static str, fd: libc::c_int, cap: usize) -> Result<libc::c_int, Error> {
 	if fd < 0 {
-		error("memfd_create")
+		return Err(super::os_error(call, cap));
 	}
 	if libc::ftruncate(fd, cap as libc::off_t) != 0 {
+		let err = super::os_error("ftruncate", cap);
 		libc::close(fd);
-		error("ftruncate")
+		return Err(err);
 	}
+	Ok(fd)
+}
 
-	// Reserve memory for twice the capacity
-	let base = libc::mmap(ptr::null_mut(), cap + cap, libc::PROT_NONE, libc::MAP_PRIVATE|libc::MAP_ANONYMOUS, -1, 0);
-	if base == libc::MAP_FAILED || base.is_null() {
-		libc::close(fd);
-		error("mmap")
+// Shows up as `/memfd:ringbuf:<name>` in /proc/<pid>/maps, the kernel limits the name to 249 bytes
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn memfd_name(options: &Options) -> Vec<u8> {
+	let mut name = b"ringbuf".to_vec();
+	if let Some(suffix) = &options.name {
+		name.push(b':');
+		name.extend_from_slice(suffix.as_bytes());
+		name.truncate(249);
 	}
+	name.push(0);
+	name
+}
 
-	// Replace the reserved memory with the ring buffer mapping
-	let addr1 = base;
-	let ptr1 = libc::mmap(addr1, cap, libc::PROT_READ|libc::PROT_WRITE, libc::MAP_SHARED|libc::MAP_FIXED, fd, 0);
-	let addr2 = (base as *mut u8).add(cap) as *mut libc::c_void;
-	let ptr2 = libc::mmap(addr2, cap, libc::PROT_READ|libc::PROT_WRITE, libc::MAP_SHARED|libc::MAP_FIXED, fd, 0);
+// Maps the memfd twice back to back, huge pages must be mapped at aligned addresses
+unsafe fn map_mirrored(fd: Handle, cap: usize, align: usize, options: &Options) -> Result<NonNull<u8>, Error> {
+	let extra = map_flags(options);
+	let guard = guard_size(options);
+	let start = match &options.address_range {
+		Some(range) => reserve_in(range, cap + cap, align, guard)?,
+		None if align <= granularity() && guard == 0 => return super::shm::map(fd, 0, cap, extra),
+		None => reserve_aligned(cap + cap, align, guard)?,
+	};
 
-	libc::close(fd);
+	// Like shm::map only the reservation is replaced
+	let prot = libc::PROT_READ|libc::PROT_WRITE;
+	let flags = libc::MAP_SHARED|libc::MAP_FIXED|extra;
+	let addr1 = start as *mut libc::c_void;
+	let addr2 = start.add(cap) as *mut libc::c_void;
+	let ptr1 = libc::mmap(addr1, cap, prot, flags, fd, 0);
+	let ptr2 = libc::mmap(addr2, cap, prot, flags, fd, 0);
+	if ptr1 == addr1 && ptr2 == addr2 {
+		return Ok(NonNull::new_unchecked(start));
+	}
+
+	let err = super::os_error("mmap", cap);
+	unmap(start, cap, guard);
+	Err(err)
+}
+
+// Reserves enough memory to align the reservation and trims the excess
+// The `guard` bytes before and after the aligned `size` bytes stay reserved, the returned address points after the leading guard
+unsafe fn reserve_aligned(size: usize, align: usize, guard: usize) -> Result<*mut u8, Error> {
+	let len = guard + size + guard + align;
+	let base = libc::mmap(ptr::null_mut(), len, libc::PROT_NONE, libc::MAP_PRIVATE|libc::MAP_ANONYMOUS, -1, 0);
+	if base == libc::MAP_FAILED {
+		return Err(super::os_error("mmap", len));
+	}
+	let head = (align - (base as usize + guard) % align) % align;
+	let start = (base as *mut u8).add(head + guard);
+	if head > 0 {
+		libc::munmap(base, head);
+	}
+	let tail = len - head - guard - size - guard;
+	if tail > 0 {
+		libc::munmap(start.add(size + guard) as *mut libc::c_void, tail);
+	}
+	Ok(start)
+}
+
+// Reserves memory at the first address within the range where it fits together with its guards
+// Systems without a flag to refuse replacing existing mappings take the address as a hint, which is not honored if it is taken
+unsafe fn reserve_in(range: &Range<usize>, size: usize, align: usize, guard: usize) -> Result<*mut u8, Error> {
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	const NOREPLACE: libc::c_int = libc::MAP_FIXED_NOREPLACE;
+	#[cfg(target_os = "freebsd")]
+	const NOREPLACE: libc::c_int = libc::MAP_FIXED | libc::MAP_EXCL;
+	#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+	const NOREPLACE: libc::c_int = 0;
+
+	let len = guard + size + guard;
+	let inner = range.start.saturating_add(guard)..range.end.saturating_sub(guard);
+	for addr in super::candidates(&inner, size, align) {
+		let ptr = libc::mmap((addr - guard) as *mut libc::c_void, len, libc::PROT_NONE, libc::MAP_PRIVATE|libc::MAP_ANONYMOUS|NOREPLACE, -1, 0);
+		if ptr as usize == addr - guard {
+			return Ok(addr as *mut u8);
+		}
+		// Kernels before 4.17 ignore MAP_FIXED_NOREPLACE and map elsewhere
+		if ptr != libc::MAP_FAILED {
+			libc::munmap(ptr, len);
+		}
+	}
+	Err(super::out_of_range(size))
+}
+
+// Size of the inaccessible guards reserved before and after the mapping
+#[inline]
+fn guard_size(options: &Options) -> usize {
+	if options.guard_pages { granularity() } else { 0 }
+}
+
+// Unmaps both views and the guards around them
+#[inline]
+unsafe fn unmap(ptr: *mut u8, cap: usize, guard: usize) {
+	libc::munmap(ptr.sub(guard) as *mut libc::c_void, guard + cap + cap + guard);
+}
 
-	if addr1 == ptr1 && addr2 == ptr2 {
-		return (NonNull::new_unchecked(base as *mut u8), cap);
+// Transparent huge pages only back the parts of the mapping aligned to the huge page size
+fn alignment(page: usize, options: &Options) -> usize {
+	match (options.transparent_huge_pages, huge_page_size()) {
+		(true, Some(huge)) => cmp::max(page, huge),
+		_ => page,
 	}
+}
 
-	libc::munmap(base, cap + cap);
-	error("mmap")
+// Additional mmap flags asked for by the options, OpenBSD excludes concealed mappings from core dumps
+#[inline]
+fn map_flags(options: &Options) -> libc::c_int {
+	#[cfg(target_os = "openbsd")]
+	if options.exclude_from_dumps {
+		return libc::MAP_CONCEAL;
+	}
+	let _ = options;
+	0
 }
 
+// Applies the advice asked for by the options to both views, failing is harmless
 #[inline]
-pub unsafe fn free(ptr: NonNull<u8>, cap: usize) {
+unsafe fn advise(ptr: NonNull<u8>, cap: usize, options: &Options) {
+	let addr = ptr.as_ptr() as *mut libc::c_void;
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	{
+		if options.transparent_huge_pages {
+			libc::madvise(addr, cap + cap, libc::MADV_HUGEPAGE);
+		}
+		if options.exclude_from_dumps {
+			libc::madvise(addr, cap + cap, libc::MADV_DONTDUMP);
+		}
+	}
+	#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+	if options.exclude_from_dumps {
+		libc::madvise(addr, cap + cap, libc::MADV_NOCORE);
+	}
+	let _ = (addr, cap, options);
+}
+
+// Looks up the mapping in /proc/self/smaps, the kernel marks mappings with the transparent huge page hint as `hg`
+pub fn huge_page_hint(ptr: NonNull<u8>, fd: Handle) -> bool {
+	if fd == NULL_HANDLE {
+		return false;
+	}
+	let smaps = match read_proc("/proc/self/smaps\0") {
+		Some(smaps) => smaps,
+		None => return false,
+	};
+	let start = format!("{:x}-", ptr.as_ptr() as usize);
+	let mut lines = smaps.lines().skip_while(|line| !line.starts_with(&start));
+	match lines.find(|line| line.starts_with("VmFlags:")) {
+		Some(line) => line.split_whitespace().any(|flag| flag == "hg"),
+		None => false,
+	}
+}
+
+// Grows the memfd and maps it again into a larger reservation, the old mapping is freed on success
+//
+// The existing pages keep their offsets in the memfd so nothing is copied, None if the memfd cannot be grown.
+pub unsafe fn grow(ptr: NonNull<u8>, cap: usize, fd: Handle, new_cap: usize, size_of: usize, options: &Options) -> Option<(NonNull<u8>, usize)> {
+	let page = options.granularity();
+	let new_cap = super::try_round_capacity(new_cap, size_of, page)?;
+	if fd == NULL_HANDLE || new_cap <= cap {
+		return None;
+	}
+	// The memfd is sealed against shrinking, map it before growing so failing leaves its size alone
+	let new_ptr = map_mirrored(fd, new_cap, alignment(page, options), options).ok()?;
+	let guard = guard_size(options);
+	if libc::ftruncate(fd, new_cap as libc::off_t) != 0 {
+		unmap(new_ptr.as_ptr(), new_cap, guard);
+		return None;
+	}
+	advise(new_ptr, new_cap, options);
+	if options.lock_memory && lock(new_ptr, new_cap).is_err() {
+		unmap(new_ptr.as_ptr(), new_cap, guard);
+		return None;
+	}
+	unmap(ptr.as_ptr(), cap, guard);
+	Some((new_ptr, new_cap))
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn map_backing(fd: Handle) -> io::Result<(NonNull<u8>, usize)> {
+	let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+	if libc::fstat(fd, stat.as_mut_ptr()) != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let cap = stat.assume_init().st_size as usize;
+	if cap == 0 || cap & (granularity() - 1) != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid backing file size"));
+	}
+	// Truncating the file would crash the mapping with SIGBUS
+	#[cfg(target_os = "linux")]
+	{
+		let seals = libc::fcntl(fd, libc::F_GET_SEALS);
+		if seals < 0 || seals & libc::F_SEAL_SHRINK == 0 {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "backing file is not sealed against shrinking"));
+		}
+	}
+	Ok((super::shm::map(fd, 0, cap, 0)?, cap))
+}
+
+#[inline]
+pub unsafe fn free(ptr: NonNull<u8>, cap: usize, fd: Handle) {
 	let ptr = ptr.as_ptr();
 	libc::munmap(ptr as *mut libc::c_void, cap + cap);
+	if fd != NULL_HANDLE {
+		libc::close(fd);
+	}
+}
+
+// Unmaps the guards reserved around a mapping allocated with guard pages
+#[inline]
+pub unsafe fn free_guards(ptr: NonNull<u8>, cap: usize) {
+	let guard = granularity();
+	libc::munmap(ptr.as_ptr().sub(guard) as *mut libc::c_void, guard);
+	libc::munmap(ptr.as_ptr().add(cap + cap) as *mut libc::c_void, guard);
 }
 
-#[cold]
-#[track_caller]
-fn error(name: &str) -> ! {
-	let errno = unsafe { *libc::__errno_location() };
-	panic!("{}(): {}", name, errno)
+// Revokes access to the pages of both views outside of the `len` bytes at `offset`, wrapping around at the end of the first view
+pub unsafe fn protect(ptr: NonNull<u8>, cap: usize, page: usize, offset: usize, len: usize) {
+	let ptr = ptr.as_ptr();
+	libc::mprotect(ptr as *mut libc::c_void, cap + cap, libc::PROT_READ|libc::PROT_WRITE);
+	let start = offset & !(page - 1);
+	let end = (offset + len + page - 1) & !(page - 1);
+	if end - start >= cap {
+		return;
+	}
+	let revoke = |at: usize, len: usize| {
+		libc::mprotect(ptr.add(at) as *mut libc::c_void, len, libc::PROT_NONE);
+		libc::mprotect(ptr.add(cap + at) as *mut libc::c_void, len, libc::PROT_NONE);
+	};
+	// The pages from the end around to the start
+	let at = end % cap;
+	let len = cap - (end - start);
+	if at + len <= cap {
+		revoke(at, len);
+	}
+	else {
+		revoke(at, cap - at);
+		revoke(0, at + len - cap);
+	}
+}
+
+// Frees the pages backing the range, they read as zeroes afterwards
+#[inline]
+pub unsafe fn decommit(ptr: NonNull<u8>, len: usize) {
+	// MADV_DONTNEED would only drop the page table entries, the memfd keeps the pages
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	let advice = libc::MADV_REMOVE;
+	#[cfg(not(any(target_os = "linux", target_os = "android")))]
+	let advice = libc::MADV_DONTNEED;
+	libc::madvise(ptr.as_ptr() as *mut libc::c_void, len, advice);
+}
+
+// Locks the pages of both views into physical memory
+#[inline]
+pub unsafe fn lock(ptr: NonNull<u8>, cap: usize) -> Result<(), Error> {
+	if libc::mlock(ptr.as_ptr() as *const libc::c_void, cap + cap) != 0 {
+		return Err(super::os_error("mlock", cap + cap));
+	}
+	Ok(())
+}
+
+#[inline]
+pub unsafe fn unlock(ptr: NonNull<u8>, cap: usize) {
+	libc::munlock(ptr.as_ptr() as *const libc::c_void, cap + cap);
+}
+
+// Populates the page tables of both views, touching the pages on kernels older than 5.14
+#[inline]
+pub unsafe fn prefault(ptr: NonNull<u8>, cap: usize) {
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	if libc::madvise(ptr.as_ptr() as *mut libc::c_void, cap + cap, libc::MADV_POPULATE_WRITE) == 0 {
+		return;
+	}
+	super::touch(ptr, cap + cap);
 }
@@ -2,7 +2,9 @@ use std::mem;
 use std::ptr::NonNull;
 
 use mach::kern_return::*;
+use mach::mach_port::mach_port_deallocate;
 use mach::memory_object_types::memory_object_size_t;
+use mach::port::{mach_port_t, MACH_PORT_NULL};
 use mach::traps::mach_task_self;
 use mach::vm::{mach_make_memory_entry_64, mach_vm_allocate, mach_vm_deallocate, mach_vm_remap};
 use mach::vm_inherit::VM_INHERIT_NONE;
@@ -10,34 +12,70 @@ use mach::vm_prot::*;
 use mach::vm_statistics::{VM_FLAGS_ANYWHERE, VM_FLAGS_FIXED, VM_FLAGS_OVERWRITE};
 use mach::vm_types::mach_vm_address_t;
 
+use crate::{PlatformError, TryReserveError};
+use super::{Error, Options};
+
+// The memory entry backing the mapping.
+pub type Handle = mach_port_t;
+pub const NULL_HANDLE: Handle = MACH_PORT_NULL;
+
 #[inline]
 pub fn granularity() -> usize {
 	unsafe { mach::vm_page_size::vm_page_size as usize }
 }
 
+// Huge pages are not supported
+#[inline]
+pub fn huge_page_size() -> Option<usize> {
+	None
+}
+
+#[inline]
+pub fn page_size(_ptr: NonNull<u8>, _handle: Handle) -> usize {
+	granularity()
+}
+
+// Transparent huge pages are not supported
+#[inline]
+pub fn huge_page_hint(_ptr: NonNull<u8>, _handle: Handle) -> bool {
+	false
+}
+
 #[inline(never)]
-pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
+pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
 	if cap == 0 {
-		return (NonNull::dangling(), 0);
+		return Ok((NonNull::dangling(), 0, NULL_HANDLE));
 	}
 
 	// Round capacity to nearest multiple of the system's allocation granularity
-	let cap = super::round_capacity(cap, size_of);
+	let cap = super::try_round_capacity(cap, size_of, granularity()).ok_or(TryReserveError::CapacityOverflow)?;
 
 	let task = mach_task_self();
 
-	// Reserve memory for twice the capacity
+	// Reserve memory for twice the capacity, at the first address within the address range where it fits
 	let mut addr: mach_vm_address_t = 0;
-	let ret = mach_vm_allocate(task, &mut addr, (cap + cap) as u64, VM_FLAGS_ANYWHERE);
-	if ret != KERN_SUCCESS {
-		error("vm_allocate", ret)
+	if let Some(range) = &options.address_range {
+		let fits = super::candidates(range, cap + cap, granularity()).any(|candidate| {
+			addr = candidate as mach_vm_address_t;
+			mach_vm_allocate(task, &mut addr, (cap + cap) as u64, VM_FLAGS_FIXED) == KERN_SUCCESS
+		});
+		if !fits {
+			return Err(TryReserveError::AllocError(super::out_of_range(cap + cap)));
+		}
+	}
+	else {
+		let ret = mach_vm_allocate(task, &mut addr, (cap + cap) as u64, VM_FLAGS_ANYWHERE);
+		if ret != KERN_SUCCESS {
+			return Err(error("mach_vm_allocate", ret, cap + cap));
+		}
 	}
 	debug_assert!(addr != 0);
 
 	// Allocate the first half of the reserved memory
 	let ret = mach_vm_allocate(task, &mut addr, cap as u64, VM_FLAGS_FIXED|VM_FLAGS_OVERWRITE);
 	if ret != KERN_SUCCESS {
-		error("vm_allocate", ret)
+		mach_vm_deallocate(task, addr, (cap + cap) as u64);
+		return Err(error("mach_vm_allocate", ret, cap));
 	}
 
 	// Get an object handle to the first memory region
@@ -47,7 +85,7 @@ pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
 	let ret = mach_make_memory_entry_64(task, &mut memory_object_size, addr, VM_PROT_READ|VM_PROT_WRITE, object_handle.as_mut_ptr(), parent_handle);
 	if ret != KERN_SUCCESS {
 		mach_vm_deallocate(task, addr, (cap + cap) as u64);
-		error("make_memory_entry_64", ret)
+		return Err(error("mach_make_memory_entry_64", ret, cap));
 	}
 
 	// Map the first half to the second half using the object handle
@@ -55,24 +93,58 @@ pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
 	let mut current_prot = mem::MaybeUninit::uninit();
 	let mut out_prot = mem::MaybeUninit::uninit();
 	let ret = mach_vm_remap(task, &mut to, cap as u64, /*mask:*/0, VM_FLAGS_FIXED|VM_FLAGS_OVERWRITE, task, addr, /*copy:*/0, current_prot.as_mut_ptr(), out_prot.as_mut_ptr(), VM_INHERIT_NONE);
+	let object_handle = object_handle.assume_init();
 	if ret != KERN_SUCCESS {
 		mach_vm_deallocate(task, addr, (cap + cap) as u64);
-		error("vm_remap", ret)
+		mach_port_deallocate(task, object_handle);
+		return Err(error("mach_vm_remap", ret, cap));
 	}
 
-	// TODO: object_handle is leaked here. Investigate whether this is ok
-	(NonNull::new_unchecked(addr as *mut u8), cap)
+	Ok((NonNull::new_unchecked(addr as *mut u8), cap, object_handle))
 }
 
+// The mapping cannot be grown in place, reallocating copies the elements
 #[inline]
-pub unsafe fn free(ptr: NonNull<u8>, cap: usize) {
+pub unsafe fn grow(_ptr: NonNull<u8>, _cap: usize, _handle: Handle, _new_cap: usize, _size_of: usize, _options: &Options) -> Option<(NonNull<u8>, usize)> {
+	None
+}
+
+#[inline]
+pub unsafe fn free(ptr: NonNull<u8>, cap: usize, handle: Handle) {
 	let addr = ptr.as_ptr() as mach_vm_address_t;
 	let size = (cap + cap) as u64;
 	mach_vm_deallocate(mach_task_self(), addr, size);
+	if handle != NULL_HANDLE {
+		mach_port_deallocate(mach_task_self(), handle);
+	}
+}
+
+// Lets the kernel reclaim the pages backing the range, their contents become undefined
+#[inline]
+pub unsafe fn decommit(ptr: NonNull<u8>, len: usize) {
+	libc::madvise(ptr.as_ptr() as *mut libc::c_void, len, libc::MADV_FREE);
+}
+
+// Locks the pages of both views into physical memory
+#[inline]
+pub unsafe fn lock(ptr: NonNull<u8>, cap: usize) -> Result<(), Error> {
+	if libc::mlock(ptr.as_ptr() as *const libc::c_void, cap + cap) != 0 {
+		return Err(super::os_error("mlock", cap + cap));
+	}
+	Ok(())
+}
+
+#[inline]
+pub unsafe fn unlock(ptr: NonNull<u8>, cap: usize) {
+	libc::munlock(ptr.as_ptr() as *const libc::c_void, cap + cap);
+}
+
+#[inline]
+pub unsafe fn prefault(ptr: NonNull<u8>, cap: usize) {
+	super::touch(ptr, cap + cap);
 }
 
 #[cold]
-#[track_caller]
-fn error(name: &str, ret: kern_return_t) -> ! {
-	panic!("mach_{}(): {}", name, ret)
+fn error(call: &'static str, code: kern_return_t, size: usize) -> TryReserveError {
+	PlatformError::Mach { call, code, size }.into()
 }
@@ -0,0 +1,223 @@
+use core::ptr;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::{io, mem};
+#[cfg(feature = "std")]
+use std::ffi::CString;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use super::Error;
+
+// Named shared memory objects hold a header of `header` bytes followed by the ring buffer.
+// The header is mapped right before the mirrored ring buffer, returned pointers point at the header.
+// The shared memory object stays open so it can be sent to other processes, the caller closes it.
+// Only mapping is available without std, the Unix backend maps its ring buffers like shared memory objects without a header.
+
+// Permission bits of the shared memory object, the default grants access to the owner.
+#[cfg(feature = "std")]
+pub type Security<'a> = u32;
+#[cfg(feature = "std")]
+pub const DEFAULT_SECURITY: Security<'static> = 0o600;
+
+#[cfg(feature = "std")]
+pub unsafe fn create_shared(name: &str, header: usize, cap: usize, size_of: usize, mode: Security) -> io::Result<(NonNull<u8>, usize, RawFd)> {
+	if cap == 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "zero capacity"));
+	}
+
+	// Round capacity to nearest multiple of the system's allocation granularity
+	let cap = super::round_capacity(cap, size_of);
+
+	let name = shm_name(name)?;
+	// The mode is passed through varargs on Apple platforms
+	#[cfg(any(target_os = "macos", target_os = "ios"))]
+	let mode = mode as libc::c_uint;
+	#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+	let mode = mode as libc::mode_t;
+	let fd = libc::shm_open(name.as_ptr(), libc::O_RDWR|libc::O_CREAT|libc::O_EXCL|libc::O_CLOEXEC, mode);
+	if fd < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let result = if libc::ftruncate(fd, (header + cap) as libc::off_t) != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		map(fd, header, cap, 0).map_err(io::Error::from)
+	};
+
+	match result {
+		Ok(ptr) => Ok((ptr, cap, fd)),
+		Err(err) => {
+			libc::close(fd);
+			libc::shm_unlink(name.as_ptr());
+			Err(err)
+		},
+	}
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn open_shared(name: &str, header: usize) -> io::Result<(NonNull<u8>, usize, RawFd)> {
+	let name = shm_name(name)?;
+	let fd = libc::shm_open(name.as_ptr(), libc::O_RDWR|libc::O_CLOEXEC, 0);
+	if fd < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	match map_shared(fd, header) {
+		Ok((ptr, cap)) => Ok((ptr, cap, fd)),
+		Err(err) => {
+			libc::close(fd);
+			Err(err)
+		},
+	}
+}
+
+// Maps a shared memory object, the capacity is whatever follows the header
+#[cfg(feature = "std")]
+pub unsafe fn map_shared(fd: RawFd, header: usize) -> io::Result<(NonNull<u8>, usize)> {
+	let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+	if libc::fstat(fd, stat.as_mut_ptr()) != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let size = stat.assume_init().st_size as usize;
+	if size <= header || (size - header) & (super::granularity() - 1) != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid shared memory size"));
+	}
+	let cap = size - header;
+	Ok((map(fd, header, cap, 0)?, cap))
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn unlink_shared(name: &str) -> io::Result<()> {
+	let name = shm_name(name)?;
+	if libc::shm_unlink(name.as_ptr()) != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+// Sends a file descriptor along with the data over a Unix domain socket
+#[cfg(feature = "std")]
+pub unsafe fn send_fd(socket: RawFd, fd: RawFd, data: &[u8]) -> io::Result<()> {
+	let mut iov = libc::iovec { iov_base: data.as_ptr() as *mut libc::c_void, iov_len: data.len() };
+	let mut control = [0u64; 8];
+	let mut msg: libc::msghdr = mem::zeroed();
+	msg.msg_iov = &mut iov;
+	msg.msg_iovlen = 1;
+	msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+	msg.msg_controllen = libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) as _;
+
+	let cmsg = libc::CMSG_FIRSTHDR(&msg);
+	(*cmsg).cmsg_level = libc::SOL_SOCKET;
+	(*cmsg).cmsg_type = libc::SCM_RIGHTS;
+	(*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as u32) as _;
+	(libc::CMSG_DATA(cmsg) as *mut RawFd).write_unaligned(fd);
+
+	let n = libc::sendmsg(socket, &msg, 0);
+	if n < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	if n as usize != data.len() {
+		return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to send the whole message"));
+	}
+	Ok(())
+}
+
+// Receives a file descriptor along with the data sent by `send_fd`
+#[cfg(feature = "std")]
+pub unsafe fn recv_fd(socket: RawFd, data: &mut [u8]) -> io::Result<RawFd> {
+	let mut iov = libc::iovec { iov_base: data.as_mut_ptr() as *mut libc::c_void, iov_len: data.len() };
+	let mut control = [0u64; 8];
+	let mut msg: libc::msghdr = mem::zeroed();
+	msg.msg_iov = &mut iov;
+	msg.msg_iovlen = 1;
+	msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+	msg.msg_controllen = libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) as _;
+
+	// Don't leak the file descriptor into executed programs
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	let flags = libc::MSG_CMSG_CLOEXEC;
+	#[cfg(not(any(target_os = "linux", target_os = "android")))]
+	let flags = 0;
+
+	let n = libc::recvmsg(socket, &mut msg, flags);
+	if n < 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let mut fd = -1;
+	let cmsg = libc::CMSG_FIRSTHDR(&msg);
+	if !cmsg.is_null() && (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+		fd = (libc::CMSG_DATA(cmsg) as *const RawFd).read_unaligned();
+		#[cfg(not(any(target_os = "linux", target_os = "android")))]
+		libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+	}
+	if fd < 0 || n as usize != data.len() || msg.msg_flags & libc::MSG_CTRUNC != 0 {
+		if fd >= 0 {
+			libc::close(fd);
+		}
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid message received"));
+	}
+	Ok(fd)
+}
+
+// Files are laid out like the shared memory objects
+#[cfg(feature = "std")]
+#[inline]
+pub unsafe fn map_file(file: &File, header: usize, cap: usize) -> io::Result<NonNull<u8>> {
+	Ok(map(file.as_raw_fd(), header, cap, 0)?)
+}
+
+#[cfg(feature = "std")]
+pub unsafe fn flush(ptr: NonNull<u8>, header: usize, cap: usize) -> io::Result<()> {
+	// The second half of the ring buffer maps the same pages as the first half
+	if libc::msync(ptr.as_ptr() as *mut libc::c_void, header + cap, libc::MS_SYNC) != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub unsafe fn free_shared(ptr: NonNull<u8>, header: usize, cap: usize) {
+	libc::munmap(ptr.as_ptr() as *mut libc::c_void, header + cap + cap);
+}
+
+// Maps the header, if any, followed by the mirrored ring buffer with additional mmap flags
+pub(super) unsafe fn map(fd: libc::c_int, header: usize, cap: usize, extra: libc::c_int) -> Result<NonNull<u8>, Error> {
+	// Reserve memory for the header and twice the capacity
+	let base = libc::mmap(ptr::null_mut(), header + cap + cap, libc::PROT_NONE, libc::MAP_PRIVATE|libc::MAP_ANONYMOUS, -1, 0);
+	if base == libc::MAP_FAILED || base.is_null() {
+		return Err(super::os_error("mmap", header + cap + cap));
+	}
+
+	// Replace the reserved memory with the header and ring buffer mappings
+	// MAP_FIXED only replaces the reservation, the range is never unmapped so no other thread can map anything in between
+	// MAP_FIXED_NOREPLACE would refuse to replace the reservation
+	let prot = libc::PROT_READ|libc::PROT_WRITE;
+	let flags = libc::MAP_SHARED|libc::MAP_FIXED|extra;
+	let addr1 = (base as *mut u8).add(header) as *mut libc::c_void;
+	let addr2 = (base as *mut u8).add(header + cap) as *mut libc::c_void;
+	let ptr0 = if header == 0 { base } else { libc::mmap(base, header, prot, flags, fd, 0) };
+	let ptr1 = libc::mmap(addr1, cap, prot, flags, fd, header as libc::off_t);
+	let ptr2 = libc::mmap(addr2, cap, prot, flags, fd, header as libc::off_t);
+
+	if ptr0 == base && ptr1 == addr1 && ptr2 == addr2 {
+		return Ok(NonNull::new_unchecked(base as *mut u8));
+	}
+
+	let err = super::os_error("mmap", cap);
+	libc::munmap(base, header + cap + cap);
+	Err(err)
+}
+
+// Shared memory object names start with a slash
+#[cfg(feature = "std")]
+fn shm_name(name: &str) -> io::Result<CString> {
+	if name.is_empty() || name.contains('/') {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid shared memory name"));
+	}
+	CString::new(format!("/{}", name)).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid shared memory name"))
+}
@@ -0,0 +1,111 @@
+use std::sync::atomic::AtomicU32;
+use std::time::Duration;
+
+// Waiting on 32-bit words in memory shared between processes.
+//
+// `wait_on` blocks while the word equals `expected` until another thread or process calls `wake_all` on it, or the timeout passes.
+// Both may wake up spuriously, callers check their condition in a loop.
+
+cfg_if::cfg_if! {
+	if #[cfg(any(target_os = "linux", target_os = "android"))] {
+		pub fn wait_on(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+			let ts = timeout.map(|timeout| libc::timespec {
+				tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+				tv_nsec: timeout.subsec_nanos() as _,
+			});
+			let ts = ts.as_ref().map_or(std::ptr::null(), |ts| ts as *const libc::timespec);
+			// Not FUTEX_PRIVATE_FLAG, the word is shared between processes
+			unsafe { libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAIT, expected, ts); }
+		}
+
+		pub fn wake_all(word: &AtomicU32) {
+			unsafe { libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAKE, i32::MAX); }
+		}
+	}
+	else if #[cfg(any(target_os = "macos", target_os = "ios"))] {
+		use std::mem;
+		use std::sync::OnceLock;
+
+		// Available starting with macOS 14.4, older systems fall back to polling
+		struct OsSync {
+			wait: unsafe extern "C" fn(*mut libc::c_void, u64, libc::size_t, u32, u32, u64) -> libc::c_int,
+			wait_forever: unsafe extern "C" fn(*mut libc::c_void, u64, libc::size_t, u32) -> libc::c_int,
+			wake_all: unsafe extern "C" fn(*mut libc::c_void, libc::size_t, u32) -> libc::c_int,
+		}
+
+		fn os_sync() -> Option<&'static OsSync> {
+			static OS_SYNC: OnceLock<Option<OsSync>> = OnceLock::new();
+			OS_SYNC.get_or_init(|| unsafe {
+				let wait = libc::dlsym(libc::RTLD_DEFAULT, b"os_sync_wait_on_address_with_timeout\0".as_ptr() as *const libc::c_char);
+				let wait_forever = libc::dlsym(libc::RTLD_DEFAULT, b"os_sync_wait_on_address\0".as_ptr() as *const libc::c_char);
+				let wake_all = libc::dlsym(libc::RTLD_DEFAULT, b"os_sync_wake_by_address_all\0".as_ptr() as *const libc::c_char);
+				if wait.is_null() || wait_forever.is_null() || wake_all.is_null() {
+					return None;
+				}
+				Some(OsSync {
+					wait: mem::transmute(wait),
+					wait_forever: mem::transmute(wait_forever),
+					wake_all: mem::transmute(wake_all),
+				})
+			}).as_ref()
+		}
+
+		pub fn wait_on(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+			let os_sync = match os_sync() {
+				Some(os_sync) => os_sync,
+				None => return poll(timeout),
+			};
+			let addr = word.as_ptr() as *mut libc::c_void;
+			unsafe {
+				match timeout {
+					Some(timeout) => {
+						let timeout = timeout.as_nanos().clamp(1, u64::MAX as u128) as u64;
+						(os_sync.wait)(addr, expected as u64, 4, libc::OS_SYNC_WAIT_ON_ADDRESS_SHARED, libc::OS_CLOCK_MACH_ABSOLUTE_TIME, timeout);
+					},
+					None => {
+						(os_sync.wait_forever)(addr, expected as u64, 4, libc::OS_SYNC_WAIT_ON_ADDRESS_SHARED);
+					},
+				}
+			}
+		}
+
+		pub fn wake_all(word: &AtomicU32) {
+			if let Some(os_sync) = os_sync() {
+				unsafe { (os_sync.wake_all)(word.as_ptr() as *mut libc::c_void, 4, libc::OS_SYNC_WAKE_BY_ADDRESS_SHARED); }
+			}
+		}
+	}
+	else if #[cfg(windows)] {
+		use winapi::um::synchapi::{WaitOnAddress, WakeByAddressAll};
+
+		// WaitOnAddress only wakes up threads in the same process, waiters poll for other processes
+		pub fn wait_on(word: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+			let timeout = timeout.map_or(POLL_INTERVAL, |timeout| timeout.min(POLL_INTERVAL));
+			let millis = timeout.as_millis().max(1) as u32;
+			let mut expected = expected;
+			unsafe { WaitOnAddress(word.as_ptr() as _, &mut expected as *mut u32 as _, 4, millis); }
+		}
+
+		pub fn wake_all(word: &AtomicU32) {
+			unsafe { WakeByAddressAll(word.as_ptr() as _); }
+		}
+	}
+	else {
+		pub fn wait_on(_word: &AtomicU32, _expected: u32, timeout: Option<Duration>) {
+			poll(timeout)
+		}
+
+		#[inline]
+		pub fn wake_all(_word: &AtomicU32) {}
+	}
+}
+
+// Waiters without a way to be woken up check back regularly
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn poll(timeout: Option<Duration>) {
+	let timeout = timeout.map_or(POLL_INTERVAL, |timeout| timeout.min(POLL_INTERVAL));
+	std::thread::sleep(timeout);
+}
@@ -1,12 +1,33 @@
-use std::{mem, ptr};
+use std::{cmp, io, mem, ptr, thread};
+use std::fs::File;
+use std::ops::Range;
+use std::os::windows::io::AsRawHandle;
 use std::ptr::NonNull;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use winapi::shared::minwindef::*;
+use winapi::shared::sddl::*;
+use winapi::shared::winerror::*;
 use winapi::um::errhandlingapi::*;
 use winapi::um::handleapi::*;
+use winapi::um::libloaderapi::*;
 use winapi::um::memoryapi::*;
+use winapi::um::minwinbase::*;
+use winapi::um::processthreadsapi::*;
+use winapi::um::psapi::*;
+use winapi::um::securitybaseapi::*;
+use winapi::um::winbase::*;
 use winapi::um::winnt::*;
 use winapi::um::sysinfoapi::*;
 
+use crate::TryReserveError;
+use super::{Error, Options};
+
+// The section backing the mapping.
+pub type Handle = HANDLE;
+pub const NULL_HANDLE: Handle = ptr::null_mut();
+
 #[inline]
 pub fn granularity() -> usize {
 	let mut si = mem::MaybeUninit::<SYSTEM_INFO>::uninit();
@@ -17,40 +38,192 @@ pub fn granularity() -> usize {
 	si.dwAllocationGranularity as usize
 }
 
+// Minimum size of large pages, None if the processor does not support them
+#[inline]
+pub fn huge_page_size() -> Option<usize> {
+	match unsafe { GetLargePageMinimum() } {
+		0 => None,
+		size => Some(size),
+	}
+}
+
+// Size of the pages backing the mapping, large pages are always resident in the working set
+pub fn page_size(ptr: NonNull<u8>, map: Handle) -> usize {
+	if map.is_null() {
+		return granularity();
+	}
+	let mut info = PSAPI_WORKING_SET_EX_INFORMATION {
+		VirtualAddress: ptr.as_ptr() as PVOID,
+		VirtualAttributes: PSAPI_WORKING_SET_EX_BLOCK { Flags: 0 },
+	};
+	let ret = unsafe { K32QueryWorkingSetEx(GetCurrentProcess(), &mut info as *mut _ as PVOID, mem::size_of_val(&info) as DWORD) };
+	match (ret != 0 && info.VirtualAttributes.LargePage() != 0, huge_page_size()) {
+		(true, Some(size)) => size,
+		_ => granularity(),
+	}
+}
+
+// Transparent huge pages are not supported
+#[inline]
+pub fn huge_page_hint(_ptr: NonNull<u8>, _handle: Handle) -> bool {
+	false
+}
+
 #[inline(never)]
-pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
+pub unsafe fn allocate(cap: usize, size_of: usize, options: &Options) -> Result<(NonNull<u8>, usize, Handle), TryReserveError> {
 	if cap == 0 {
-		return (NonNull::dangling(), 0);
+		return Ok((NonNull::dangling(), 0, NULL_HANDLE));
+	}
+
+	// Large pages require the lock memory privilege, fall back to regular pages without it
+	if options.huge_pages && lock_memory_privilege() {
+		if let Some(large) = huge_page_size() {
+			let cap = super::try_round_capacity(cap, size_of, large).ok_or(TryReserveError::CapacityOverflow)?;
+			if let Some(result) = allocate_large(cap, large, options) {
+				return Ok(result);
+			}
+		}
 	}
 
 	// Round capacity to nearest multiple of the system's allocation granularity
-	let cap = super::round_capacity(cap, size_of);
+	let cap = super::try_round_capacity(cap, size_of, granularity()).ok_or(TryReserveError::CapacityOverflow)?;
 
 	let cap_high = (cap as u64 >> 32) as u32;
 	let cap_low = (cap as u64 & 0xffffffff) as u32;
 
 	let map = CreateFileMappingW(ptr::null_mut(), ptr::null_mut(), PAGE_READWRITE, cap_high, cap_low, ptr::null());
 	if map.is_null() {
-		error("CreateFileMapping")
+		return Err(TryReserveError::AllocError(super::os_error("CreateFileMappingW", cap)));
 	}
 
-	// Race condition between replacing the virtual memory with file mapping
-	// Attempt it a couple of times and give up otherwise
-	for _ in 0..10 {
-		let base = VirtualAlloc(ptr::null_mut(), cap + cap, MEM_RESERVE, PAGE_READWRITE);
-		if base.is_null() {
+	// The section is kept open to share the mapping
+	match map_mirrored(map, cap, options) {
+		Ok(ptr) => Ok((ptr, cap, map)),
+		Err(err) => {
 			CloseHandle(map);
-			error("VirtualAlloc")
+			Err(TryReserveError::AllocError(err))
+		},
+	}
+}
+
+// Large page sections are committed up front and their views must be aligned to the large page size
+unsafe fn allocate_large(cap: usize, large: usize, options: &Options) -> Option<(NonNull<u8>, usize, Handle)> {
+	let cap_high = (cap as u64 >> 32) as u32;
+	let cap_low = (cap as u64 & 0xffffffff) as u32;
+
+	let map = CreateFileMappingW(INVALID_HANDLE_VALUE, ptr::null_mut(), PAGE_READWRITE | SEC_COMMIT | SEC_LARGE_PAGES, cap_high, cap_low, ptr::null());
+	if map.is_null() {
+		return None;
+	}
+
+	if let Some(result) = map_placeholders(map, &[(0, cap), (0, cap)], large, MEM_LARGE_PAGES, options.address_range.as_ref()) {
+		return match result {
+			Ok(ptr) => Some((ptr, cap, map)),
+			Err(_) => {
+				CloseHandle(map);
+				None
+			},
+		};
+	}
+
+	let result = retry(options, cap, || {
+		let reserve = match reserve_address(options.address_range.as_ref(), cap + cap, large, "VirtualAlloc", |addr, len| VirtualAlloc(addr, len, MEM_RESERVE, PAGE_READWRITE)) {
+			Ok((reserve, _)) => reserve,
+			Err(err) => return Some(Err(err)),
+		};
+		VirtualFree(reserve, 0, MEM_RELEASE);
+		let base = (reserve as usize + (large - 1)) & !(large - 1);
+
+		let access = FILE_MAP_READ | FILE_MAP_WRITE | FILE_MAP_LARGE_PAGES;
+		let p1 = MapViewOfFileEx(map, access, 0, 0, cap, base as *mut _);
+		let p2 = MapViewOfFileEx(map, access, 0, 0, cap, (base + cap) as *mut _);
+
+		if !p1.is_null() && !p2.is_null() {
+			return Some(Ok(NonNull::new_unchecked(base as *mut u8)));
+		}
+
+		if !p1.is_null() {
+			UnmapViewOfFile(p1);
 		}
+		if !p2.is_null() {
+			UnmapViewOfFile(p2);
+		}
+		None
+	});
+	match result {
+		Ok(ptr) => Some((ptr, cap, map)),
+		Err(_) => {
+			CloseHandle(map);
+			None
+		},
+	}
+}
+
+// Enables SeLockMemoryPrivilege for the process once, true if the account holds it
+fn lock_memory_privilege() -> bool {
+	static ENABLED: OnceLock<bool> = OnceLock::new();
+	*ENABLED.get_or_init(|| unsafe {
+		let mut token = ptr::null_mut();
+		if OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token) == 0 {
+			return false;
+		}
+		let mut privileges = TOKEN_PRIVILEGES {
+			PrivilegeCount: 1,
+			Privileges: [LUID_AND_ATTRIBUTES { Luid: mem::zeroed(), Attributes: SE_PRIVILEGE_ENABLED }],
+		};
+		let name = wide(SE_LOCK_MEMORY_NAME).unwrap();
+		let mut enabled = LookupPrivilegeValueW(ptr::null(), name.as_ptr(), &mut privileges.Privileges[0].Luid) != 0
+			&& AdjustTokenPrivileges(token, FALSE, &mut privileges, 0, ptr::null_mut(), ptr::null_mut()) != 0;
+		// Succeeds without enabling privileges the account does not hold
+		if enabled && GetLastError() == ERROR_NOT_ALL_ASSIGNED {
+			enabled = false;
+		}
+		CloseHandle(token);
+		enabled
+	})
+}
+
+pub unsafe fn map_backing(map: Handle) -> io::Result<(NonNull<u8>, usize)> {
+	let cap = section_size(map)?;
+	if cap & (granularity() - 1) != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid section size"));
+	}
+	Ok((map_mirrored(map, cap, &Options::new())?, cap))
+}
+
+// Maps the whole section once to find out its size
+unsafe fn section_size(map: HANDLE) -> io::Result<usize> {
+	let view = MapViewOfFile(map, FILE_MAP_READ|FILE_MAP_WRITE, 0, 0, 0);
+	if view.is_null() {
+		return Err(io::Error::last_os_error());
+	}
+	let mut info = mem::MaybeUninit::<MEMORY_BASIC_INFORMATION>::uninit();
+	let ret = VirtualQuery(view, info.as_mut_ptr(), mem::size_of::<MEMORY_BASIC_INFORMATION>());
+	UnmapViewOfFile(view);
+	if ret == 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(info.assume_init().RegionSize)
+}
+
+// Maps the section twice back to back
+unsafe fn map_mirrored(map: HANDLE, cap: usize, options: &Options) -> Result<NonNull<u8>, Error> {
+	if let Some(result) = map_placeholders(map, &[(0, cap), (0, cap)], granularity(), 0, options.address_range.as_ref()) {
+		return result;
+	}
+
+	retry(options, cap, || {
+		let base = match reserve_address(options.address_range.as_ref(), cap + cap, granularity(), "VirtualAlloc", |addr, len| VirtualAlloc(addr, len, MEM_RESERVE, PAGE_READWRITE)) {
+			Ok((base, _)) => base,
+			Err(err) => return Some(Err(err)),
+		};
 		VirtualFree(base, 0, MEM_RELEASE);
 
 		let p1 = MapViewOfFileEx(map, FILE_MAP_READ|FILE_MAP_WRITE, 0, 0, cap, base);
 		let p2 = MapViewOfFileEx(map, FILE_MAP_READ|FILE_MAP_WRITE, 0, 0, cap, (base as usize + cap) as *mut _);
 
 		if !p1.is_null() && !p2.is_null() {
-			// FIXME! I'm pretty sure it's not okay to close the file mapping handle while using the mapped views
-			CloseHandle(map);
-			return (NonNull::new_unchecked(base as *mut u8), cap);
+			return Some(Ok(NonNull::new_unchecked(base as *mut u8)));
 		}
 
 		if !p1.is_null() {
@@ -59,21 +232,356 @@ pub unsafe fn allocate(cap: usize, size_of: usize) -> (NonNull<u8>, usize) {
 		if !p2.is_null() {
 			UnmapViewOfFile(p2);
 		}
+		None
+	})
+}
+
+// Default number of attempts to map the views
+const MAP_ATTEMPTS: u32 = 10;
+
+// Number of times mapping views at a freed reservation lost the race for the address to another thread
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+pub fn map_retries() -> u64 {
+	RETRIES.load(Ordering::Relaxed)
+}
+
+// Race condition between replacing the virtual memory with file mapping
+// Attempt it as often as the options allow and give up otherwise, `map` returns None if it lost the race mapping views of `size` bytes
+fn retry<R>(options: &Options, size: usize, mut map: impl FnMut() -> Option<Result<R, Error>>) -> Result<R, Error> {
+	let attempts = options.map_attempts.unwrap_or(MAP_ATTEMPTS);
+	for attempt in 0..cmp::max(attempts, 1) {
+		if attempt > 0 {
+			RETRIES.fetch_add(1, Ordering::Relaxed);
+			if !options.map_backoff.is_zero() {
+				thread::sleep(options.map_backoff);
+			}
+		}
+		if let Some(result) = map() {
+			return result;
+		}
 	}
+	Err(super::os_error("MapViewOfFileEx", size))
+}
 
-	CloseHandle(map);
-	error("MapViewOfFileEx")
+// Placeholders reserve address space which stays reserved until views replace them,
+// unlike reserving, freeing and mapping at the freed address no other thread can take the address in between.
+// Available starting with Windows 10 version 1803, older systems fall back to retrying.
+const MEM_RESERVE_PLACEHOLDER: DWORD = 0x00040000;
+const MEM_REPLACE_PLACEHOLDER: DWORD = 0x00004000;
+const MEM_PRESERVE_PLACEHOLDER: DWORD = 0x00000002;
+
+struct Placeholders {
+	virtual_alloc2: unsafe extern "system" fn(HANDLE, PVOID, usize, DWORD, DWORD, PVOID, DWORD) -> PVOID,
+	map_view_of_file3: unsafe extern "system" fn(HANDLE, HANDLE, PVOID, u64, usize, DWORD, DWORD, PVOID, DWORD) -> PVOID,
+}
+
+fn placeholders() -> Option<&'static Placeholders> {
+	static PLACEHOLDERS: OnceLock<Option<Placeholders>> = OnceLock::new();
+	PLACEHOLDERS.get_or_init(|| unsafe {
+		let kernelbase = GetModuleHandleW(wide("kernelbase.dll").unwrap().as_ptr());
+		if kernelbase.is_null() {
+			return None;
+		}
+		let virtual_alloc2 = GetProcAddress(kernelbase, b"VirtualAlloc2\0".as_ptr() as LPCSTR);
+		let map_view_of_file3 = GetProcAddress(kernelbase, b"MapViewOfFile3\0".as_ptr() as LPCSTR);
+		if virtual_alloc2.is_null() || map_view_of_file3.is_null() {
+			return None;
+		}
+		Some(Placeholders {
+			virtual_alloc2: mem::transmute(virtual_alloc2),
+			map_view_of_file3: mem::transmute(map_view_of_file3),
+		})
+	}).as_ref()
+}
+
+// Reserves address space with `alloc`, anywhere with extra room to align it as reservations are only aligned to the allocation granularity,
+// or at the first aligned address within the range where it fits. Returns the reservation and its size
+unsafe fn reserve_address(range: Option<&Range<usize>>, size: usize, align: usize, call: &'static str, mut alloc: impl FnMut(PVOID, usize) -> PVOID) -> Result<(PVOID, usize), Error> {
+	match range {
+		Some(range) => super::candidates(range, size, align)
+			.map(|addr| alloc(addr as PVOID, size))
+			.find(|ptr| !ptr.is_null())
+			.map(|ptr| (ptr, size))
+			.ok_or_else(|| super::out_of_range(size)),
+		None => {
+			let len = size + (align - granularity());
+			let ptr = alloc(ptr::null_mut(), len);
+			if ptr.is_null() {
+				return Err(super::os_error(call, len));
+			}
+			Ok((ptr, len))
+		},
+	}
+}
+
+// Maps views given as (offset, size) back to back at an address aligned to `align` within the range if any, None if placeholders are not supported
+unsafe fn map_placeholders(map: HANDLE, views: &[(u64, usize)], align: usize, flags: DWORD, range: Option<&Range<usize>>) -> Option<Result<NonNull<u8>, Error>> {
+	let api = placeholders()?;
+	let size: usize = views.iter().map(|&(_, size)| size).sum();
+
+	let (reserve, len) = match reserve_address(range, size, align, "VirtualAlloc2", |addr, len| {
+		(api.virtual_alloc2)(ptr::null_mut(), addr, len, MEM_RESERVE | MEM_RESERVE_PLACEHOLDER, PAGE_NOACCESS, ptr::null_mut(), 0)
+	}) {
+		Ok(reserved) => reserved,
+		Err(err) => return Some(Err(err)),
+	};
+	let base = (reserve as usize + (align - 1)) & !(align - 1);
+	let end = reserve as usize + len;
+
+	// Splitting off the extra room leaves it as a placeholder of its own to release
+	if base > reserve as usize {
+		VirtualFree(reserve, base - reserve as usize, MEM_RELEASE | MEM_PRESERVE_PLACEHOLDER);
+		VirtualFree(reserve, 0, MEM_RELEASE);
+	}
+	if base + size < end {
+		VirtualFree(base as PVOID, size, MEM_RELEASE | MEM_PRESERVE_PLACEHOLDER);
+		VirtualFree((base + size) as PVOID, 0, MEM_RELEASE);
+	}
+
+	// Split a placeholder off for every view and replace it with the view
+	let mut address = base;
+	for (i, &(offset, len)) in views.iter().enumerate() {
+		let last = i + 1 == views.len();
+		if !last {
+			VirtualFree(address as PVOID, len, MEM_RELEASE | MEM_PRESERVE_PLACEHOLDER);
+		}
+		let view = (api.map_view_of_file3)(map, ptr::null_mut(), address as PVOID, offset, len, MEM_REPLACE_PLACEHOLDER | flags, PAGE_READWRITE, ptr::null_mut(), 0);
+		if view.is_null() {
+			let err = super::os_error("MapViewOfFile3", len);
+			// Unmap the views mapped so far and release the placeholders left
+			let mut mapped = base;
+			for &(_, mapped_len) in &views[..i] {
+				UnmapViewOfFile(mapped as PVOID);
+				mapped += mapped_len;
+			}
+			VirtualFree(address as PVOID, 0, MEM_RELEASE);
+			if !last {
+				VirtualFree((address + len) as PVOID, 0, MEM_RELEASE);
+			}
+			return Some(Err(err));
+		}
+		address += len;
+	}
+	Some(Ok(NonNull::new_unchecked(base as *mut u8)))
+}
+
+// The mapping cannot be grown in place, reallocating copies the elements
+#[inline]
+pub unsafe fn grow(_ptr: NonNull<u8>, _cap: usize, _handle: Handle, _new_cap: usize, _size_of: usize, _options: &Options) -> Option<(NonNull<u8>, usize)> {
+	None
 }
 
 #[inline]
-pub unsafe fn free(ptr: NonNull<u8>, cap: usize) {
+pub unsafe fn free(ptr: NonNull<u8>, cap: usize, map: Handle) {
 	let ptr = ptr.as_ptr();
 	UnmapViewOfFile(ptr as _);
 	UnmapViewOfFile(ptr.add(cap) as _);
+	if !map.is_null() {
+		CloseHandle(map);
+	}
+}
+
+// Named sections hold a header of `header` bytes followed by the ring buffer.
+// The header is mapped right before the mirrored ring buffer, returned pointers point at the header.
+// The section stays open so it can be duplicated into other processes, the caller closes it.
+// Sections are removed by the system once their last handle is closed.
+
+// Security descriptor in SDDL, or the default security which grants access to the creator.
+pub type Security<'a> = Option<&'a str>;
+pub const DEFAULT_SECURITY: Security<'static> = None;
+
+pub unsafe fn create_shared(name: &str, header: usize, cap: usize, size_of: usize, security: Security) -> io::Result<(NonNull<u8>, usize, HANDLE)> {
+	if cap == 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "zero capacity"));
+	}
+
+	// Round capacity to nearest multiple of the system's allocation granularity
+	let cap = super::round_capacity(cap, size_of);
+	let size = (header + cap) as u64;
+
+	let name = section_name(name)?;
+	let mut descriptor: PSECURITY_DESCRIPTOR = ptr::null_mut();
+	if let Some(sddl) = security {
+		let sddl = wide(sddl)?;
+		if ConvertStringSecurityDescriptorToSecurityDescriptorW(sddl.as_ptr(), SDDL_REVISION_1 as DWORD, &mut descriptor, ptr::null_mut()) == 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+	let mut attributes = SECURITY_ATTRIBUTES {
+		nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as DWORD,
+		lpSecurityDescriptor: descriptor,
+		bInheritHandle: FALSE,
+	};
+
+	SetLastError(0);
+	let map = CreateFileMappingW(INVALID_HANDLE_VALUE, &mut attributes, PAGE_READWRITE, (size >> 32) as DWORD, size as DWORD, name.as_ptr());
+	let err = io::Error::last_os_error();
+	if !descriptor.is_null() {
+		LocalFree(descriptor);
+	}
+	if map.is_null() {
+		return Err(err);
+	}
+	// Opening an existing section succeeds, creating the ring buffer must not
+	if err.raw_os_error() == Some(ERROR_ALREADY_EXISTS as i32) {
+		CloseHandle(map);
+		return Err(err);
+	}
+
+	match map_views(map, header, cap) {
+		Ok(ptr) => Ok((ptr, cap, map)),
+		Err(err) => {
+			CloseHandle(map);
+			Err(err.into())
+		},
+	}
+}
+
+pub unsafe fn open_shared(name: &str, header: usize) -> io::Result<(NonNull<u8>, usize, HANDLE)> {
+	let name = section_name(name)?;
+	let map = OpenFileMappingW(FILE_MAP_READ|FILE_MAP_WRITE, FALSE, name.as_ptr());
+	if map.is_null() {
+		return Err(io::Error::last_os_error());
+	}
+	match map_shared(map, header) {
+		Ok((ptr, cap)) => Ok((ptr, cap, map)),
+		Err(err) => {
+			CloseHandle(map);
+			Err(err)
+		},
+	}
+}
+
+// Maps a named section, the capacity is whatever follows the header
+pub unsafe fn map_shared(map: HANDLE, header: usize) -> io::Result<(NonNull<u8>, usize)> {
+	let size = section_size(map)?;
+	if size <= header || (size - header) & (granularity() - 1) != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid shared memory size"));
+	}
+	let cap = size - header;
+	Ok((map_views(map, header, cap)?, cap))
+}
+
+#[inline]
+pub unsafe fn unlink_shared(name: &str) -> io::Result<()> {
+	// The system removes the section along with its last handle
+	section_name(name).map(|_| ())
+}
+
+// Duplicates the section into another process, returns the handle valid in that process
+pub unsafe fn duplicate_handle(map: HANDLE, process: HANDLE) -> io::Result<HANDLE> {
+	let mut target = ptr::null_mut();
+	if DuplicateHandle(GetCurrentProcess(), map, process, &mut target, 0, FALSE, DUPLICATE_SAME_ACCESS) == 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(target)
+}
+
+// Section names may be placed in the global or local namespace, but must not contain further separators
+fn section_name(name: &str) -> io::Result<Vec<u16>> {
+	let base = name.strip_prefix("Global\\").or_else(|| name.strip_prefix("Local\\")).unwrap_or(name);
+	if base.is_empty() || base.contains(&['/', '\\'][..]) {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid shared memory name"));
+	}
+	wide(name)
+}
+
+fn wide(s: &str) -> io::Result<Vec<u16>> {
+	if s.contains('\0') {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "unexpected nul byte"));
+	}
+	Ok(s.encode_utf16().chain(Some(0)).collect())
 }
 
-#[cold]
-#[track_caller]
-fn error(name: &str) -> ! {
-	panic!("{}(): {}", name, unsafe { GetLastError() })
+// Files hold a header of `header` bytes followed by the ring buffer.
+// The header is mapped right before the mirrored ring buffer, returned pointers point at the header.
+pub unsafe fn map_file(file: &File, header: usize, cap: usize) -> io::Result<NonNull<u8>> {
+	let map = CreateFileMappingW(file.as_raw_handle() as HANDLE, ptr::null_mut(), PAGE_READWRITE, 0, 0, ptr::null());
+	if map.is_null() {
+		return Err(io::Error::last_os_error());
+	}
+	// The views keep the section alive
+	let result = map_views(map, header, cap);
+	CloseHandle(map);
+	Ok(result?)
+}
+
+// Maps the header followed by the mirrored ring buffer
+unsafe fn map_views(map: HANDLE, header: usize, cap: usize) -> Result<NonNull<u8>, Error> {
+	if let Some(result) = map_placeholders(map, &[(0, header), (header as u64, cap), (header as u64, cap)], granularity(), 0, None) {
+		return result;
+	}
+
+	let offset_high = (header as u64 >> 32) as u32;
+	let offset_low = (header as u64 & 0xffffffff) as u32;
+
+	retry(&Options::new(), cap, || {
+		let base = VirtualAlloc(ptr::null_mut(), header + cap + cap, MEM_RESERVE, PAGE_READWRITE);
+		if base.is_null() {
+			return Some(Err(super::os_error("VirtualAlloc", header + cap + cap)));
+		}
+		VirtualFree(base, 0, MEM_RELEASE);
+
+		let p0 = MapViewOfFileEx(map, FILE_MAP_READ|FILE_MAP_WRITE, 0, 0, header, base);
+		let p1 = MapViewOfFileEx(map, FILE_MAP_READ|FILE_MAP_WRITE, offset_high, offset_low, cap, (base as usize + header) as *mut _);
+		let p2 = MapViewOfFileEx(map, FILE_MAP_READ|FILE_MAP_WRITE, offset_high, offset_low, cap, (base as usize + header + cap) as *mut _);
+
+		if !p0.is_null() && !p1.is_null() && !p2.is_null() {
+			return Some(Ok(NonNull::new_unchecked(base as *mut u8)));
+		}
+
+		for &p in &[p0, p1, p2] {
+			if !p.is_null() {
+				UnmapViewOfFile(p);
+			}
+		}
+		None
+	})
+}
+
+pub unsafe fn flush(ptr: NonNull<u8>, header: usize, cap: usize) -> io::Result<()> {
+	// The second half of the ring buffer maps the same pages as the first half
+	let ptr = ptr.as_ptr();
+	if FlushViewOfFile(ptr as _, header) == 0 || FlushViewOfFile(ptr.add(header) as _, cap) == 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+#[inline]
+pub unsafe fn free_shared(ptr: NonNull<u8>, header: usize, cap: usize) {
+	let ptr = ptr.as_ptr();
+	UnmapViewOfFile(ptr as _);
+	UnmapViewOfFile(ptr.add(header) as _);
+	UnmapViewOfFile(ptr.add(header + cap) as _);
+}
+
+// Lets the system discard the pages backing the range, their contents become undefined
+#[inline]
+pub unsafe fn decommit(ptr: NonNull<u8>, len: usize) {
+	VirtualAlloc(ptr.as_ptr() as _, len, MEM_RESET, PAGE_NOACCESS);
+}
+
+// Locks the pages of both views into the working set, limited by the minimum working set size of the process
+#[inline]
+pub unsafe fn lock(ptr: NonNull<u8>, cap: usize) -> Result<(), Error> {
+	if VirtualLock(ptr.as_ptr() as _, cap + cap) == 0 {
+		return Err(super::os_error("VirtualLock", cap + cap));
+	}
+	Ok(())
+}
+
+#[inline]
+pub unsafe fn unlock(ptr: NonNull<u8>, cap: usize) {
+	VirtualUnlock(ptr.as_ptr() as _, cap + cap);
+}
+
+// Prefetching reads pages back in from the page file in large batches, touching them maps them into both views
+#[inline]
+pub unsafe fn prefault(ptr: NonNull<u8>, cap: usize) {
+	let mut range = WIN32_MEMORY_RANGE_ENTRY { VirtualAddress: ptr.as_ptr() as PVOID, NumberOfBytes: cap };
+	PrefetchVirtualMemory(GetCurrentProcess(), 1, &mut range, 0);
+	super::touch(ptr, cap + cap);
 }
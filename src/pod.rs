@@ -0,0 +1,42 @@
+/*!
+Typed views of byte ring buffers with bytemuck.
+*/
+
+use core::mem;
+
+use bytemuck::Pod;
+
+use super::RingBuffer;
+
+impl RingBuffer<u8> {
+	/// Reinterprets the contents of the ring buffer as a slice of `T`.
+	///
+	/// Trailing bytes which do not form a complete `T` are excluded.
+	/// Returns `None` if the start of the contents is not suitably aligned for `T`.
+	#[inline]
+	pub fn as_pod_slice<T: Pod>(&self) -> Option<&[T]> {
+		let data = self.as_slice();
+		let len = match mem::size_of::<T>() {
+			0 => 0,
+			size => data.len() / size * size,
+		};
+		bytemuck::try_cast_slice(&data[..len]).ok()
+	}
+
+	/// Removes a `T` from the tail and returns it, or `None` if the ring buffer contains fewer than `size_of::<T>()` bytes.
+	///
+	/// The contents do not need to be aligned for `T`.
+	#[inline]
+	pub fn pop_pod<T: Pod>(&mut self) -> Option<T> {
+		let size = mem::size_of::<T>();
+		let value = bytemuck::pod_read_unaligned(self.as_slice().get(..size)?);
+		self.remove_tail(size);
+		Some(value)
+	}
+
+	/// Appends the bytes of `value` to the front.
+	#[inline]
+	pub fn push_pod<T: Pod>(&mut self, value: &T) {
+		self.extend_from_slice(bytemuck::bytes_of(value));
+	}
+}
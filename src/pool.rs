@@ -0,0 +1,177 @@
+/*!
+Recycling mirrored mappings between ring buffers.
+*/
+
+use std::{fmt, mem};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::Mutex;
+
+use super::{platform, GrowthPolicy, OsMemory, RingBuffer, TryReserveError};
+
+/// Pool of mirrored mappings recycled between ring buffers.
+///
+/// Creating a ring buffer takes several system calls to set up its mirrored mapping.
+/// Servers which create a ring buffer per connection [recycle](Self::recycle) them when the connection closes
+/// and [get](Self::get) them back from the pool for new connections.
+///
+/// Mappings are matched by their capacity after rounding it up to the allocation granularity.
+/// Recycled ring buffers are cleared but their memory is not zeroed.
+pub struct RingBufferPool<T> {
+	state: Mutex<State>,
+	// Maximum number of idle mappings kept around.
+	max_idle: usize,
+	_marker: PhantomData<fn(T) -> T>,
+}
+
+struct State {
+	idle: Vec<Mapping>,
+	stats: PoolStats,
+}
+
+struct Mapping {
+	ptr: NonNull<u8>,
+	cap: usize,
+	handle: platform::Handle,
+}
+
+// The mappings are not referenced by anything else
+unsafe impl Send for Mapping {}
+
+/// Statistics of a [`RingBufferPool`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PoolStats {
+	/// Number of ring buffers handed out with a recycled mapping.
+	pub hits: u64,
+	/// Number of ring buffers handed out with a newly created mapping.
+	pub misses: u64,
+	/// Number of mappings returned to the pool.
+	pub recycled: u64,
+	/// Number of mappings freed because the pool was full.
+	pub discarded: u64,
+}
+
+impl PoolStats {
+	/// Returns the fraction of ring buffers handed out with a recycled mapping, 0 if none were handed out.
+	#[inline]
+	pub fn hit_rate(&self) -> f64 {
+		let total = self.hits + self.misses;
+		if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+	}
+}
+
+impl<T> RingBufferPool<T> {
+	/// Constructs a new, empty pool keeping at most `max_idle` mappings around.
+	#[inline]
+	pub fn new(max_idle: usize) -> RingBufferPool<T> {
+		RingBufferPool {
+			state: Mutex::new(State { idle: Vec::new(), stats: PoolStats::default() }),
+			max_idle,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Returns an empty ring buffer with the specified capacity, recycling a mapping of the same capacity if one is available.
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits or there is not enough contigious memory for 2x the requested capacity.
+	#[inline]
+	pub fn get(&self, capacity: usize) -> RingBuffer<T> {
+		match self.try_get(capacity) {
+			Ok(rb) => rb,
+			Err(err) => platform::alloc_failed(err),
+		}
+	}
+
+	/// Returns an empty ring buffer with the specified capacity, recycling a mapping of the same capacity if one is available.
+	///
+	/// Like [`get`](Self::get) but returns an error instead of panicking if the allocation fails.
+	pub fn try_get(&self, capacity: usize) -> Result<RingBuffer<T>, TryReserveError> {
+		if capacity == 0 {
+			return Ok(RingBuffer::new());
+		}
+		let cap = platform::try_round_capacity(capacity, mem::size_of::<T>(), platform::granularity()).ok_or(TryReserveError::CapacityOverflow)?;
+
+		let recycled = {
+			let mut state = self.state.lock().unwrap();
+			let recycled = state.idle.iter().rposition(|mapping| mapping.cap == cap).map(|i| state.idle.swap_remove(i));
+			match recycled {
+				Some(_) => state.stats.hits += 1,
+				None => state.stats.misses += 1,
+			}
+			recycled
+		};
+
+		match recycled {
+			Some(Mapping { ptr, cap, handle }) => Ok(RingBuffer {
+				ptr: ptr.cast(),
+				cap,
+				base: 0,
+				len: 0,
+				handle: Some(handle),
+				growth: GrowthPolicy::Doubling,
+				limit: usize::MAX,
+				memory: OsMemory::new(),
+			}),
+			None => RingBuffer::try_with_capacity(capacity),
+		}
+	}
+
+	/// Clears the ring buffer and returns its mapping to the pool.
+	///
+	/// The mapping is freed instead if the pool already holds `max_idle` mappings
+	/// or the ring buffer was [built](RingBuffer::builder) with allocation options.
+	pub fn recycle(&self, mut rb: RingBuffer<T>) {
+		rb.clear();
+		let handle = match rb.handle {
+			Some(handle) if rb.memory == OsMemory::new() => handle,
+			_ => return,
+		};
+		let mut state = self.state.lock().unwrap();
+		if state.idle.len() >= self.max_idle {
+			state.stats.discarded += 1;
+			return;
+		}
+		state.stats.recycled += 1;
+		let rb = mem::ManuallyDrop::new(rb);
+		state.idle.push(Mapping { ptr: rb.ptr.cast(), cap: rb.cap, handle });
+	}
+
+	/// Returns the number of idle mappings in the pool.
+	#[inline]
+	pub fn idle(&self) -> usize {
+		self.state.lock().unwrap().idle.len()
+	}
+
+	/// Returns the statistics of the pool.
+	#[inline]
+	pub fn stats(&self) -> PoolStats {
+		self.state.lock().unwrap().stats
+	}
+
+	/// Frees all idle mappings.
+	pub fn clear(&self) {
+		let idle = mem::take(&mut self.state.lock().unwrap().idle);
+		for mapping in idle {
+			unsafe { platform::free(mapping.ptr, mapping.cap, mapping.handle); }
+		}
+	}
+}
+
+impl<T> Drop for RingBufferPool<T> {
+	fn drop(&mut self) {
+		self.clear();
+	}
+}
+
+impl<T> fmt::Debug for RingBufferPool<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let state = self.state.lock().unwrap();
+		f.debug_struct("RingBufferPool")
+			.field("idle", &state.idle.len())
+			.field("max_idle", &self.max_idle)
+			.field("stats", &state.stats)
+			.finish()
+	}
+}
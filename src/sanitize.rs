@@ -0,0 +1,54 @@
+/*!
+Poisoning the unused capacity of ring buffers with AddressSanitizer.
+
+Whenever a ring buffer reserves room the memory outside of its elements and the reserved room is poisoned in both views of the mapping,
+elements claimed with `add_len` and `set_len` are unpoisoned. The whole mapping is unpoisoned before it is freed.
+*/
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+
+extern "C" {
+	fn __asan_poison_memory_region(addr: *const c_void, size: usize);
+	fn __asan_unpoison_memory_region(addr: *const c_void, size: usize);
+}
+
+// Poisons both views outside of the `len` bytes at `offset`, wrapping around at the end of the first view
+pub unsafe fn poison(ptr: NonNull<u8>, cap: usize, offset: usize, len: usize) {
+	let ptr = ptr.as_ptr();
+	__asan_unpoison_memory_region(ptr as *const c_void, cap + cap);
+	if len >= cap {
+		return;
+	}
+	let poison = |at: usize, len: usize| {
+		__asan_poison_memory_region(ptr.add(at) as *const c_void, len);
+		__asan_poison_memory_region(ptr.add(cap + at) as *const c_void, len);
+	};
+	// The bytes from the end around to the start
+	let at = (offset + len) % cap;
+	let len = cap - len;
+	if at + len <= cap {
+		poison(at, len);
+	}
+	else {
+		poison(at, cap - at);
+		poison(0, at + len - cap);
+	}
+}
+
+// Unpoisons the `len` bytes at `offset` in both views
+pub unsafe fn unpoison(ptr: NonNull<u8>, cap: usize, offset: usize, len: usize) {
+	let ptr = ptr.as_ptr();
+	let offset = offset % cap;
+	let unpoison = |at: usize, len: usize| {
+		__asan_unpoison_memory_region(ptr.add(at) as *const c_void, len);
+		__asan_unpoison_memory_region(ptr.add(cap + at) as *const c_void, len);
+	};
+	if offset + len <= cap {
+		unpoison(offset, len);
+	}
+	else {
+		unpoison(offset, cap - offset);
+		unpoison(0, offset + len - cap);
+	}
+}
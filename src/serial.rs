@@ -0,0 +1,115 @@
+/*!
+Frame extraction for serial line protocols.
+*/
+
+use super::{FrameError, RingBuffer};
+
+#[cfg(feature = "cobs")]
+impl RingBuffer<u8> {
+	/// Decodes the next COBS frame into `dst` and consumes it from the tail.
+	///
+	/// Frames are delimited by a zero byte, empty frames are skipped.
+	/// Returns `Ok(None)` without consuming anything if the ring buffer does not contain a complete frame.
+	/// Otherwise returns the length of the decoded frame written to the start of `dst`.
+	///
+	/// On error the raw bytes of the frame are consumed so the stream can resynchronize on the next frame.
+	pub fn pop_cobs_frame(&mut self, dst: &mut [u8]) -> Result<Option<usize>, FrameError> {
+		loop {
+			let end = match self.find_byte(0) {
+				Some(end) => end,
+				None => return Ok(None),
+			};
+			if end == 0 {
+				self.remove_tail(1);
+				continue;
+			}
+			let result = cobs_decode(&self.as_slice()[..end], dst);
+			self.remove_tail(end + 1);
+			return result.map(Some);
+		}
+	}
+}
+
+#[cfg(feature = "cobs")]
+fn cobs_decode(src: &[u8], dst: &mut [u8]) -> Result<usize, FrameError> {
+	let mut len = 0;
+	let mut i = 0;
+	while i < src.len() {
+		let code = src[i] as usize;
+		// Zero bytes are frame delimiters and cannot appear in the encoded data
+		if code == 0 || i + code > src.len() {
+			return Err(FrameError::Malformed);
+		}
+		let block = &src[i + 1..i + code];
+		i += code;
+		// Every block except the last and maximum length blocks is followed by an implicit zero
+		let zero = code < 0xff && i < src.len();
+		let n = block.len() + zero as usize;
+		if len + n > dst.len() {
+			return Err(FrameError::Overflow);
+		}
+		dst[len..len + block.len()].copy_from_slice(block);
+		if zero {
+			dst[len + block.len()] = 0;
+		}
+		len += n;
+	}
+	Ok(len)
+}
+
+#[cfg(feature = "slip")]
+const SLIP_END: u8 = 0xc0;
+#[cfg(feature = "slip")]
+const SLIP_ESC: u8 = 0xdb;
+#[cfg(feature = "slip")]
+const SLIP_ESC_END: u8 = 0xdc;
+#[cfg(feature = "slip")]
+const SLIP_ESC_ESC: u8 = 0xdd;
+
+#[cfg(feature = "slip")]
+impl RingBuffer<u8> {
+	/// Decodes the next SLIP frame into `dst` and consumes it from the tail.
+	///
+	/// Frames are delimited by an `END` (`0xC0`) byte, empty frames are skipped.
+	/// Returns `Ok(None)` without consuming anything if the ring buffer does not contain a complete frame.
+	/// Otherwise returns the length of the decoded frame written to the start of `dst`.
+	///
+	/// On error the raw bytes of the frame are consumed so the stream can resynchronize on the next frame.
+	pub fn pop_slip_frame(&mut self, dst: &mut [u8]) -> Result<Option<usize>, FrameError> {
+		loop {
+			let end = match self.find_byte(SLIP_END) {
+				Some(end) => end,
+				None => return Ok(None),
+			};
+			if end == 0 {
+				self.remove_tail(1);
+				continue;
+			}
+			let result = slip_decode(&self.as_slice()[..end], dst);
+			self.remove_tail(end + 1);
+			return result.map(Some);
+		}
+	}
+}
+
+#[cfg(feature = "slip")]
+fn slip_decode(src: &[u8], dst: &mut [u8]) -> Result<usize, FrameError> {
+	let mut len = 0;
+	let mut iter = src.iter();
+	while let Some(&byte) = iter.next() {
+		let byte = match byte {
+			SLIP_ESC => match iter.next() {
+				Some(&SLIP_ESC_END) => SLIP_END,
+				Some(&SLIP_ESC_ESC) => SLIP_ESC,
+				_ => return Err(FrameError::Malformed),
+			},
+			byte => byte,
+		};
+		match dst.get_mut(len) {
+			Some(dst) => *dst = byte,
+			None => return Err(FrameError::Overflow),
+		}
+		len += 1;
+	}
+	Ok(len)
+}
@@ -0,0 +1,646 @@
+/*!
+Ring buffers shared between processes.
+*/
+
+use std::{cmp, fmt, io, mem, slice};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{fence, AtomicU32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{platform, HeaderError, RingBuffer};
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle, RawHandle};
+
+// Shared memory object (Unix) or section (Windows), kept open to share it with other processes.
+#[cfg(unix)]
+type Object = OwnedFd;
+#[cfg(windows)]
+type Object = OwnedHandle;
+
+#[cfg(unix)]
+#[inline]
+unsafe fn own(fd: RawFd) -> Object {
+	OwnedFd::from_raw_fd(fd)
+}
+#[cfg(windows)]
+#[inline]
+unsafe fn own(handle: platform::Handle) -> Object {
+	OwnedHandle::from_raw_handle(handle as RawHandle)
+}
+
+// Size of `usize` and C's `size_t` in bytes
+const W: usize = mem::size_of::<usize>();
+
+/// Header at the start of the shared memory of a [`SharedRingBuffer`], for processes not written in Rust.
+///
+/// The layout is stable and only changes together with [`VERSION`](Self::VERSION).
+/// Fields are native endian and `usize` is C's `size_t`.
+/// The positions and the waits each start their own 128 byte cache line:
+///
+/// | Offset | Type     | Field     |
+/// |--------|----------|-----------|
+/// | 0      | `u32`    | `magic`   |
+/// | 4      | `u32`    | `version` |
+/// | 8      | `size_t` | `size_of` |
+/// | 8 + W  | `size_t` | `cap`     |
+/// | 128    | `size_t` | `head`    |
+/// | 256    | `size_t` | `tail`    |
+/// | 384    |          | private   |
+/// | 512    |          | private   |
+///
+/// W is the size of `size_t`. The header is 640 bytes in total.
+/// `include/vringbuf.h` declares it as `vringbuf_shared_header`.
+///
+/// The `cap` bytes of elements start at [`header_len`](SharedRingBuffer::header_len).
+/// On Unix the shared memory is opened with `shm_open("/<name>")`, which is the file `/dev/shm/<name>` on Linux.
+/// The positions are byte offsets modulo twice the capacity.
+/// The element at position `pos` is at `pos % cap` bytes from the start of the elements.
+/// The ring buffer is empty when `head == tail` and full when they are `cap` bytes apart.
+///
+/// Readers in other processes must load `magic` first and only read the other fields once it equals [`MAGIC`](Self::MAGIC).
+/// They may inspect the elements between the tail and the head at any time.
+/// They must not move the positions while a [`SharedRingBuffer`] is popping.
+#[repr(C)]
+pub struct SharedHeader {
+	/// Equals [`MAGIC`](Self::MAGIC) once the creator has initialized the header.
+	/// The other fields are valid once it is visible.
+	pub magic: AtomicU32,
+	/// Equals [`VERSION`](Self::VERSION).
+	pub version: AtomicU32,
+	/// Size of the elements in bytes.
+	pub size_of: AtomicUsize,
+	/// Capacity of the ring buffer in bytes.
+	pub cap: AtomicUsize,
+	_pad0: [u8; 128 - 8 - 2 * W],
+	/// Position of the next element to be written.
+	/// Only the producer modifies it.
+	pub head: AtomicUsize,
+	_pad1: [u8; 128 - W],
+	/// Position of the next element to be read.
+	/// Only the consumer modifies it.
+	pub tail: AtomicUsize,
+	_pad2: [u8; 128 - W],
+	// Wakes up the consumer waiting for the head to move.
+	head_wait: Wait,
+	_pad3: [u8; 128 - mem::size_of::<Wait>()],
+	// Wakes up the producer waiting for the tail to move.
+	tail_wait: Wait,
+	_pad4: [u8; 128 - mem::size_of::<Wait>()],
+}
+
+impl SharedHeader {
+	/// Identifies shared memory holding ring buffers.
+	/// It is the bytes `vrsh` read as a little endian `u32`.
+	pub const MAGIC: u32 = u32::from_le_bytes(*b"vrsh");
+	/// Version of the shared memory layout.
+	pub const VERSION: u32 = 4;
+}
+
+impl fmt::Debug for SharedHeader {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("SharedHeader")
+			.field("magic", &self.magic)
+			.field("version", &self.version)
+			.field("size_of", &self.size_of)
+			.field("cap", &self.cap)
+			.field("head", &self.head)
+			.field("tail", &self.tail)
+			.finish()
+	}
+}
+
+// Lets a process sleep until the other process moves a position.
+//
+// The waiter announces itself before checking the position a final time.
+// The other side checks for a waiter after moving the position and bumps the sequence number the waiter sleeps on.
+// The SeqCst fences on both sides guarantee that one of them sees the other.
+#[repr(C)]
+struct Wait {
+	seq: AtomicU32,
+	waiting: AtomicU32,
+}
+
+impl Wait {
+	// Blocks until `ready` returns true or the deadline passes
+	fn wait_until<F: FnMut() -> bool>(&self, deadline: Option<Instant>, mut ready: F) -> bool {
+		loop {
+			if ready() {
+				return true;
+			}
+			let seq = self.seq.load(Ordering::Acquire);
+			self.waiting.store(1, Ordering::Relaxed);
+			fence(Ordering::SeqCst);
+			if ready() {
+				self.waiting.store(0, Ordering::Relaxed);
+				return true;
+			}
+			let timeout = match deadline {
+				Some(deadline) => {
+					let now = Instant::now();
+					if now >= deadline {
+						self.waiting.store(0, Ordering::Relaxed);
+						return ready();
+					}
+					Some(deadline - now)
+				},
+				None => None,
+			};
+			platform::wait_on(&self.seq, seq, timeout);
+			self.waiting.store(0, Ordering::Relaxed);
+		}
+	}
+
+	// Wakes up the waiter after moving the position
+	#[inline]
+	fn wake(&self) {
+		fence(Ordering::SeqCst);
+		if self.waiting.load(Ordering::Relaxed) != 0 {
+			self.seq.fetch_add(1, Ordering::Release);
+			platform::wake_all(&self.seq);
+		}
+	}
+}
+
+// Size of the mapping reserved for the header
+#[inline]
+fn header_len() -> usize {
+	cmp::max(platform::granularity(), mem::size_of::<SharedHeader>())
+}
+
+impl<T: Copy> RingBuffer<T> {
+	/// Creates a ring buffer in named shared memory for single-producer single-consumer use across processes.
+	///
+	/// Other processes [open](Self::open_shared) the ring buffer by its name.
+	/// The name is removed again when the returned ring buffer is dropped.
+	/// On Windows it lives on until every process has dropped the ring buffer and may be prefixed with `Global\` or `Local\`.
+	/// The capacity is rounded up like [`with_capacity`](Self::with_capacity).
+	///
+	/// Only the creating user has access to the ring buffer.
+	/// See `create_shared_with_mode` (Unix) and `create_shared_with_sddl` (Windows) to grant others access.
+	///
+	/// Returns an error if a shared ring buffer with the same name already exists or the name contains slashes.
+	///
+	/// # Safety
+	///
+	/// * All processes must use the same element type `T`.
+	/// * `T` must be valid for any bit pattern.
+	/// * At most one process may push elements and at most one process may pop elements.
+	///
+	/// # Panics
+	///
+	/// Panics if the capacity exceeds system limits.
+	#[inline]
+	pub unsafe fn create_shared(name: &str, capacity: usize) -> io::Result<SharedRingBuffer<T>> {
+		RingBuffer::create_shared_with(name, capacity, platform::DEFAULT_SECURITY)
+	}
+
+	/// Creates a ring buffer in named shared memory with the given permission bits, such as `0o660`.
+	///
+	/// # Safety
+	///
+	/// See [`create_shared`](Self::create_shared).
+	#[cfg(unix)]
+	#[inline]
+	pub unsafe fn create_shared_with_mode(name: &str, capacity: usize, mode: u32) -> io::Result<SharedRingBuffer<T>> {
+		RingBuffer::create_shared_with(name, capacity, mode)
+	}
+
+	/// Creates a ring buffer in named shared memory with the given security descriptor in SDDL, such as `"D:(A;;GA;;;AU)"`.
+	///
+	/// # Safety
+	///
+	/// See [`create_shared`](Self::create_shared).
+	#[cfg(windows)]
+	#[inline]
+	pub unsafe fn create_shared_with_sddl(name: &str, capacity: usize, sddl: &str) -> io::Result<SharedRingBuffer<T>> {
+		RingBuffer::create_shared_with(name, capacity, Some(sddl))
+	}
+
+	unsafe fn create_shared_with(name: &str, capacity: usize, security: platform::Security) -> io::Result<SharedRingBuffer<T>> {
+		let () = Self::ALIGNED;
+		let header = header_len();
+		let (ptr, cap, object) = platform::create_shared(name, header, capacity, mem::size_of::<T>(), security)?;
+		let object = own(object);
+		// The shared memory object starts out zeroed
+		let ring = SharedRingBuffer { ptr, header, cap, object, name: Some(name.to_owned()), _marker: PhantomData };
+		let state = ring.header();
+		state.version.store(SharedHeader::VERSION, Ordering::Relaxed);
+		state.size_of.store(mem::size_of::<T>(), Ordering::Relaxed);
+		state.cap.store(cap, Ordering::Relaxed);
+		state.magic.store(SharedHeader::MAGIC, Ordering::Release);
+		Ok(ring)
+	}
+
+	/// Opens a ring buffer in named shared memory created by another process.
+	///
+	/// Returns an error if no shared ring buffer with the name exists or its header is invalid.
+	/// An invalid header is reported with a [`HeaderError`].
+	///
+	/// # Safety
+	///
+	/// See [`create_shared`](Self::create_shared).
+	pub unsafe fn open_shared(name: &str) -> io::Result<SharedRingBuffer<T>> {
+		let () = Self::ALIGNED;
+		let header = header_len();
+		let (ptr, cap, object) = platform::open_shared(name, header)?;
+		let object = own(object);
+		SharedRingBuffer { ptr, header, cap, object, name: None, _marker: PhantomData }.validate()
+	}
+
+	/// Opens a ring buffer from a section handle [duplicated](SharedRingBuffer::duplicate_handle) into this process by another process.
+	///
+	/// The ring buffer takes ownership of the handle.
+	///
+	/// Returns an error if the section does not hold a shared ring buffer.
+	///
+	/// # Safety
+	///
+	/// * `handle` must be an open section handle which is not used anywhere else.
+	/// * See [`create_shared`](Self::create_shared).
+	#[cfg(windows)]
+	pub unsafe fn from_shared_handle(handle: RawHandle) -> io::Result<SharedRingBuffer<T>> {
+		let object = own(handle as platform::Handle);
+		let () = Self::ALIGNED;
+		let header = header_len();
+		let (ptr, cap) = platform::map_shared(handle as platform::Handle, header)?;
+		SharedRingBuffer { ptr, header, cap, object, name: None, _marker: PhantomData }.validate()
+	}
+
+	/// Receives a ring buffer [sent](SharedRingBuffer::send) by another process over a Unix domain socket.
+	///
+	/// One process pushes elements and the other pops them.
+	///
+	/// Returns an error if the message is not a shared ring buffer of the same element type `T`.
+	///
+	/// # Safety
+	///
+	/// See [`create_shared`](Self::create_shared).
+	#[cfg(unix)]
+	pub unsafe fn recv_shared(socket: &UnixStream) -> io::Result<SharedRingBuffer<T>> {
+		let mut token = [0u8; 24];
+		let object = own(platform::recv_fd(socket.as_raw_fd(), &mut token)?);
+		let field = |i: usize| {
+			let mut bytes = [0u8; 8];
+			bytes.copy_from_slice(&token[i * 8..i * 8 + 8]);
+			u64::from_ne_bytes(bytes)
+		};
+		let () = Self::ALIGNED;
+		let header = header_len();
+		if field(0) != header as u64 {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "incompatible shared ring buffer"));
+		}
+		if field(2) != mem::size_of::<T>() as u64 {
+			return Err(HeaderError::ElementSize { expected: mem::size_of::<T>(), found: field(2) as usize }.into());
+		}
+		let (ptr, cap) = platform::map_shared(object.as_raw_fd(), header)?;
+		let ring = SharedRingBuffer { ptr, header, cap, object, name: None, _marker: PhantomData };
+		if field(1) != cap as u64 {
+			return Err(HeaderError::Capacity { expected: cap, found: field(1) as usize }.into());
+		}
+		ring.validate()
+	}
+}
+
+/// Ring buffer in named shared memory.
+///
+/// This struct is created by [`create_shared`](RingBuffer::create_shared) and [`open_shared`](RingBuffer::open_shared).
+/// It can also be received from another process with `recv_shared` (Unix) or `from_shared_handle` (Windows).
+/// The producing process pushes elements and the consuming process pops them.
+/// The blocking variants of pushing and popping sleep until the other process makes room or pushes elements.
+pub struct SharedRingBuffer<T> {
+	// Start of the header.
+	ptr: NonNull<u8>,
+	// Size of the header in bytes.
+	header: usize,
+	// Capacity of the ring buffer in bytes.
+	cap: usize,
+	object: Object,
+	// Name of the shared memory object the creator removes when dropped.
+	name: Option<String>,
+	_marker: PhantomData<T>,
+}
+
+impl<T: Copy> SharedRingBuffer<T> {
+	/// Returns the number of elements the ring buffer can hold.
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		self.cap / mem::size_of::<T>()
+	}
+
+	/// Returns the number of elements in the ring buffer.
+	///
+	/// The other process may push or pop elements concurrently.
+	#[inline]
+	pub fn len(&self) -> usize {
+		let tail = self.load(&self.header().tail, Ordering::Acquire);
+		let head = self.load(&self.header().head, Ordering::Acquire);
+		self.distance(tail, head)
+	}
+
+	/// Returns `true` if the ring buffer contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns `true` if the ring buffer has no room for more elements.
+	#[inline]
+	pub fn is_full(&self) -> bool {
+		self.len() == self.capacity()
+	}
+
+	/// Returns the number of elements which can be pushed without the ring buffer being full.
+	#[inline]
+	pub fn free_len(&self) -> usize {
+		self.capacity() - self.len()
+	}
+
+	/// Appends an element to the front.
+	///
+	/// Returns the element back if the ring buffer is full.
+	#[inline]
+	pub fn push(&mut self, value: T) -> Result<(), T> {
+		match self.push_slice(slice::from_ref(&value)) {
+			0 => Err(value),
+			_ => Ok(()),
+		}
+	}
+
+	/// Copies as many elements from `values` as there is room for and appends them to the front.
+	///
+	/// Returns the number of elements appended.
+	pub fn push_slice(&mut self, values: &[T]) -> usize {
+		let header = self.header();
+		let head = self.load(&header.head, Ordering::Relaxed);
+		let tail = self.load(&header.tail, Ordering::Acquire);
+		let n = cmp::min(self.capacity() - self.distance(tail, head), values.len());
+		unsafe { values.as_ptr().copy_to_nonoverlapping(self.at(head), n); }
+		if n > 0 {
+			header.head.store(self.advance(head, n), Ordering::Release);
+			header.head_wait.wake();
+		}
+		n
+	}
+
+	/// Appends an element to the front, sleeping while the ring buffer is full.
+	#[inline]
+	pub fn push_blocking(&mut self, value: T) {
+		self.header().tail_wait.wait_until(None, || !self.is_full());
+		let _ = self.push(value);
+	}
+
+	/// Appends an element to the front, sleeping while the ring buffer is full for at most `timeout`.
+	///
+	/// Returns the element back if the ring buffer is still full.
+	#[inline]
+	pub fn push_timeout(&mut self, value: T, timeout: Duration) -> Result<(), T> {
+		let deadline = Instant::now().checked_add(timeout);
+		self.header().tail_wait.wait_until(deadline, || !self.is_full());
+		self.push(value)
+	}
+
+	/// Removes the element at the tail and returns it, or [`None`] if the ring buffer is empty.
+	#[inline]
+	pub fn pop(&mut self) -> Option<T> {
+		let mut value = [mem::MaybeUninit::uninit()];
+		match self.pop_slice_uninit(&mut value) {
+			0 => None,
+			_ => Some(unsafe { value[0].assume_init() }),
+		}
+	}
+
+	/// Copies as many elements as are available into `dst` and removes them from the tail.
+	///
+	/// Returns the number of elements removed.
+	#[inline]
+	pub fn pop_slice(&mut self, dst: &mut [T]) -> usize {
+		unsafe { self.pop_slice_uninit(&mut *(dst as *mut [T] as *mut [mem::MaybeUninit<T>])) }
+	}
+
+	/// Copies as many elements as are available into `dst` and removes them from the tail.
+	///
+	/// Returns the number of elements `n` removed.
+	/// The first `n` elements of `dst` are initialized.
+	pub fn pop_slice_uninit(&mut self, dst: &mut [mem::MaybeUninit<T>]) -> usize {
+		let src = self.read();
+		let n = cmp::min(src.len(), dst.len());
+		unsafe { (src.as_ptr() as *const mem::MaybeUninit<T>).copy_to_nonoverlapping(dst.as_mut_ptr(), n); }
+		self.release(n);
+		n
+	}
+
+	/// Returns all the elements available to the consumer.
+	///
+	/// The elements can be inspected in place and are removed once they are [released](Self::release).
+	#[inline]
+	pub fn read(&mut self) -> &[T] {
+		let header = self.header();
+		let tail = self.load(&header.tail, Ordering::Relaxed);
+		let head = self.load(&header.head, Ordering::Acquire);
+		unsafe { slice::from_raw_parts(self.at(tail), self.distance(tail, head)) }
+	}
+
+	/// Removes the first `n` elements and hands their memory back to the producer.
+	///
+	/// # Panics
+	///
+	/// Panics if fewer than `n` elements are available.
+	#[inline]
+	pub fn release(&mut self, n: usize) {
+		assert!(n <= self.read().len(), "release exceeds the available elements");
+		if n > 0 {
+			let header = self.header();
+			let tail = self.load(&header.tail, Ordering::Relaxed);
+			header.tail.store(self.advance(tail, n), Ordering::Release);
+			header.tail_wait.wake();
+		}
+	}
+
+	/// Removes the element at the tail and returns it, sleeping while the ring buffer is empty.
+	#[inline]
+	pub fn pop_blocking(&mut self) -> T {
+		loop {
+			if let Some(value) = self.pop() {
+				return value;
+			}
+			self.header().head_wait.wait_until(None, || !self.is_empty());
+		}
+	}
+
+	/// Removes the element at the tail and returns it, sleeping while the ring buffer is empty for at most `timeout`.
+	///
+	/// Returns [`None`] if the ring buffer is still empty.
+	#[inline]
+	pub fn pop_timeout(&mut self, timeout: Duration) -> Option<T> {
+		let deadline = Instant::now().checked_add(timeout);
+		self.header().head_wait.wait_until(deadline, || !self.is_empty());
+		self.pop()
+	}
+
+	/// Sends the ring buffer to another process over a Unix domain socket.
+	///
+	/// The other process [receives](RingBuffer::recv_shared) it.
+	/// Unlike opening the ring buffer by name this keeps working after the name has been removed.
+	#[cfg(unix)]
+	pub fn send(&self, socket: &UnixStream) -> io::Result<()> {
+		let mut token = [0u8; 24];
+		let fields = [self.header as u64, self.cap as u64, mem::size_of::<T>() as u64];
+		for (chunk, field) in token.chunks_exact_mut(8).zip(&fields) {
+			chunk.copy_from_slice(&field.to_ne_bytes());
+		}
+		unsafe { platform::send_fd(socket.as_raw_fd(), self.object.as_raw_fd(), &token) }
+	}
+
+	/// Duplicates the section backing the ring buffer into another process.
+	///
+	/// Returns the handle valid in `process`, which [opens](RingBuffer::from_shared_handle) the ring buffer with it.
+	/// Unlike opening the ring buffer by name this does not depend on the namespace of the other process.
+	#[cfg(windows)]
+	pub fn duplicate_handle(&self, process: RawHandle) -> io::Result<RawHandle> {
+		let handle = unsafe { platform::duplicate_handle(self.object.as_raw_handle() as platform::Handle, process as platform::Handle)? };
+		Ok(handle as RawHandle)
+	}
+
+	/// Validates the positions in the header and restores them after the other process crashed.
+	///
+	/// A crash alone never leaves the ring buffer inconsistent, but a misbehaving process may corrupt the header.
+	/// Invalid positions are restored by discarding the elements.
+	/// Returns `true` if the ring buffer was restored.
+	///
+	/// The other process must not use the ring buffer while it is being recovered.
+	pub fn recover(&mut self) -> bool {
+		let header = self.header();
+		let head = header.head.load(Ordering::Acquire);
+		let tail = header.tail.load(Ordering::Acquire);
+		let (new_head, new_tail) = match (self.is_valid(head), self.is_valid(tail)) {
+			(true, true) if self.is_valid_distance(tail, head) => return false,
+			(true, _) => (head, head),
+			(false, true) => (tail, tail),
+			(false, false) => (0, 0),
+		};
+		header.head.store(new_head, Ordering::Release);
+		header.tail.store(new_tail, Ordering::Release);
+		true
+	}
+
+	fn validate(self) -> io::Result<SharedRingBuffer<T>> {
+		let header = self.header();
+		if header.magic.load(Ordering::Acquire) != SharedHeader::MAGIC {
+			return Err(HeaderError::Magic.into());
+		}
+		let version = header.version.load(Ordering::Relaxed);
+		if version != SharedHeader::VERSION {
+			return Err(HeaderError::Version { expected: SharedHeader::VERSION as u64, found: version as u64 }.into());
+		}
+		let size_of = header.size_of.load(Ordering::Relaxed);
+		if size_of != mem::size_of::<T>() {
+			return Err(HeaderError::ElementSize { expected: mem::size_of::<T>(), found: size_of }.into());
+		}
+		let cap = header.cap.load(Ordering::Relaxed);
+		if cap != self.cap {
+			return Err(HeaderError::Capacity { expected: self.cap, found: cap }.into());
+		}
+		let head = header.head.load(Ordering::Acquire);
+		let tail = header.tail.load(Ordering::Acquire);
+		if !self.is_valid(head) || !self.is_valid(tail) || !self.is_valid_distance(tail, head) {
+			return Err(HeaderError::Corrupted.into());
+		}
+		Ok(self)
+	}
+	// Positions must be within twice the capacity and element aligned
+	#[inline]
+	fn is_valid(&self, offset: usize) -> bool {
+		offset < 2 * self.cap && offset & (mem::align_of::<T>() - 1) == 0
+	}
+	#[inline]
+	fn is_valid_distance(&self, tail: usize, head: usize) -> bool {
+		let bytes = if head >= tail { head - tail } else { head + 2 * self.cap - tail };
+		bytes % mem::size_of::<T>() == 0 && bytes / mem::size_of::<T>() <= self.capacity()
+	}
+	#[inline]
+	fn header(&self) -> &SharedHeader {
+		unsafe { &*(self.ptr.as_ptr() as *const SharedHeader) }
+	}
+
+	/// Returns the offset of the elements from the start of the shared memory.
+	///
+	/// This is the size of the header rounded up to the allocation granularity.
+	#[inline]
+	pub fn header_len(&self) -> usize {
+		self.header
+	}
+	// Loads a position the other process may have corrupted, wrapped to an element within the mapping
+	#[inline]
+	fn load(&self, position: &AtomicUsize, order: Ordering) -> usize {
+		let mut offset = position.load(order);
+		if offset >= 2 * self.cap {
+			offset %= 2 * self.cap;
+		}
+		offset - offset % mem::size_of::<T>()
+	}
+	// Number of elements between the tail and head offsets, at most the capacity
+	#[inline]
+	fn distance(&self, tail: usize, head: usize) -> usize {
+		let bytes = if head >= tail { head - tail } else { head + 2 * self.cap - tail };
+		cmp::min(bytes / mem::size_of::<T>(), self.capacity())
+	}
+	// Advances an offset by `n` elements
+	#[inline]
+	fn advance(&self, offset: usize, n: usize) -> usize {
+		let offset = offset + n * mem::size_of::<T>();
+		if offset >= 2 * self.cap { offset - 2 * self.cap } else { offset }
+	}
+	// Pointer to the element at the offset
+	#[inline]
+	fn at(&self, offset: usize) -> *mut T {
+		let offset = if offset >= self.cap { offset - self.cap } else { offset };
+		unsafe { self.ptr.as_ptr().add(self.header + offset) as *mut T }
+	}
+}
+
+impl<T> Drop for SharedRingBuffer<T> {
+	fn drop(&mut self) {
+		unsafe {
+			platform::free_shared(self.ptr, self.header, self.cap);
+			if let Some(name) = &self.name {
+				let _ = platform::unlink_shared(name);
+			}
+		}
+	}
+}
+
+#[cfg(unix)]
+impl<T> AsRawFd for SharedRingBuffer<T> {
+	/// Returns the shared memory object backing the ring buffer.
+	#[inline]
+	fn as_raw_fd(&self) -> RawFd {
+		self.object.as_raw_fd()
+	}
+}
+
+#[cfg(windows)]
+impl<T> AsRawHandle for SharedRingBuffer<T> {
+	/// Returns the section backing the ring buffer.
+	#[inline]
+	fn as_raw_handle(&self) -> RawHandle {
+		self.object.as_raw_handle()
+	}
+}
+
+impl<T: Copy> fmt::Debug for SharedRingBuffer<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("SharedRingBuffer")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish()
+	}
+}
+
+// The elements are copied in and out
+unsafe impl<T: Copy + Send> Send for SharedRingBuffer<T> {}
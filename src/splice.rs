@@ -0,0 +1,108 @@
+/*!
+Moving bytes between the ring buffer and file descriptors.
+
+On Linux the memfd backing the ring buffer is spliced to and from pipes, the bytes never pass through user space.
+Everywhere else, and for file descriptors which are not pipes, the bytes are written and read as usual.
+*/
+
+use std::{cmp, io};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use super::RingBuffer;
+
+impl RingBuffer<u8> {
+	/// Moves up to `len` bytes from the tail of the ring buffer into `fd`.
+	///
+	/// On Linux the bytes are spliced into pipes without copying them through user space.
+	/// The pipe references the pages of the ring buffer until its reader consumes the bytes,
+	/// bytes pushed in the meantime may show up in the pipe instead when they reuse the same memory.
+	///
+	/// Returns the number of bytes removed from the ring buffer.
+	pub fn splice_to<F: AsRawFd + ?Sized>(&mut self, fd: &F, len: usize) -> io::Result<usize> {
+		let len = cmp::min(len, self.len);
+		if len == 0 {
+			return Ok(0);
+		}
+		let n = unsafe { self.splice_to_fd(fd.as_raw_fd(), len)? };
+		// The pipe may still reference the pages, don't wipe them
+		self.discard(n, false);
+		Ok(n)
+	}
+
+	/// Moves up to `additional` bytes from `fd` into the ring buffer.
+	///
+	/// Reserves room for at least `additional` bytes first.
+	/// On Linux the bytes are spliced from pipes without copying them through user space.
+	///
+	/// Returns the number of bytes appended, 0 indicates end of file when `additional` is not 0.
+	pub fn splice_from<F: AsRawFd + ?Sized>(&mut self, fd: &F, additional: usize) -> io::Result<usize> {
+		self.reserve(additional);
+		let additional = cmp::min(additional, self.reserved_len());
+		if additional == 0 {
+			return Ok(0);
+		}
+		let n = unsafe { self.splice_from_fd(fd.as_raw_fd(), additional)? };
+		unsafe { self.add_len(n); }
+		Ok(n)
+	}
+
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	unsafe fn splice_to_fd(&mut self, fd: RawFd, len: usize) -> io::Result<usize> {
+		// The memfd holds the bytes once, splice up to its end and let the caller come back for the rest
+		let mut offset = self.base as libc::loff_t;
+		let len = cmp::min(len, self.cap - self.base);
+		let n = libc::splice(self.raw_handle(), &mut offset, fd, std::ptr::null_mut(), len, libc::SPLICE_F_MOVE);
+		if n >= 0 {
+			return Ok(n as usize);
+		}
+		match io::Error::last_os_error() {
+			err if err.raw_os_error() == Some(libc::EINVAL) => write(fd, self.as_ptr(), len),
+			err => Err(err),
+		}
+	}
+
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	unsafe fn splice_from_fd(&mut self, fd: RawFd, additional: usize) -> io::Result<usize> {
+		// Splicing past the end of the memfd would grow it
+		let start = self.base + self.len;
+		let start = if start >= self.cap { start - self.cap } else { start };
+		let additional = cmp::min(additional, self.cap - start);
+		let mut offset = start as libc::loff_t;
+		let n = libc::splice(fd, std::ptr::null_mut(), self.raw_handle(), &mut offset, additional, libc::SPLICE_F_MOVE);
+		if n >= 0 {
+			return Ok(n as usize);
+		}
+		match io::Error::last_os_error() {
+			err if err.raw_os_error() == Some(libc::EINVAL) => read(fd, self.reserved_ptr(), additional),
+			err => Err(err),
+		}
+	}
+
+	#[cfg(not(any(target_os = "linux", target_os = "android")))]
+	#[inline]
+	unsafe fn splice_to_fd(&mut self, fd: RawFd, len: usize) -> io::Result<usize> {
+		write(fd, self.as_ptr(), len)
+	}
+
+	#[cfg(not(any(target_os = "linux", target_os = "android")))]
+	#[inline]
+	unsafe fn splice_from_fd(&mut self, fd: RawFd, additional: usize) -> io::Result<usize> {
+		read(fd, self.reserved_ptr(), additional)
+	}
+}
+
+unsafe fn write(fd: RawFd, buf: *const u8, len: usize) -> io::Result<usize> {
+	let n = libc::write(fd, buf as *const libc::c_void, len);
+	if n < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(n as usize)
+}
+
+unsafe fn read(fd: RawFd, buf: *mut u8, len: usize) -> io::Result<usize> {
+	let n = libc::read(fd, buf as *mut libc::c_void, len);
+	if n < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(n as usize)
+}
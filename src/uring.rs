@@ -0,0 +1,54 @@
+/*!
+Reading and writing with io_uring fixed buffers.
+*/
+
+use std::{cmp, io};
+
+use io_uring::{opcode, types, Submitter};
+
+use super::RingBuffer;
+
+impl RingBuffer<u8> {
+	/// Returns the mirrored mapping for registering it as a fixed buffer with io_uring.
+	///
+	/// The iovec covers both halves so every readable region and the spare capacity lie within it.
+	/// It becomes invalid when the ring buffer reallocates or is dropped.
+	#[inline]
+	pub fn fixed_buffer(&mut self) -> libc::iovec {
+		libc::iovec { iov_base: self.ptr.as_ptr() as *mut libc::c_void, iov_len: self.cap * 2 }
+	}
+
+	/// Registers the mirrored mapping as the only fixed buffer, at index 0.
+	///
+	/// # Safety
+	///
+	/// The ring buffer must not reallocate or be dropped until the buffer is unregistered or the io_uring is destroyed.
+	#[inline]
+	pub unsafe fn register_fixed(&mut self, submitter: &Submitter) -> io::Result<()> {
+		submitter.register_buffers(&[self.fixed_buffer()])
+	}
+
+	/// Prepares reading from `fd` into the spare capacity with the fixed buffer at `buf_index`.
+	///
+	/// Reserve capacity beforehand, the read targets the current [`reserved_len`](Self::reserved_len).
+	/// Once it completes, append the bytes read with [`add_len`](Self::add_len).
+	///
+	/// The ring buffer must not be modified while the read is in flight.
+	#[inline]
+	pub fn read_fixed(&mut self, fd: types::Fd, buf_index: u16) -> opcode::ReadFixed {
+		self.protect(self.reserved_len());
+		let len = cmp::min(self.reserved_len(), u32::MAX as usize) as u32;
+		opcode::ReadFixed::new(fd, self.reserved_ptr(), len, buf_index)
+	}
+
+	/// Prepares writing the readable region to `fd` with the fixed buffer at `buf_index`.
+	///
+	/// Once it completes, remove the bytes written with [`remove_tail`](Self::remove_tail).
+	///
+	/// The ring buffer must not be modified while the write is in flight.
+	#[inline]
+	pub fn write_fixed(&self, fd: types::Fd, buf_index: u16) -> opcode::WriteFixed {
+		let len = cmp::min(self.len, u32::MAX as usize) as u32;
+		opcode::WriteFixed::new(fd, self.as_ptr(), len, buf_index)
+	}
+}
@@ -0,0 +1,28 @@
+/*!
+Wiping the memory of ring buffers with zeroize.
+*/
+
+use core::mem::MaybeUninit;
+use core::slice;
+
+use zeroize::{DefaultIsZeroes, Zeroize, ZeroizeOnDrop};
+
+use super::{MirroredMemory, RingBuffer};
+
+// Overwrites the bytes with zeroes in a way the compiler does not optimize away
+#[inline]
+pub unsafe fn wipe(ptr: *mut u8, len: usize) {
+	slice::from_raw_parts_mut(ptr as *mut MaybeUninit<u8>, len).zeroize();
+}
+
+/// Zeroizes the elements, clears the ring buffer and wipes its whole mapping.
+impl<T: Zeroize, M: MirroredMemory> Zeroize for RingBuffer<T, M> {
+	fn zeroize(&mut self) {
+		self.as_mut_slice().iter_mut().zeroize();
+		self.clear();
+		self.reset_mapping();
+	}
+}
+
+/// Dropping wipes the mapping, elements of plain data leave nothing behind elsewhere.
+impl<T: DefaultIsZeroes, M: MirroredMemory> ZeroizeOnDrop for RingBuffer<T, M> {}
@@ -0,0 +1,93 @@
+#![cfg(all(feature = "std", unix, not(any(target_os = "macos", target_os = "ios", target_os = "fuchsia", heap_fallback))))]
+
+use std::os::unix::io::{AsRawFd, BorrowedFd, IntoRawFd};
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_from_backing_fd() {
+	let empty = RingBuffer::<u32>::new();
+	assert_eq!(empty.as_raw_fd(), -1);
+
+	let mut rbuf = RingBuffer::<u32>::with_capacity(100);
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	let fd = unsafe { BorrowedFd::borrow_raw(rbuf.as_raw_fd()) }.try_clone_to_owned().unwrap();
+	let mut other = unsafe { RingBuffer::<u32>::from_backing_fd(fd.into_raw_fd()).unwrap() };
+	assert_eq!(other.capacity(), rbuf.capacity());
+	assert!(other.is_empty());
+
+	// Both ring buffers see the same pages
+	unsafe { other.set_len(3); }
+	assert_eq!(other.as_slice(), [1, 2, 3]);
+	other[1] = 20;
+	assert_eq!(rbuf.as_slice(), [1, 20, 3]);
+
+	// Dropping wipes the shared pages with the zeroize feature
+	drop(rbuf);
+	#[cfg(not(feature = "zeroize"))]
+	assert_eq!(other.as_slice(), [1, 20, 3]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_sealed() {
+	use std::os::unix::io::FromRawFd;
+
+	let rbuf = RingBuffer::<u8>::with_capacity(1);
+	let file = std::fs::File::from(unsafe { BorrowedFd::borrow_raw(rbuf.as_raw_fd()) }.try_clone_to_owned().unwrap());
+	assert!(file.set_len(0).is_err());
+	assert!(file.set_len(rbuf.capacity() as u64 * 2).is_ok());
+
+	// Files which can be truncated are not mapped
+	let path = std::env::temp_dir().join(format!("vringbuf-sealed-{}", std::process::id()));
+	let file = std::fs::File::create(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+	file.set_len(rbuf.capacity() as u64).unwrap();
+	let err = unsafe { RingBuffer::<u8>::from_backing_fd(file.as_raw_fd()) }.unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+	// Neither are shared memory objects like those backing ring buffers without memfd_create
+	let name = format!("/vringbuf-sealed-{}\0", std::process::id());
+	let fd = unsafe { libc::shm_open(name.as_ptr() as *const libc::c_char, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_CLOEXEC, 0o600) };
+	assert!(fd >= 0);
+	unsafe { libc::shm_unlink(name.as_ptr() as *const libc::c_char); }
+	let file = unsafe { std::fs::File::from_raw_fd(fd) };
+	file.set_len(rbuf.capacity() as u64).unwrap();
+	let err = unsafe { RingBuffer::<u8>::from_backing_fd(file.as_raw_fd()) }.unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_decommit() {
+	use std::os::unix::fs::MetadataExt;
+
+	// Blocks allocated by the memfd
+	let blocks = |rbuf: &RingBuffer<u8>| std::fs::metadata(format!("/proc/self/fd/{}", rbuf.as_raw_fd())).unwrap().blocks();
+
+	let mut rbuf = RingBuffer::<u8>::with_capacity(1 << 20);
+	let capacity = rbuf.capacity();
+	rbuf.extend_from_slice(&vec![1; capacity]);
+	let full = blocks(&rbuf);
+	rbuf.remove_tail(capacity - 10);
+	rbuf.decommit();
+	assert!(blocks(&rbuf) < full / 2);
+	assert_eq!(rbuf.as_slice(), [1; 10]);
+
+	// Decommitted pages are faulted back in
+	rbuf.extend_from_slice(&vec![2; capacity - 10]);
+	assert_eq!(rbuf[..10], [1; 10]);
+	assert!(rbuf[10..].iter().all(|&byte| byte == 2));
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+#[test]
+fn test_grow_failed() {
+	// Mapping twice the capacity fails, the memfd keeps its size
+	let mut rbuf = RingBuffer::<u8>::with_capacity(1);
+	rbuf.push(1);
+	assert!(rbuf.try_reserve(1 << 46).is_err());
+	let fd = unsafe { BorrowedFd::borrow_raw(rbuf.as_raw_fd()) }.try_clone_to_owned().unwrap();
+	let other = unsafe { RingBuffer::<u8>::from_backing_fd(fd.into_raw_fd()).unwrap() };
+	assert_eq!(other.capacity(), rbuf.capacity());
+	assert_eq!(rbuf.as_slice(), [1]);
+}
@@ -0,0 +1,86 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::rc::Rc;
+use std::thread;
+use vringbuf::RingBuffer;
+use vringbuf::concurrent::{PopError, PushError};
+
+#[test]
+fn test_broadcast() {
+	let mut rbuf = RingBuffer::<u32>::with_capacity(16);
+	rbuf.push(0);
+	let cap = rbuf.capacity();
+	let (mut producer, mut first) = rbuf.broadcast();
+	let mut second = first.clone();
+
+	assert_eq!(producer.push_slice(&vec![1; cap]), cap - 1);
+	assert_eq!(producer.push(2), Err(PushError::Full(2)));
+
+	// The slowest consumer holds back the producer
+	first.release(cap);
+	assert_eq!(producer.push(2), Err(PushError::Full(2)));
+	assert_eq!(second.pop(), Ok(0));
+	assert_eq!(producer.push(2), Ok(()));
+	assert_eq!(producer.len(), cap);
+
+	// Dropping the slowest consumer releases its elements
+	let third = second.clone();
+	drop(second);
+	assert_eq!(producer.len(), cap);
+	drop(third);
+	assert_eq!(producer.len(), 1);
+	assert_eq!(first.read(), [2]);
+
+	drop(first);
+	assert!(producer.is_closed());
+	assert_eq!(producer.push(3), Err(PushError::Closed(3)));
+}
+
+#[test]
+fn test_drop_remaining() {
+	let value = Rc::new(());
+	let rbuf = RingBuffer::<Rc<()>>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.broadcast();
+	producer.push(value.clone()).unwrap();
+	producer.push(value.clone()).unwrap();
+	consumer.release(1);
+	assert!(!producer.is_empty());
+	assert_eq!(Rc::strong_count(&value), 2);
+	drop(producer);
+	assert_eq!(Rc::strong_count(&value), 2);
+	assert_eq!(consumer.pop().map(|_| ()), Ok(()));
+	assert_eq!(consumer.pop().map(|_| ()), Err(PopError::Closed));
+	drop(consumer);
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_threads() {
+	const N: u64 = 100_000;
+	let rbuf = RingBuffer::<u64>::with_capacity(64);
+	let (mut producer, consumer) = rbuf.broadcast();
+	let threads: Vec<_> = (0..4).map(|_| {
+		let mut consumer = consumer.clone();
+		thread::spawn(move || {
+			let mut sum = 0;
+			loop {
+				match consumer.pop() {
+					Ok(value) => sum += value,
+					Err(PopError::Empty) => thread::yield_now(),
+					Err(PopError::Closed) => break sum,
+				}
+			}
+		})
+	}).collect();
+	drop(consumer);
+
+	for i in 0..N {
+		while producer.push(i).is_err() {
+			thread::yield_now();
+		}
+	}
+	drop(producer);
+	for thread in threads {
+		assert_eq!(thread.join().unwrap(), N * (N - 1) / 2);
+	}
+}
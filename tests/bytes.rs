@@ -0,0 +1,144 @@
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_find_byte() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	assert_eq!(rbuf.find_byte(b'\n'), None);
+	assert_eq!(rbuf.rfind_byte(b'\n'), None);
+
+	// Move the base offset so the contents straddle the mirrored boundary
+	rbuf.reserve(1);
+	let cap = rbuf.capacity();
+	rbuf.resize(cap - 3, 0);
+	rbuf.remove_tail(cap - 3);
+
+	rbuf.extend_from_slice(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n");
+	assert_eq!(rbuf.find_byte(b'\n'), Some(15));
+	assert_eq!(rbuf.rfind_byte(b'\n'), Some(26));
+	assert_eq!(rbuf.position_of(b"\r\n\r\n"), Some(23));
+	assert_eq!(rbuf.position_of(b"\n\n"), None);
+	assert_eq!(rbuf.position_of(b""), Some(0));
+}
+
+#[test]
+fn test_take_until() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"PING\nPONG\nPI");
+
+	let ping = rbuf.take_until(b'\n').unwrap();
+	assert_eq!(ping.as_slice(), b"PING\n");
+	assert_eq!(rbuf.take_until_vec(b'\n'), Some(b"PONG\n".to_vec()));
+	assert!(rbuf.take_until(b'\n').is_none());
+	assert_eq!(rbuf.take_until_vec(b'\n'), None);
+	assert_eq!(rbuf.as_slice(), b"PI");
+}
+
+#[test]
+fn test_zeroed() {
+	let rbuf = RingBuffer::<u8>::with_capacity_zeroed(10000);
+	assert_eq!(rbuf.len(), 10000);
+	assert!(rbuf.iter().all(|&byte| byte == 0));
+	assert!(RingBuffer::<u8>::with_capacity_zeroed(0).is_empty());
+
+	// Overwrites the leftovers of removed bytes, wrapping around
+	let mut rbuf = RingBuffer::<u8>::with_capacity(1);
+	let cap = rbuf.capacity();
+	rbuf.resize(cap, 0xff);
+	rbuf.remove_tail(cap - 2);
+	rbuf.fill_zero(cap - 2);
+	assert_eq!(rbuf.len(), cap);
+	assert_eq!(&rbuf[..2], &[0xff, 0xff]);
+	assert!(rbuf[2..].iter().all(|&byte| byte == 0));
+	rbuf.fill_zero(1);
+	assert!(rbuf.capacity() > cap);
+	assert_eq!(rbuf[cap], 0);
+
+	rbuf.resize_zeroed(1);
+	assert_eq!(rbuf.as_slice(), &[0xff]);
+	rbuf.resize_zeroed(3);
+	assert_eq!(rbuf.as_slice(), &[0xff, 0, 0]);
+}
+
+#[test]
+fn test_lines() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"first\r\nsecond\n\nthird\rpartial");
+
+	let lines: Vec<Vec<u8>> = rbuf.lines().map(|line| line.to_vec()).collect();
+	assert_eq!(lines, [&b"first"[..], b"second", b""]);
+	assert_eq!(rbuf.as_slice(), b"third\rpartial");
+
+	rbuf.extend_from_slice(b"\n");
+	let mut lines = rbuf.lines();
+	assert_eq!(lines.next(), Some(&b"third\rpartial"[..]));
+	assert_eq!(lines.next(), None);
+	drop(lines);
+	assert!(rbuf.is_empty());
+}
+
+#[test]
+fn test_records() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"a\0bc\0\0de");
+
+	let mut records = rbuf.records(0);
+	assert_eq!(records.next(), Some(&b"a"[..]));
+	assert_eq!(records.remainder(), b"bc\0\0de");
+	assert_eq!(records.next(), Some(&b"bc"[..]));
+	assert_eq!(records.next(), Some(&b""[..]));
+	assert_eq!(records.next(), None);
+	drop(records);
+	assert_eq!(rbuf.as_slice(), b"de");
+
+	// Records which are not yielded are not consumed
+	rbuf.extend_from_slice(b"\0fg\0");
+	assert_eq!(rbuf.records(0).next(), Some(&b"de"[..]));
+	assert_eq!(rbuf.as_slice(), b"fg\0");
+}
+
+#[test]
+fn test_int_accessors() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.push_u16_le(0x1234);
+	rbuf.push_u16_be(0x1234);
+	rbuf.push_u32_be(0xdeadbeef);
+	rbuf.push_i64_le(-2);
+	assert_eq!(&rbuf[..4], &[0x34, 0x12, 0x12, 0x34]);
+
+	assert_eq!(rbuf.pop_u16_be(), Some(0x3412));
+	assert_eq!(rbuf.pop_u16_le(), Some(0x3412));
+	assert_eq!(rbuf.pop_u32_be(), Some(0xdeadbeef));
+	assert_eq!(rbuf.pop_i64_le(), Some(-2));
+	assert_eq!(rbuf.pop_u16_le(), None);
+
+	rbuf.push(1);
+	assert_eq!(rbuf.pop_u16_le(), None);
+	assert_eq!(rbuf.len(), 1);
+}
+
+#[test]
+fn test_varint() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	let values = [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX];
+	for &value in &values {
+		rbuf.push_varint(value);
+	}
+	for &value in &[0, -1, 1, i64::MIN, i64::MAX] {
+		rbuf.push_varint_signed(value);
+	}
+	assert_eq!(&rbuf[..6], &[0x00, 0x01, 0x7f, 0x80, 0x01, 0xac]);
+
+	for &value in &values {
+		assert_eq!(rbuf.pop_varint(), Ok(Some(value)));
+	}
+	for &value in &[0, -1, 1, i64::MIN, i64::MAX] {
+		assert_eq!(rbuf.pop_varint_signed(), Ok(Some(value)));
+	}
+	assert!(rbuf.is_empty());
+
+	rbuf.extend_from_slice(&[0x80, 0x80]);
+	assert_eq!(rbuf.pop_varint(), Ok(None));
+	rbuf.extend_from_slice(&[0x80; 8]);
+	assert!(rbuf.pop_varint().is_err());
+	assert_eq!(rbuf.len(), 10);
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "capi")]
+
+use vringbuf::capi::*;
+
+#[test]
+fn test_capi() {
+	unsafe {
+		let rbuf = vringbuf_create(1);
+		assert!(!rbuf.is_null());
+		let capacity = vringbuf_capacity(rbuf);
+		assert!(capacity >= 1);
+
+		// Wrap around the end of the mapping
+		let data = vec![7u8; capacity - 2];
+		assert_eq!(vringbuf_write(rbuf, data.as_ptr(), data.len()), data.len());
+		vringbuf_consume(rbuf, data.len());
+		assert_eq!(vringbuf_write(rbuf, b"hello".as_ptr(), 5), 5);
+		assert_eq!(std::slice::from_raw_parts(vringbuf_data(rbuf), vringbuf_len(rbuf)), b"hello");
+
+		let mut buf = [0u8; 3];
+		assert_eq!(vringbuf_read(rbuf, buf.as_mut_ptr(), buf.len()), 3);
+		assert_eq!(&buf, b"hel");
+		assert_eq!(vringbuf_read(rbuf, buf.as_mut_ptr(), buf.len()), 2);
+		assert_eq!(&buf[..2], b"lo");
+		assert_eq!(vringbuf_len(rbuf), 0);
+
+		// Grows to fit
+		let data = vec![1u8; capacity + 1];
+		assert_eq!(vringbuf_write(rbuf, data.as_ptr(), data.len()), data.len());
+		assert!(vringbuf_capacity(rbuf) > capacity);
+		vringbuf_destroy(rbuf);
+		vringbuf_destroy(std::ptr::null_mut());
+	}
+}
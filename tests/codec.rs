@@ -0,0 +1,37 @@
+#![cfg(feature = "bytes")]
+
+use bytes::{Buf, BufMut};
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_framed() {
+	let mut rbuf = RingBuffer::<u8>::new();
+
+	// Write a bunch of length prefixed frames and decode them again
+	for i in 0..1000u32 {
+		rbuf.put_u32(i);
+		rbuf.put_u8(b'#');
+		while rbuf.remaining() >= 5 {
+			assert_eq!(rbuf.get_u32(), i);
+			assert_eq!(rbuf.get_u8(), b'#');
+		}
+	}
+	assert_eq!(rbuf.len(), 0);
+}
+
+#[test]
+fn test_chunk_mut() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	assert!(rbuf.chunk_mut().len() >= 64);
+	rbuf.chunk_mut()[..3].copy_from_slice(b"abc");
+	unsafe { rbuf.advance_mut(3); }
+	assert_eq!(rbuf.chunk(), b"abc");
+}
+
+#[test]
+#[should_panic]
+fn test_advance_past_end() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.put_slice(b"abc");
+	rbuf.advance(4);
+}
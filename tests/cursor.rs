@@ -0,0 +1,35 @@
+#![cfg(feature = "nightly")]
+#![feature(core_io_borrowed_buf)]
+
+use std::io;
+use std::io::Read;
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_read_buf_from() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	let mut reader = io::repeat(0xab).take(10000);
+	let mut total = 0;
+	loop {
+		let n = rbuf.read_buf_from(&mut reader, 1000).unwrap();
+		if n == 0 {
+			break;
+		}
+		total += n;
+		rbuf.remove_tail(n / 2);
+	}
+	assert_eq!(total, 10000);
+	assert!(rbuf.iter().all(|&byte| byte == 0xab));
+}
+
+#[test]
+fn test_fill_reserved() {
+	let mut rbuf = RingBuffer::<u8>::with_capacity(16);
+	rbuf.push(1);
+	let result = rbuf.fill_reserved(|mut cursor| {
+		cursor.append(&[2, 3]);
+		Err(io::ErrorKind::Interrupted.into())
+	});
+	assert!(result.is_err());
+	assert_eq!(rbuf.as_slice(), &[1, 2, 3]);
+}
@@ -0,0 +1,130 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::{fs, io, process};
+use std::path::PathBuf;
+use vringbuf::{HeaderError, RingBuffer};
+
+// Keep the files unique per test run
+fn path(test: &str) -> PathBuf {
+	std::env::temp_dir().join(format!("vringbuf-{}-{}", test, process::id()))
+}
+
+fn header_error(err: &io::Error) -> Option<HeaderError> {
+	assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	err.get_ref()?.downcast_ref::<HeaderError>().copied()
+}
+
+#[test]
+fn test_reopen() {
+	let path = path("reopen");
+	let _ = fs::remove_file(&path);
+
+	let mut ring = unsafe { RingBuffer::<u32>::open_file(&path, 1000).unwrap() };
+	assert!(ring.capacity() >= 1000);
+	assert!(ring.is_empty());
+	assert_eq!(ring.extend_from_slice(&[1, 2, 3, 4]), 4);
+	assert_eq!(ring.pop(), Some(1));
+	ring.flush().unwrap();
+	let capacity = ring.capacity();
+	drop(ring);
+
+	// The capacity of an existing ring buffer is kept
+	let mut ring = unsafe { RingBuffer::<u32>::open_file(&path, 1).unwrap() };
+	assert_eq!(ring.capacity(), capacity);
+	assert_eq!(ring.as_slice(), [2, 3, 4]);
+	ring.clear();
+	drop(ring);
+
+	let ring = unsafe { RingBuffer::<u32>::open_file(&path, 1).unwrap() };
+	assert!(ring.is_empty());
+	drop(ring);
+
+	fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wrap_around() {
+	let path = path("wrap-around");
+	let _ = fs::remove_file(&path);
+
+	let mut ring = unsafe { RingBuffer::<u64>::open_file(&path, 100).unwrap() };
+	let capacity = ring.capacity();
+	let values: Vec<u64> = (0..capacity as u64).collect();
+	assert_eq!(ring.extend_from_slice(&values), capacity);
+	assert!(ring.is_full());
+	assert_eq!(ring.push(0), Err(0));
+	ring.remove_tail(capacity - 2);
+	assert_eq!(ring.extend_from_slice(&values[..3]), 3);
+	drop(ring);
+
+	let mut ring = unsafe { RingBuffer::<u64>::open_file(&path, 100).unwrap() };
+	assert_eq!(ring.as_slice(), [capacity as u64 - 2, capacity as u64 - 1, 0, 1, 2]);
+	ring[0] = 42;
+	assert_eq!(ring.pop(), Some(42));
+	drop(ring);
+
+	fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_file() {
+	let path = path("invalid-file");
+	fs::write(&path, b"not a ring buffer").unwrap();
+	let err = unsafe { RingBuffer::<u8>::open_file(&path, 100).unwrap_err() };
+	assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	fs::remove_file(&path).unwrap();
+
+	// The element type must match
+	drop(unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap() });
+	let err = unsafe { RingBuffer::<u64>::open_file(&path, 100).unwrap_err() };
+	assert_eq!(header_error(&err), Some(HeaderError::ElementSize { expected: 8, found: 4 }));
+
+	// Garbage of the right size is not mistaken for a ring buffer
+	let len = fs::metadata(&path).unwrap().len();
+	fs::write(&path, vec![0x55; len as usize]).unwrap();
+	let err = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap_err() };
+	assert_eq!(header_error(&err), Some(HeaderError::Magic));
+	fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_recover() {
+	let path = path("recover");
+	let _ = fs::remove_file(&path);
+
+	let mut ring = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap() };
+	assert!(!ring.recover());
+	ring.extend_from_slice(&[1, 2, 3]);
+	ring.flush().unwrap();
+	ring.extend_from_slice(&[4, 5]);
+	ring.pop();
+	// Crash without closing the file
+	std::mem::forget(ring);
+
+	// The state survives the crash until it is recovered
+	let mut ring = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap() };
+	assert_eq!(ring.as_slice(), [2, 3, 4, 5]);
+	assert!(ring.recover());
+	assert_eq!(ring.as_slice(), [1, 2, 3]);
+	assert!(!ring.recover());
+	drop(ring);
+
+	// Corrupt the head position, the last flushed state is restored
+	let mut ring = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap() };
+	ring.push(4).unwrap();
+	drop(ring);
+	let mut bytes = fs::read(&path).unwrap();
+	bytes[40..48].copy_from_slice(&u64::MAX.to_ne_bytes());
+	fs::write(&path, &bytes).unwrap();
+	let ring = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap() };
+	assert_eq!(ring.as_slice(), [1, 2, 3, 4]);
+	drop(ring);
+
+	// Corrupt the version
+	bytes[8..16].copy_from_slice(&u64::MAX.to_ne_bytes());
+	fs::write(&path, &bytes).unwrap();
+	let err = unsafe { RingBuffer::<u32>::open_file(&path, 100).unwrap_err() };
+	assert!(matches!(header_error(&err), Some(HeaderError::Version { found: u64::MAX, .. })));
+
+	fs::remove_file(&path).unwrap();
+}
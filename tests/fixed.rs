@@ -0,0 +1,122 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use proptest::prelude::*;
+use vringbuf::{Ring, RingBuffer, StaticRingBuffer};
+
+#[test]
+fn test_wrap_around() {
+	let mut rbuf = StaticRingBuffer::<u32, 4>::new();
+	assert_eq!(rbuf.capacity(), 4);
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	assert_eq!(rbuf.pop(), Some(1));
+	assert_eq!(rbuf.pop(), Some(2));
+	rbuf.extend_from_slice(&[4, 5, 6]);
+	assert!(rbuf.is_full());
+	assert_eq!(rbuf.as_slices(), (&[3, 4][..], &[5, 6][..]));
+	assert_eq!(rbuf.iter().rev().copied().collect::<Vec<_>>(), [6, 5, 4, 3]);
+	assert!(rbuf.try_push(7).is_err());
+	assert!(rbuf.try_extend_from_slice(&[7]).is_err());
+	assert_eq!(rbuf.make_contiguous(), &[3, 4, 5, 6]);
+	assert_eq!(rbuf.as_slices(), (&[3, 4, 5, 6][..], &[][..]));
+	assert_eq!(format!("{:?}", rbuf.clone()), "[3, 4, 5, 6]");
+
+	let mut empty = StaticRingBuffer::<u32, 0>::new();
+	assert!(empty.try_push(1).is_err());
+	assert_eq!(empty.pop(), None);
+}
+
+#[test]
+fn test_drops() {
+	struct Counted<'a>(&'a Cell<usize>);
+	impl<'a> Drop for Counted<'a> {
+		fn drop(&mut self) {
+			self.0.set(self.0.get() + 1);
+		}
+	}
+
+	let drops = Cell::new(0);
+	let mut rbuf = StaticRingBuffer::<Counted, 3>::new();
+	for _ in 0..3 {
+		rbuf.push(Counted(&drops));
+	}
+	rbuf.pop();
+	rbuf.push(Counted(&drops));
+	assert_eq!(drops.get(), 1);
+	rbuf.truncate(1);
+	assert_eq!(drops.get(), 3);
+	rbuf.push(Counted(&drops));
+	rbuf.remove_tail(1);
+	assert_eq!(drops.get(), 4);
+	drop(rbuf);
+	assert_eq!(drops.get(), 5);
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+	Push(u8),
+	Pop,
+	RemoveTail(usize),
+	Truncate(usize),
+	Clear,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+	prop_oneof![
+		4 => any::<u8>().prop_map(Op::Push),
+		2 => Just(Op::Pop),
+		1 => (0..6usize).prop_map(Op::RemoveTail),
+		1 => (0..6usize).prop_map(Op::Truncate),
+		1 => Just(Op::Clear),
+	]
+}
+
+// Applies the same operations through the shared interface
+fn apply<R: Ring<u8>>(ring: &mut R, op: &Op) -> Option<u8> {
+	match *op {
+		Op::Push(value) => {
+			if ring.len() == 5 {
+				ring.pop();
+			}
+			ring.try_push(value).unwrap();
+			None
+		},
+		Op::Pop => ring.pop(),
+		Op::RemoveTail(n) => { ring.remove_tail(n); None },
+		Op::Truncate(len) => { ring.truncate(len); None },
+		Op::Clear => { ring.clear(); None },
+	}
+}
+
+fn contents<R: Ring<u8>>(ring: &R) -> Vec<u8> {
+	let (first, second) = ring.as_slices();
+	[first, second].concat()
+}
+
+proptest! {
+	#[test]
+	fn test_shared_interface(ops in prop::collection::vec(op(), 0..64)) {
+		let mut fixed = StaticRingBuffer::<u8, 5>::new();
+		let mut rbuf = RingBuffer::<u8>::new();
+		let mut model = VecDeque::new();
+		for op in &ops {
+			let expected = match *op {
+				Op::Push(value) => {
+					if model.len() == 5 {
+						model.pop_front();
+					}
+					model.push_back(value);
+					None
+				},
+				Op::Pop => model.pop_front(),
+				Op::RemoveTail(n) => { model.drain(..n.min(model.len())); None },
+				Op::Truncate(len) => { model.truncate(len); None },
+				Op::Clear => { model.clear(); None },
+			};
+			prop_assert_eq!(apply(&mut fixed, op), expected);
+			prop_assert_eq!(apply(&mut rbuf, op), expected);
+			let model: Vec<u8> = model.iter().copied().collect();
+			prop_assert_eq!(contents(&fixed), model.clone());
+			prop_assert_eq!(contents(&rbuf), model);
+		}
+	}
+}
@@ -0,0 +1,57 @@
+use vringbuf::{FrameError, LengthPrefix, RingBuffer};
+
+const PREFIXES: [LengthPrefix; 6] = [
+	LengthPrefix::U8,
+	LengthPrefix::U16Le,
+	LengthPrefix::U16Be,
+	LengthPrefix::U32Le,
+	LengthPrefix::U32Be,
+	LengthPrefix::Varint,
+];
+
+#[test]
+fn test_roundtrip() {
+	let data: Vec<u8> = (0..=255).collect();
+	for &prefix in &PREFIXES {
+		let mut rbuf = RingBuffer::<u8>::new();
+		for len in 0..=255 {
+			rbuf.write_frame(prefix, &data[..len]);
+		}
+		for len in 0..=255 {
+			assert_eq!(rbuf.read_frame(prefix), Ok(Some(&data[..len])));
+			assert_eq!(rbuf.take_frame(prefix), Ok(Some(&data[..len])));
+		}
+		assert_eq!(rbuf.take_frame(prefix), Ok(None));
+		assert!(rbuf.is_empty());
+	}
+}
+
+#[test]
+fn test_partial() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(&[0x00, 0x03, b'a', b'b']);
+	assert_eq!(rbuf.read_frame(LengthPrefix::U16Be), Ok(None));
+	assert_eq!(rbuf.take_frame(LengthPrefix::U16Be), Ok(None));
+	rbuf.push(b'c');
+	assert_eq!(rbuf.take_frame(LengthPrefix::U16Be), Ok(Some(&b"abc"[..])));
+	rbuf.push(0x00);
+	assert_eq!(rbuf.take_frame(LengthPrefix::U16Be), Ok(None));
+}
+
+#[test]
+fn test_varint() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.write_frame(LengthPrefix::Varint, &[0; 300]);
+	assert_eq!(&rbuf[..2], &[0xac, 0x02]);
+	assert_eq!(rbuf.take_frame(LengthPrefix::Varint).unwrap().map(<[u8]>::len), Some(300));
+
+	rbuf.extend_from_slice(&[0xff; 10]);
+	assert_eq!(rbuf.read_frame(LengthPrefix::Varint), Err(FrameError::Malformed));
+}
+
+#[test]
+#[should_panic]
+fn test_too_long() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.write_frame(LengthPrefix::U8, &[0; 256]);
+}
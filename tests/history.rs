@@ -0,0 +1,70 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::thread;
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_history() {
+	let mut rbuf = RingBuffer::<u32>::with_capacity(16);
+	rbuf.extend_from_slice(&[1, 2]);
+	let (mut writer, reader) = rbuf.into_history();
+	let cap = writer.capacity();
+
+	let mut dst = [0; 4];
+	assert_eq!(reader.read_latest(&mut dst), 2);
+	assert_eq!(dst[..2], [1, 2]);
+
+	writer.push(3);
+	assert_eq!(reader.latest(), Some(3));
+	assert_eq!(reader.read_latest(&mut dst), 3);
+	assert_eq!(dst[..3], [1, 2, 3]);
+
+	// Overwrite the oldest elements
+	let values: Vec<u32> = (0..cap as u32 * 3 + 1).collect();
+	writer.push_slice(&values);
+	assert_eq!(reader.len(), cap);
+	let mut all = vec![0; cap + 1];
+	assert_eq!(reader.read_latest(&mut all), cap);
+	assert_eq!(all[..cap], values[values.len() - cap..]);
+}
+
+#[test]
+fn test_empty() {
+	let rbuf = RingBuffer::<u64>::with_capacity(16);
+	let (writer, reader) = rbuf.into_history();
+	assert!(writer.is_empty());
+	assert_eq!(reader.latest(), None);
+	assert_eq!(writer.reader().read_latest(&mut [0; 4]), 0);
+}
+
+#[test]
+fn test_threads() {
+	// Every element holds the same value twice, a torn read would mix up two elements
+	const N: u64 = 200_000;
+	let rbuf = RingBuffer::<[u64; 3]>::with_capacity(100);
+	let (mut writer, reader) = rbuf.into_history();
+
+	let readers: Vec<_> = (0..2).map(|_| {
+		let reader = reader.clone();
+		thread::spawn(move || {
+			let mut dst = [[0; 3]; 8];
+			loop {
+				let n = reader.read_latest(&mut dst);
+				for window in dst[..n].windows(2) {
+					assert_eq!(window[0][0], window[0][2]);
+					assert_eq!(window[0][0] + 1, window[1][0]);
+				}
+				if n > 0 && dst[n - 1][0] == N - 1 {
+					break;
+				}
+			}
+		})
+	}).collect();
+
+	for i in 0..N {
+		writer.push([i, !i, i]);
+	}
+	for thread in readers {
+		thread.join().unwrap();
+	}
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "std")]
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vringbuf::{LazyRing, RingBuffer};
+
+static INITS: AtomicUsize = AtomicUsize::new(0);
+static RING: LazyRing<u32> = LazyRing::new(|| {
+	INITS.fetch_add(1, Ordering::SeqCst);
+	RingBuffer::with_capacity(16)
+});
+
+#[test]
+fn test_lazy_ring() {
+	// Not constructed until first locked
+	assert!(!RING.is_initialized());
+	assert!(RING.try_lock().is_none());
+	assert_eq!(INITS.load(Ordering::SeqCst), 0);
+
+	let threads: Vec<_> = (0..4).map(|i| std::thread::spawn(move || RING.lock().push(i))).collect();
+	for thread in threads {
+		thread.join().unwrap();
+	}
+	assert_eq!(INITS.load(Ordering::SeqCst), 1);
+	assert_eq!(RING.lock().len(), 4);
+
+	// Held by the current thread
+	{
+		let _guard = RING.lock();
+		assert!(RING.try_lock().is_none());
+	}
+
+	// Panicking while holding the lock does not poison it
+	let _ = panic::catch_unwind(|| {
+		let mut ring = RING.lock();
+		ring.push(5);
+		panic!();
+	});
+	assert_eq!(RING.try_lock().unwrap().len(), 5);
+}
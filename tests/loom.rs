@@ -0,0 +1,195 @@
+//! Model checks the concurrent ring buffers.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+use vringbuf::RingBuffer;
+use vringbuf::concurrent::{PopError, WaitStrategy};
+
+// Large elements keep the capacity small enough to wrap around in a few operations.
+type Block = [u64; 256];
+
+fn block(i: u64) -> Block {
+	[i; 256]
+}
+
+// Bounds the preemptions to keep the models tractable, unless overridden with `LOOM_MAX_PREEMPTIONS`.
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+	let mut builder = loom::model::Builder::new();
+	if builder.preemption_bound.is_none() {
+		builder.preemption_bound = Some(3);
+	}
+	builder.check(f);
+}
+
+#[test]
+fn push_pop() {
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<u32>::with_capacity(2).split();
+		let thread = thread::spawn(move || {
+			producer.push(1).unwrap();
+			producer.push(2).unwrap();
+		});
+		let mut next = 1;
+		while next <= 2 {
+			match consumer.pop() {
+				Ok(value) => {
+					assert_eq!(value, next);
+					next += 1;
+				},
+				Err(PopError::Empty) => thread::yield_now(),
+				Err(PopError::Closed) => unreachable!(),
+			}
+		}
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn wrap_around() {
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<Block>::with_capacity(1).split();
+		let capacity = producer.capacity() as u64;
+		let thread = thread::spawn(move || {
+			for i in 0..capacity + 1 {
+				producer.push_blocking(block(i)).unwrap();
+			}
+		});
+		for i in 0..capacity + 1 {
+			assert_eq!(consumer.pop_blocking().map(|block| block[0]), Some(i));
+		}
+		assert_eq!(consumer.pop_blocking(), None);
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn grow() {
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<u32>::with_capacity(1).split();
+		let thread = thread::spawn(move || {
+			producer.push_blocking(1).unwrap();
+			let capacity = producer.capacity();
+			producer.grow(capacity + 1);
+			producer.push_blocking(2).unwrap();
+		});
+		assert_eq!(consumer.pop_blocking(), Some(1));
+		assert_eq!(consumer.pop_blocking(), Some(2));
+		assert_eq!(consumer.pop_blocking(), None);
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn spin() {
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<u32>::with_capacity(1).split();
+		consumer.set_wait_strategy(WaitStrategy::Spin);
+		let thread = thread::spawn(move || {
+			producer.push_blocking(1).unwrap();
+		});
+		assert_eq!(consumer.pop_blocking(), Some(1));
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn drop_remaining() {
+	// Loom reports an error if any of the `Arc`s leak
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<Arc<u32>>::with_capacity(2).split();
+		let thread = thread::spawn(move || {
+			let _ = producer.push(Arc::new(1));
+			let _ = producer.push(Arc::new(2));
+		});
+		let _ = consumer.pop();
+		drop(consumer);
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn disconnect() {
+	model(|| {
+		let (mut producer, mut consumer) = RingBuffer::<u32>::with_capacity(1).split();
+		let thread = thread::spawn(move || {
+			producer.push(1).unwrap();
+		});
+		assert_eq!(consumer.pop_blocking(), Some(1));
+		assert_eq!(consumer.pop_blocking(), None);
+		assert!(consumer.is_closed());
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn disconnect_producer() {
+	model(|| {
+		let (mut producer, consumer) = RingBuffer::<Block>::with_capacity(1).split();
+		let capacity = producer.capacity() as u64;
+		let thread = thread::spawn(move || {
+			drop(consumer);
+		});
+		let mut result = Ok(());
+		for i in 0..capacity + 1 {
+			result = producer.push_blocking(block(i)).map_err(|_| ());
+			if result.is_err() {
+				break;
+			}
+		}
+		assert_eq!(result, Err(()));
+		thread.join().unwrap();
+	});
+}
+
+#[test]
+fn mpmc() {
+	use vringbuf::concurrent::MpmcRingBuffer;
+	model(|| {
+		let rbuf = Arc::new(MpmcRingBuffer::<u32>::with_capacity(1));
+		let threads: Vec<_> = (1..=2).map(|i| {
+			let rbuf = rbuf.clone();
+			thread::spawn(move || rbuf.try_push(i).unwrap())
+		}).collect();
+		let mut sum = 0;
+		for _ in 0..2 {
+			loop {
+				match rbuf.try_pop() {
+					Some(value) => break sum += value,
+					None => thread::yield_now(),
+				}
+			}
+		}
+		for thread in threads {
+			thread.join().unwrap();
+		}
+		assert_eq!(sum, 3);
+	});
+}
+
+#[test]
+fn mpsc() {
+	model(|| {
+		let (producer, mut consumer) = RingBuffer::<u32>::with_capacity(1).split_mpsc();
+		let threads: Vec<_> = (1..=2).map(|i| {
+			let mut producer = producer.clone();
+			thread::spawn(move || producer.push(i).unwrap())
+		}).collect();
+		drop(producer);
+		let mut sum = 0;
+		loop {
+			match consumer.pop() {
+				Ok(value) => sum += value,
+				Err(PopError::Empty) => thread::yield_now(),
+				Err(PopError::Closed) => break,
+			}
+		}
+		for thread in threads {
+			thread.join().unwrap();
+		}
+		assert_eq!(sum, 3);
+	});
+}
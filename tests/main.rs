@@ -1,4 +1,8 @@
-use vringbuf::RingBuffer;
+use std::cell::Cell;
+use std::{cmp, iter};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use vringbuf::{GrowthPolicy, InvariantError, RingBuffer, TryReserveError};
 
 #[test]
 fn test_looptheloop() {
@@ -22,3 +26,676 @@ fn test_looptheloop() {
 		rbuf.clear();
 	}
 }
+
+#[test]
+fn test_try_reserve() {
+	let err = RingBuffer::<u32>::try_with_capacity(usize::MAX).unwrap_err();
+	assert!(matches!(err, TryReserveError::CapacityOverflow));
+
+	let mut rbuf = RingBuffer::<u32>::try_with_capacity(10).unwrap();
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	let cap = rbuf.capacity();
+
+	// The ring buffer is left unchanged on error
+	assert!(matches!(rbuf.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow)));
+	assert!(matches!(rbuf.try_reserve_exact(isize::MAX as usize / 4), Err(TryReserveError::CapacityOverflow)));
+	assert_eq!(rbuf.capacity(), cap);
+	assert_eq!(rbuf.as_slice(), [1, 2, 3]);
+
+	rbuf.try_reserve(cap).unwrap();
+	assert!(rbuf.capacity() >= cap + 3);
+	assert_eq!(rbuf.as_slice(), [1, 2, 3]);
+}
+
+#[test]
+fn test_reserve_exact() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.reserve_exact(1);
+	// Rounded up to the allocation granularity
+	let granularity = rbuf.capacity();
+	assert!(granularity >= 1);
+
+	rbuf.extend_from_slice(&vec![0; granularity]);
+	rbuf.reserve_exact(1);
+	assert_eq!(rbuf.capacity(), granularity * 2);
+	rbuf.reserve_exact(granularity);
+	assert_eq!(rbuf.capacity(), granularity * 2);
+}
+
+#[test]
+fn test_amortized_growth() {
+	let mut rbuf = RingBuffer::<u64>::new();
+	let mut reallocations = 0;
+	let mut cap = rbuf.capacity();
+	for i in 0..1000000 {
+		rbuf.push(i);
+		if rbuf.capacity() != cap {
+			assert!(rbuf.capacity() >= cap * 2);
+			cap = rbuf.capacity();
+			reallocations += 1;
+		}
+	}
+	assert!(reallocations <= 20, "{} reallocations", reallocations);
+}
+
+#[test]
+fn test_growth_policy() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	assert!(matches!(rbuf.growth_policy(), GrowthPolicy::Doubling));
+	rbuf.reserve(1);
+	let granularity = rbuf.capacity();
+
+	rbuf.set_growth_policy(GrowthPolicy::Exact);
+	rbuf.extend_from_slice(&vec![0; granularity + 1]);
+	assert_eq!(rbuf.capacity(), granularity * 2);
+
+	rbuf.set_growth_policy(GrowthPolicy::Pages(3));
+	rbuf.extend_from_slice(&vec![0; granularity]);
+	assert_eq!(rbuf.capacity(), granularity * 5);
+
+	rbuf.set_growth_policy(GrowthPolicy::Custom(|capacity, _| capacity * 4));
+	rbuf.extend_from_slice(&vec![0; granularity * 3]);
+	assert_eq!(rbuf.capacity(), granularity * 20);
+	assert_eq!(rbuf.len(), granularity * 5 + 1);
+
+	// The policy is kept across reallocations and clones
+	assert!(matches!(rbuf.clone().growth_policy(), GrowthPolicy::Custom(_)));
+}
+
+#[test]
+fn test_growth_fallback() {
+	// Twice the capacity of the policy exceeds the address space, growing falls back to the required capacity
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.set_growth_policy(GrowthPolicy::Custom(|_, _| usize::MAX));
+	rbuf.push(1);
+	let capacity = rbuf.capacity();
+	assert!(rbuf.try_reserve(capacity).is_ok());
+	assert!(rbuf.capacity() > capacity && rbuf.capacity() < isize::MAX as usize / 4);
+	assert_eq!(rbuf.as_slice(), &[1]);
+}
+
+#[cfg(all(feature = "std", target_os = "linux", target_pointer_width = "64", not(heap_fallback)))]
+#[test]
+fn test_platform_error() {
+	// Twice the capacity exceeds the address space, mapping it fails with the platform error of the system call
+	let err = match RingBuffer::<u8>::try_with_capacity(1 << 46) {
+		Err(TryReserveError::AllocError(err)) => err,
+		result => panic!("unexpected {:?}", result.map(|rb| rb.capacity())),
+	};
+	assert_eq!(err.call(), "mmap");
+	assert_eq!(err.size(), 1 << 47);
+	assert_eq!(err.raw_os_error(), Some(libc::ENOMEM));
+	assert_eq!(std::io::Error::from(err).kind(), std::io::ErrorKind::OutOfMemory);
+}
+
+#[test]
+fn test_page_aligned() {
+	// Aligned to the smallest page size, the largest alignment ring buffers support
+	#[derive(Copy, Clone)]
+	#[repr(align(4096))]
+	struct Page(#[allow(dead_code)] u8);
+
+	let mut rbuf = RingBuffer::<Page>::new();
+	for _ in 0..3 {
+		rbuf.extend_from_slice(&[Page(0); 5]);
+		rbuf.remove_tail(4);
+		assert_eq!(rbuf.as_ptr() as usize % 4096, 0);
+	}
+}
+
+#[test]
+fn test_effective_capacity() {
+	let granularity = RingBuffer::<u8>::allocation_granularity();
+	assert!(granularity.is_power_of_two());
+	assert_eq!(RingBuffer::<u32>::effective_capacity_for(0), Some(0));
+	assert_eq!(RingBuffer::<u32>::effective_capacity_for(1), Some(granularity / 4));
+	assert_eq!(RingBuffer::<u32>::effective_capacity_for(granularity / 4 + 1), Some(granularity / 2));
+	assert_eq!(RingBuffer::<u32>::effective_capacity_for(usize::MAX), None);
+	for n in [1, 1000, granularity + 1] {
+		assert_eq!(RingBuffer::<u64>::effective_capacity_for(n), Some(RingBuffer::<u64>::with_capacity(n).capacity()));
+	}
+}
+
+#[cfg(all(unix, not(heap_fallback)))]
+#[test]
+fn test_address_range() {
+	let granularity = RingBuffer::<u8>::allocation_granularity();
+
+	// Find some free address space to place the ring buffer in
+	let len = granularity * 16;
+	let free = unsafe { libc::mmap(std::ptr::null_mut(), len, libc::PROT_NONE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1, 0) };
+	assert_ne!(free, libc::MAP_FAILED);
+	unsafe { libc::munmap(free, len); }
+	let range = free as usize..free as usize + len;
+
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(granularity).address_range(range.clone()).build().unwrap();
+	let addr = rbuf.as_ptr() as usize;
+	assert!(range.contains(&addr) && addr + 2 * rbuf.capacity() <= range.end);
+	rbuf.extend_from_slice(&vec![1; granularity * 2]);
+	assert!(range.contains(&(rbuf.as_ptr() as usize)));
+
+	// No room left for a mapping twice the size of the range
+	let err = RingBuffer::<u8>::builder().capacity(len).address_range(range).build().unwrap_err();
+	assert!(matches!(err, TryReserveError::AllocError(vringbuf::PlatformError::OutOfRange { .. })));
+	#[cfg(feature = "std")]
+	assert_eq!(std::io::Error::from(err).kind(), std::io::ErrorKind::AddrNotAvailable);
+}
+
+#[cfg(all(target_os = "linux", not(heap_fallback)))]
+#[test]
+fn test_guard_pages() {
+	let granularity = RingBuffer::<u8>::allocation_granularity();
+	// Whether the page at the address is mapped
+	let mapped = |addr: usize| unsafe {
+		let ptr = libc::mmap(addr as *mut libc::c_void, granularity, libc::PROT_NONE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED_NOREPLACE, -1, 0);
+		if ptr != libc::MAP_FAILED {
+			libc::munmap(ptr, granularity);
+		}
+		ptr as usize != addr
+	};
+
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(granularity).guard_pages(true).build().unwrap();
+	let addr = rbuf.as_ptr() as usize;
+	assert!(mapped(addr - granularity) && mapped(addr + 2 * rbuf.capacity()));
+
+	// Growing maps new guard pages around the new mapping
+	rbuf.extend_from_slice(&vec![1; granularity * 2]);
+	let addr = rbuf.as_ptr() as usize;
+	assert!(mapped(addr - granularity) && mapped(addr + 2 * rbuf.capacity()));
+}
+
+#[cfg(all(target_os = "linux", not(heap_fallback)))]
+#[test]
+fn test_overrun_detection() {
+	// Bytes of the mapping which are inaccessible, the mapping starts at most a capacity before the elements
+	fn protected(rbuf: &RingBuffer<u8>) -> usize {
+		let (start, end) = (rbuf.as_ptr() as usize - rbuf.capacity(), rbuf.as_ptr() as usize + 2 * rbuf.capacity());
+		let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+		maps.lines().filter(|line| line.contains(" ---s ")).map(|line| {
+			let range = line.split(' ').next().unwrap();
+			let (lo, hi) = range.split_at(range.find('-').unwrap());
+			let (lo, hi) = (usize::from_str_radix(lo, 16).unwrap(), usize::from_str_radix(&hi[1..], 16).unwrap());
+			cmp::min(hi, end).saturating_sub(cmp::max(lo, start))
+		}).sum()
+	}
+
+	let granularity = RingBuffer::<u8>::allocation_granularity();
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(granularity * 4).overrun_detection(true).build().unwrap();
+	rbuf.reserve(1);
+	assert_eq!(protected(&rbuf), 2 * granularity * 3);
+
+	// Wrapping around keeps the elements accessible in both views
+	rbuf.extend_from_slice(&vec![1; granularity * 3]);
+	rbuf.remove_tail(granularity * 2);
+	rbuf.extend_from_slice(&vec![2; granularity * 2 - 1]);
+	assert_eq!(protected(&rbuf), 2 * granularity);
+	assert!(rbuf[..granularity].iter().all(|&byte| byte == 1));
+	assert!(rbuf[granularity..].iter().all(|&byte| byte == 2));
+
+	// The spare capacity handed out as a slice is accessible
+	rbuf.reserved_mut().iter_mut().for_each(|byte| *byte = std::mem::MaybeUninit::new(3));
+	assert_eq!(protected(&rbuf), 0);
+}
+
+#[test]
+fn test_extend() {
+	// Iterators without a size hint fill the reserved room in batches
+	let mut rbuf = RingBuffer::<u32>::new();
+	rbuf.extend(iter::empty());
+	assert_eq!(rbuf.capacity(), 0);
+	rbuf.extend((0..100_000).filter(|i| i % 3 == 0));
+	assert!(rbuf.iter().copied().eq((0..100_000).filter(|i| i % 3 == 0)));
+
+	// Iterators of exact size allocate once
+	let rbuf: RingBuffer<u32> = (0..100_000).collect();
+	assert_eq!(rbuf.capacity(), RingBuffer::<u32>::effective_capacity_for(100_000).unwrap());
+	assert!(rbuf.iter().copied().eq(0..100_000));
+}
+
+#[cfg(all(target_os = "linux", not(heap_fallback)))]
+#[test]
+fn test_extend_overrun_detection() {
+	let granularity = RingBuffer::<u8>::allocation_granularity();
+	let mut rbuf = RingBuffer::<u8>::builder().overrun_detection(true).build().unwrap();
+	rbuf.extend((0..granularity * 5).map(|i| i as u8).filter(|_| true));
+	assert!(rbuf.iter().copied().eq((0..granularity * 5).map(|i| i as u8)));
+}
+
+#[test]
+fn test_drain_tail() {
+	let value = Rc::new(());
+	let mut rbuf = RingBuffer::new();
+	rbuf.extend((0..5).map(|i| (i, value.clone())));
+	let mut drain = rbuf.drain_tail(3);
+	assert_eq!(drain.len(), 3);
+	assert_eq!(drain.next().map(|(i, _)| i), Some(0));
+	assert_eq!(drain.as_slice().len(), 2);
+	// The elements which were not yielded are dropped with the iterator
+	drop(drain);
+	assert_eq!(Rc::strong_count(&value), 3);
+	assert_eq!(rbuf[0].0, 3);
+
+	assert_eq!(rbuf.drain_tail(10).map(|(i, _)| i).collect::<Vec<_>>(), [3, 4]);
+	assert!(rbuf.is_empty());
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_read_into_copy_to_slice() {
+	let mut rbuf = RingBuffer::<u32>::with_capacity(1);
+	let mut dst = [0; 8];
+	assert_eq!(rbuf.read_into(&mut dst), 0);
+	// Wraps around the end of the mapping
+	let capacity = rbuf.capacity();
+	rbuf.extend_from_slice(&vec![1; capacity - 2]);
+	rbuf.remove_tail(capacity - 2);
+	rbuf.extend_from_slice(&[1, 2, 3, 4, 5]);
+	assert_eq!(rbuf.copy_to_slice(&mut dst), 5);
+	assert_eq!(rbuf.len(), 5);
+	assert_eq!(rbuf.read_into(&mut dst[..3]), 3);
+	assert_eq!(rbuf.read_into(&mut dst[3..]), 2);
+	assert_eq!(dst[..5], [1, 2, 3, 4, 5]);
+	assert!(rbuf.is_empty());
+}
+
+#[test]
+fn test_resize_default() {
+	let mut rbuf = RingBuffer::<Vec<u8>>::new();
+	rbuf.resize_default(3);
+	assert_eq!(rbuf.as_slice(), [vec![], vec![], vec![]]);
+	rbuf[0].push(1);
+	rbuf.try_resize_default(1).unwrap();
+	assert_eq!(rbuf.as_slice(), [vec![1]]);
+}
+
+#[test]
+fn test_capacity_limit() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.reserve_exact(1);
+	let granularity = rbuf.capacity();
+	assert_eq!(rbuf.capacity_limit(), usize::MAX);
+
+	// The limit is rounded down to the allocation granularity
+	rbuf.set_capacity_limit(granularity * 3 + 1);
+	rbuf.extend_from_slice(&vec![0; granularity + 1]);
+	assert_eq!(rbuf.capacity(), granularity * 2);
+	rbuf.try_extend_from_slice(&vec![0; granularity]).unwrap();
+	assert_eq!(rbuf.capacity(), granularity * 3);
+	rbuf.try_extend_from_slice(&vec![0; granularity - 1]).unwrap();
+	assert_eq!(rbuf.len(), granularity * 3);
+
+	assert!(matches!(rbuf.try_push(0), Err(TryReserveError::LimitExceeded)));
+	assert!(matches!(rbuf.try_extend_from_slice(&[0]), Err(TryReserveError::LimitExceeded)));
+	assert!(matches!(rbuf.try_reserve(1), Err(TryReserveError::LimitExceeded)));
+	assert!(matches!(rbuf.try_resize(granularity * 4, 0), Err(TryReserveError::LimitExceeded)));
+	assert!(matches!(rbuf.try_resize_with(granularity * 4, || unreachable!()), Err(TryReserveError::LimitExceeded)));
+	assert_eq!(rbuf.len(), granularity * 3);
+
+	// Room freed by popping can be reused
+	rbuf.pop();
+	rbuf.try_push(1).unwrap();
+	assert_eq!(rbuf.capacity(), granularity * 3);
+	rbuf.try_resize(granularity, 2).unwrap();
+	rbuf.try_resize(granularity * 2, 3).unwrap();
+	assert_eq!(rbuf.len(), granularity * 2);
+	assert_eq!(rbuf[granularity], 3);
+}
+
+#[test]
+fn test_builder() {
+	let rbuf = RingBuffer::<u32>::builder().build().unwrap();
+	assert_eq!(rbuf.capacity(), 0);
+
+	let mut rbuf = RingBuffer::<u32>::builder()
+		.capacity(100)
+		.growth_policy(GrowthPolicy::Exact)
+		.capacity_limit(100000)
+		.build()
+		.unwrap();
+	assert!(rbuf.capacity() >= 100);
+	assert!(matches!(rbuf.growth_policy(), GrowthPolicy::Exact));
+	assert_eq!(rbuf.capacity_limit(), 100000);
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	assert_eq!(rbuf.as_slice(), [1, 2, 3]);
+
+	let err = RingBuffer::<u32>::builder().capacity(2).capacity_limit(1).build().unwrap_err();
+	assert!(matches!(err, TryReserveError::LimitExceeded));
+}
+
+#[test]
+fn test_grow_wrapped() {
+	// Odd element sizes straddle the end of the mapping
+	for &(popped, policy) in &[(10, GrowthPolicy::Doubling), (1000, GrowthPolicy::Doubling), (10, GrowthPolicy::Exact), (1000, GrowthPolicy::Exact)] {
+		let mut rbuf = RingBuffer::<[u8; 3]>::with_capacity(1);
+		rbuf.set_growth_policy(policy);
+		let capacity = rbuf.capacity();
+		let mut next = 0u32;
+		let mut value = || {
+			next += 1;
+			[next as u8, (next >> 8) as u8, (next >> 16) as u8]
+		};
+		for _ in 0..capacity {
+			rbuf.push(value());
+		}
+		rbuf.remove_tail(cmp::min(popped, capacity - 1));
+		while rbuf.len() < capacity {
+			rbuf.push(value());
+		}
+		let expected: Vec<[u8; 3]> = rbuf.iter().cloned().collect();
+		#[cfg(all(feature = "std", target_os = "linux", not(heap_fallback)))]
+		let fd = std::os::unix::io::AsRawFd::as_raw_fd(&rbuf);
+		rbuf.push(value());
+		assert!(rbuf.capacity() > capacity);
+		// The memfd is grown in place
+		#[cfg(all(feature = "std", target_os = "linux", not(heap_fallback)))]
+		assert_eq!(std::os::unix::io::AsRawFd::as_raw_fd(&rbuf), fd);
+		assert_eq!(rbuf[..expected.len()], expected[..]);
+		assert_eq!(rbuf.len(), capacity + 1);
+	}
+}
+
+#[test]
+fn test_shrink_to() {
+	let mut rbuf = RingBuffer::<u32>::new();
+	rbuf.reserve_exact(1);
+	let granularity = rbuf.capacity();
+	rbuf.extend_from_slice(&vec![7; granularity * 4]);
+	rbuf.remove_tail(granularity * 4 - 3);
+	rbuf.extend_from_slice(&[1, 2]);
+
+	rbuf.shrink_to(granularity + 1);
+	assert_eq!(rbuf.capacity(), granularity * 2);
+	assert_eq!(rbuf.as_slice(), [7, 7, 7, 1, 2]);
+	rbuf.shrink_to_fit();
+	assert_eq!(rbuf.capacity(), granularity);
+	assert_eq!(rbuf.as_slice(), [7, 7, 7, 1, 2]);
+	rbuf.clear();
+	rbuf.shrink_to_fit();
+	assert_eq!(rbuf.capacity(), 0);
+}
+
+#[test]
+fn test_panic_safety() {
+	let value = Rc::new(());
+	let n = Cell::new(0);
+	let make = || {
+		n.set(n.get() + 1);
+		if n.get() == 4 {
+			panic!("constructor panicked");
+		}
+		value.clone()
+	};
+
+	// The elements constructed before the panic are kept
+	let mut rbuf = RingBuffer::<Rc<()>>::new();
+	rbuf.push(value.clone());
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| rbuf.resize_with(10, make))).is_err());
+	assert_eq!(rbuf.len(), 4);
+	assert_eq!(Rc::strong_count(&value), 5);
+
+	n.set(0);
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| rbuf.extend((0..10).map(|_| make())))).is_err());
+	assert_eq!(rbuf.len(), 7);
+	assert_eq!(Rc::strong_count(&value), 8);
+
+	// Iterators without a size hint go through push
+	n.set(0);
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| rbuf.extend(iter::from_fn(|| Some(make())).take(10)))).is_err());
+	assert_eq!(rbuf.len(), 10);
+	drop(rbuf);
+	assert_eq!(Rc::strong_count(&value), 1);
+
+	// The elements cloned before the panic are kept
+	struct Bomb(Rc<()>, bool);
+	impl Clone for Bomb {
+		fn clone(&self) -> Bomb {
+			if self.1 {
+				panic!("clone panicked");
+			}
+			Bomb(self.0.clone(), false)
+		}
+	}
+	let source = [Bomb(value.clone(), false), Bomb(value.clone(), true)];
+	let mut rbuf = RingBuffer::new();
+	rbuf.extend_from_slice_cloned(&source[..1]);
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| rbuf.extend_from_slice_cloned(&source))).is_err());
+	assert_eq!(rbuf.len(), 2);
+	assert_eq!(Rc::strong_count(&value), 5);
+	drop((rbuf, source));
+	assert_eq!(Rc::strong_count(&value), 1);
+
+	// The elements collected before the panic are dropped
+	n.set(0);
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| (0..10).map(|_| make()).collect::<RingBuffer<_>>())).is_err());
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_validate() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	assert_eq!(rbuf.validate(), Ok(()));
+	rbuf.extend_from_slice(b"hello");
+	rbuf.pop();
+	assert_eq!(rbuf.validate(), Ok(()));
+
+	// Debug builds catch the length exceeding the capacity right away
+	let capacity = rbuf.capacity();
+	let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe { rbuf.set_len(capacity + 1) }));
+	assert_eq!(result.is_err(), cfg!(debug_assertions));
+	assert_eq!(rbuf.validate(), Err(InvariantError::Length { len: capacity + 1, capacity }));
+
+	unsafe { rbuf.set_len(4) };
+	assert_eq!(rbuf.validate(), Ok(()));
+	assert_eq!(rbuf.as_slice(), b"ello");
+}
+
+#[test]
+fn test_raw_parts() {
+	// Wrapped around the end of the mapping
+	let mut rbuf = RingBuffer::<u32>::with_capacity(1);
+	let capacity = rbuf.capacity();
+	rbuf.extend(0..capacity as u32);
+	rbuf.remove_tail(capacity - 2);
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	let expected = rbuf.as_slice().to_vec();
+
+	let (ptr, cap, base, len, handle) = rbuf.into_raw_parts();
+	assert_eq!((cap, len), (capacity * 4, 5));
+	assert_eq!(base, (capacity - 2) * 4);
+	let mut rbuf = unsafe { RingBuffer::from_raw_parts(ptr, cap, base, len, handle) };
+	assert_eq!(rbuf.validate(), Ok(()));
+	assert_eq!(rbuf.as_slice(), &expected[..]);
+	rbuf.push(4);
+	assert_eq!(rbuf.len(), 6);
+
+	// The backend keeps the options
+	let rbuf = RingBuffer::<u8>::builder().name("parts").capacity(1).build().unwrap();
+	let (ptr, cap, base, len, handle, memory) = rbuf.into_raw_parts_with_memory();
+	let rbuf = unsafe { RingBuffer::from_raw_parts_in(ptr, cap, base, len, handle, memory) };
+	assert_eq!(rbuf.name(), Some("parts"));
+
+	// Nothing allocated
+	let (ptr, cap, base, len, handle) = RingBuffer::<u8>::new().into_raw_parts();
+	assert_eq!((cap, base, len), (0, 0, 0));
+	assert!(handle.is_none());
+	let rbuf = unsafe { RingBuffer::<u8>::from_raw_parts(ptr, cap, base, len, handle) };
+	assert_eq!(rbuf.capacity(), 0);
+}
+
+#[test]
+fn test_push_with() {
+	let mut rbuf = RingBuffer::<[u64; 512]>::new();
+	unsafe {
+		rbuf.push_with(|slot| {
+			for (i, value) in (*slot.as_mut_ptr()).iter_mut().enumerate() {
+				*value = i as u64;
+			}
+		});
+	}
+	assert_eq!(rbuf.len(), 1);
+	assert!(rbuf[0].iter().enumerate().all(|(i, &value)| value == i as u64));
+
+	// Nothing is appended if constructing the element panics
+	let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe { rbuf.push_with(|_| panic!()) }));
+	assert!(result.is_err());
+	assert_eq!(rbuf.len(), 1);
+
+	// Not called if the ring buffer cannot grow
+	let capacity = rbuf.capacity();
+	rbuf.set_capacity_limit(capacity);
+	for _ in 1..capacity {
+		unsafe { rbuf.try_push_with(|slot| { slot.as_mut_ptr().write([7; 512]); }).unwrap(); }
+	}
+	let result = unsafe { rbuf.try_push_with(|_| unreachable!()) };
+	assert!(matches!(result, Err(TryReserveError::LimitExceeded)));
+	assert_eq!(rbuf.len(), capacity);
+}
+
+// The heap fallback has no mirrored memory to borrow
+#[cfg(not(heap_fallback))]
+#[test]
+fn test_from_mirrored_ptr() {
+	// Borrow the mapping of another ring buffer
+	let mut backing = RingBuffer::<u8>::with_capacity(1);
+	let capacity = backing.capacity();
+	let ptr = backing.as_mut_ptr();
+
+	let mut rbuf = unsafe { RingBuffer::<u8>::from_mirrored_ptr(ptr, capacity) };
+	assert_eq!((rbuf.capacity(), rbuf.len(), rbuf.as_ptr()), (capacity, 0, ptr as *const u8));
+	rbuf.extend_from_slice(&vec![1; capacity - 2]);
+	rbuf.remove_tail(capacity - 2);
+	rbuf.extend_from_slice(b"wrap");
+	assert_eq!(rbuf.as_slice(), b"wrap");
+	rbuf.decommit();
+	drop(rbuf);
+
+	// The memory is still mapped and holds the bytes
+	unsafe { backing.set_len(capacity) };
+	assert_eq!(&backing[capacity - 2..], b"wr");
+	assert_eq!(&backing[..2], b"ap");
+
+	// Growing moves to memory of its own
+	let mut rbuf = unsafe { RingBuffer::<u8>::from_mirrored_ptr(ptr, capacity) };
+	rbuf.extend_from_slice(b"grow");
+	rbuf.reserve(capacity);
+	assert_ne!(rbuf.as_ptr(), ptr as *const u8);
+	assert_eq!(rbuf.as_slice(), b"grow");
+}
+
+#[test]
+fn test_leak() {
+	let mut rbuf = RingBuffer::<u32>::with_capacity(1);
+	let capacity = rbuf.capacity();
+	rbuf.extend(0..capacity as u32);
+	rbuf.remove_tail(capacity - 1);
+	rbuf.extend_from_slice(&[1, 2]);
+	let leaked: &'static mut [u32] = rbuf.leak();
+	leaked[0] += 1;
+	assert_eq!(leaked, &[capacity as u32, 1, 2]);
+}
+
+#[test]
+fn test_map_attempts() {
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(1).map_attempts(100, std::time::Duration::from_millis(1)).build().unwrap();
+	rbuf.extend_from_slice(&vec![1; rbuf.capacity() + 1]);
+	assert!(rbuf.iter().all(|&byte| byte == 1));
+	// Mapping only races on older versions of Windows
+	if !cfg!(windows) {
+		assert_eq!(vringbuf::map_retries(), 0);
+	}
+}
+
+#[test]
+fn test_huge_pages() {
+	let small = RingBuffer::<u8>::with_capacity(1);
+	// Falls back to regular pages if no huge pages are reserved
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(1).huge_pages(true).build().unwrap();
+	assert!(rbuf.page_size() >= small.page_size());
+	assert_eq!(rbuf.capacity() % rbuf.page_size(), 0);
+	rbuf.extend_from_slice(&vec![1; rbuf.capacity() + 1]);
+	assert_eq!(rbuf.capacity() % rbuf.page_size(), 0);
+	assert!(rbuf.iter().all(|&byte| byte == 1));
+}
+
+#[test]
+fn test_transparent_huge_pages() {
+	let small = RingBuffer::<u8>::with_capacity(1);
+	assert!(!small.transparent_huge_pages());
+	// The hint is only available if the kernel supports transparent huge pages
+	let supported = cfg!(all(target_os = "linux", not(heap_fallback))) && std::path::Path::new("/sys/kernel/mm/transparent_hugepage").exists();
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(1 << 21).transparent_huge_pages(true).build().unwrap();
+	assert_eq!(rbuf.transparent_huge_pages(), supported);
+	// Growing keeps the hint
+	rbuf.extend_from_slice(&vec![1; rbuf.capacity() + 1]);
+	assert_eq!(rbuf.transparent_huge_pages(), supported);
+	assert!(rbuf.iter().all(|&byte| byte == 1));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_lock_memory() {
+	// The limit on locked memory may be too low to lock anything
+	let mut rbuf = match RingBuffer::<u8>::builder().capacity(1).lock_memory(true).build() {
+		Ok(rbuf) => rbuf,
+		Err(_) => return,
+	};
+	assert!(rbuf.is_memory_locked());
+	rbuf.extend_from_slice(&[1, 2, 3]);
+	let clone = rbuf.clone();
+	assert!(clone.is_memory_locked());
+	assert_eq!(clone.as_slice(), &[1, 2, 3]);
+	rbuf.unlock_memory();
+	assert!(!rbuf.is_memory_locked());
+	rbuf.lock_memory().unwrap();
+	assert!(rbuf.is_memory_locked());
+}
+
+#[cfg(all(target_os = "linux", not(heap_fallback)))]
+#[test]
+fn test_exclude_from_dumps() {
+	// The kernel marks mappings left out of core dumps as `dd`
+	fn vm_flags(ptr: *const u8) -> String {
+		let smaps = std::fs::read_to_string("/proc/self/smaps").unwrap();
+		let start = format!("{:x}-", ptr as usize);
+		let mut lines = smaps.lines().skip_while(|line| !line.starts_with(&start));
+		lines.find(|line| line.starts_with("VmFlags:")).unwrap().to_string()
+	}
+	let rbuf = RingBuffer::<u8>::builder().capacity(1).exclude_from_dumps(true).build().unwrap();
+	assert!(vm_flags(rbuf.as_ptr()).split_whitespace().any(|flag| flag == "dd"));
+	let rbuf = RingBuffer::<u8>::with_capacity(1);
+	assert!(!vm_flags(rbuf.as_ptr()).split_whitespace().any(|flag| flag == "dd"));
+}
+
+#[test]
+fn test_name() {
+	let rbuf = RingBuffer::<u8>::builder().capacity(1).name("rx-conn-42").build().unwrap();
+	assert_eq!(rbuf.name(), Some("rx-conn-42"));
+	assert_eq!(RingBuffer::<u8>::new().name(), None);
+	#[cfg(all(target_os = "linux", not(heap_fallback)))]
+	{
+		let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+		assert!(maps.lines().any(|line| line.starts_with(&format!("{:x}-", rbuf.as_ptr() as usize)) && line.contains("/memfd:ringbuf:rx-conn-42")));
+	}
+}
+
+#[cfg(all(target_os = "linux", not(heap_fallback)))]
+#[test]
+fn test_prefault() {
+	// Resident memory of the first view in kB
+	fn rss(ptr: *const u8) -> usize {
+		let smaps = std::fs::read_to_string("/proc/self/smaps").unwrap();
+		let start = format!("{:x}-", ptr as usize);
+		let mut lines = smaps.lines().skip_while(|line| !line.starts_with(&start));
+		let line = lines.find(|line| line.starts_with("Rss:")).unwrap();
+		line["Rss:".len()..].trim().strip_suffix("kB").unwrap().trim().parse().unwrap()
+	}
+	let mut rbuf = RingBuffer::<u8>::builder().capacity(1 << 16).prefault(true).build().unwrap();
+	assert_eq!(rss(rbuf.as_ptr()) * 1024, rbuf.capacity());
+	rbuf.extend_from_slice(&vec![1; rbuf.capacity() + 1]);
+	assert_eq!(rss(rbuf.as_ptr()) * 1024, rbuf.capacity());
+
+	let mut rbuf = RingBuffer::<u8>::with_capacity(1 << 16);
+	assert_eq!(rss(rbuf.as_ptr()), 0);
+	rbuf.prefault();
+	assert_eq!(rss(rbuf.as_ptr()) * 1024, rbuf.capacity());
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use vringbuf::{MirroredMemory, OsMemory, RingBuffer, TryReserveError};
+
+// Test double counting the mappings it hands out, the memory comes from the OS backend
+#[derive(Default)]
+struct Counting {
+	os: OsMemory,
+	allocated: Cell<usize>,
+	freed: Cell<usize>,
+	fail: Cell<bool>,
+}
+
+unsafe impl MirroredMemory for Counting {
+	type Handle = <OsMemory as MirroredMemory>::Handle;
+
+	fn granularity(&self) -> usize {
+		self.os.granularity()
+	}
+
+	fn allocate(&self, size: usize) -> Result<(NonNull<u8>, usize, Self::Handle), TryReserveError> {
+		if self.fail.get() {
+			return Err(TryReserveError::CapacityOverflow);
+		}
+		self.allocated.set(self.allocated.get() + 1);
+		self.os.allocate(size)
+	}
+
+	unsafe fn free(&self, ptr: NonNull<u8>, size: usize, handle: Self::Handle) {
+		self.freed.set(self.freed.get() + 1);
+		self.os.free(ptr, size, handle);
+	}
+
+	// The heap fallback copies the elements which wrap around
+	unsafe fn wrap(&self, ptr: NonNull<u8>, size: usize, base: usize, len: usize) {
+		self.os.wrap(ptr, size, base, len);
+	}
+}
+
+#[test]
+fn test_custom_backend() {
+	let mut rbuf = RingBuffer::<u32, Counting>::new_in(Counting::default());
+	assert_eq!(rbuf.memory().allocated.get(), 0);
+
+	rbuf.extend(0..10);
+	assert_eq!(rbuf.memory().allocated.get(), 1);
+	assert_eq!(rbuf.capacity() * 4 % rbuf.memory().granularity(), 0);
+
+	// Growing allocates a new mapping and frees the old one
+	let capacity = rbuf.capacity();
+	rbuf.extend(0..capacity as u32);
+	assert_eq!(rbuf.memory().allocated.get(), 2);
+	assert_eq!(rbuf.memory().freed.get(), 1);
+	assert_eq!(rbuf.len(), 10 + capacity);
+	assert!(rbuf.iter().take(10).copied().eq(0..10));
+
+	// The elements wrap around through the mirrored mapping
+	rbuf.remove_tail(capacity);
+	rbuf.extend(0..capacity as u32);
+	assert!(rbuf.iter().skip(10).copied().eq(0..capacity as u32));
+
+	// Errors of the backend are returned by the fallible methods
+	rbuf.memory().fail.set(true);
+	assert!(matches!(rbuf.try_reserve(rbuf.capacity()), Err(TryReserveError::CapacityOverflow)));
+	rbuf.memory().fail.set(false);
+
+	rbuf.clear();
+	rbuf.shrink_to_fit();
+	assert_eq!(rbuf.capacity(), 0);
+	assert_eq!(rbuf.memory().freed.get(), 2);
+}
@@ -0,0 +1,107 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use proptest::prelude::*;
+use vringbuf::RingBuffer;
+
+// Element counting how many of its kind are alive
+#[derive(Debug)]
+struct Counted {
+	value: u32,
+	alive: Rc<Cell<usize>>,
+}
+impl Counted {
+	fn new(value: u32, alive: &Rc<Cell<usize>>) -> Counted {
+		alive.set(alive.get() + 1);
+		Counted { value, alive: alive.clone() }
+	}
+}
+impl PartialEq for Counted {
+	fn eq(&self, other: &Counted) -> bool {
+		self.value == other.value
+	}
+}
+impl Drop for Counted {
+	fn drop(&mut self) {
+		self.alive.set(self.alive.get() - 1);
+	}
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+	Push(u32),
+	Pop,
+	Extend(u32, usize),
+	RemoveTail(usize),
+	Reserve(usize),
+	Truncate(usize),
+	Clear,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+	prop_oneof![
+		4 => any::<u32>().prop_map(Op::Push),
+		3 => Just(Op::Pop),
+		2 => (any::<u32>(), 0..5000usize).prop_map(|(start, n)| Op::Extend(start, n)),
+		2 => (0..5000usize).prop_map(Op::RemoveTail),
+		1 => (0..10000usize).prop_map(Op::Reserve),
+		1 => (0..5000usize).prop_map(Op::Truncate),
+		1 => Just(Op::Clear),
+	]
+}
+
+fn apply(rbuf: &mut RingBuffer<Counted>, model: &mut VecDeque<Counted>, op: &Op, alive: &Rc<Cell<usize>>) {
+	match *op {
+		Op::Push(value) => {
+			rbuf.push(Counted::new(value, alive));
+			model.push_back(Counted::new(value, alive));
+		},
+		Op::Pop => {
+			assert_eq!(rbuf.pop(), model.pop_front());
+		},
+		Op::Extend(start, n) => {
+			let values = (0..n as u32).map(|i| start.wrapping_add(i));
+			rbuf.extend(values.clone().map(|value| Counted::new(value, alive)));
+			model.extend(values.map(|value| Counted::new(value, alive)));
+		},
+		Op::RemoveTail(n) => {
+			rbuf.remove_tail(n);
+			model.drain(..usize::min(n, model.len()));
+		},
+		Op::Reserve(additional) => {
+			rbuf.reserve(additional);
+			assert!(rbuf.capacity() >= rbuf.len() + additional);
+		},
+		Op::Truncate(len) => {
+			rbuf.truncate(len);
+			model.truncate(len);
+		},
+		Op::Clear => {
+			rbuf.clear();
+			model.clear();
+		},
+	}
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig::with_cases(64))]
+
+	#[test]
+	fn test_model(ops in prop::collection::vec(op(), 0..100)) {
+		let alive = Rc::new(Cell::new(0));
+		{
+			let mut rbuf = RingBuffer::new();
+			let mut model = VecDeque::new();
+			for op in &ops {
+				apply(&mut rbuf, &mut model, op, &alive);
+				prop_assert_eq!(rbuf.len(), model.len());
+				prop_assert!(rbuf.capacity() >= rbuf.len());
+				prop_assert!(rbuf.iter().eq(model.iter()));
+				prop_assert_eq!(rbuf.validate(), Ok(()));
+				prop_assert_eq!(alive.get(), rbuf.len() * 2);
+			}
+		}
+		// Dropping both leaks nothing
+		prop_assert_eq!(alive.get(), 0);
+	}
+}
@@ -0,0 +1,73 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use vringbuf::concurrent::MpmcRingBuffer;
+
+#[test]
+fn test_push_pop() {
+	let rbuf = MpmcRingBuffer::<String>::with_capacity(4);
+	let cap = rbuf.capacity();
+	assert!(cap >= 4);
+	assert!(rbuf.is_empty());
+	assert_eq!(rbuf.try_pop(), None);
+
+	// Fill and drain a few laps
+	for lap in 0..3 {
+		for i in 0..cap {
+			rbuf.try_push(format!("{}:{}", lap, i)).unwrap();
+		}
+		assert!(rbuf.is_full());
+		assert_eq!(rbuf.try_push(String::from("full")), Err(String::from("full")));
+		for i in 0..cap {
+			assert_eq!(rbuf.try_pop(), Some(format!("{}:{}", lap, i)));
+		}
+		assert!(rbuf.is_empty());
+	}
+
+	rbuf.try_push(String::from("dropped")).unwrap();
+	assert_eq!(rbuf.len(), 1);
+}
+
+#[test]
+fn test_threads() {
+	const THREADS: usize = 4;
+	const N: usize = 20_000;
+	let rbuf = Arc::new(MpmcRingBuffer::<usize>::with_capacity(64));
+	let sum = Arc::new(AtomicUsize::new(0));
+	let popped = Arc::new(AtomicUsize::new(0));
+
+	let mut threads = Vec::new();
+	for _ in 0..THREADS {
+		let producer = rbuf.clone();
+		threads.push(thread::spawn(move || {
+			for i in 1..=N {
+				let mut value = i;
+				while let Err(v) = producer.try_push(value) {
+					value = v;
+					thread::yield_now();
+				}
+			}
+		}));
+		let consumer = rbuf.clone();
+		let sum = sum.clone();
+		let popped = popped.clone();
+		threads.push(thread::spawn(move || {
+			while popped.load(Ordering::Relaxed) < THREADS * N {
+				match consumer.try_pop() {
+					Some(value) => {
+						sum.fetch_add(value, Ordering::Relaxed);
+						popped.fetch_add(1, Ordering::Relaxed);
+					},
+					None => thread::yield_now(),
+				}
+			}
+		}));
+	}
+	for thread in threads {
+		thread.join().unwrap();
+	}
+	assert_eq!(sum.load(Ordering::Relaxed), THREADS * N * (N + 1) / 2);
+	assert!(rbuf.is_empty());
+}
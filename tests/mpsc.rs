@@ -0,0 +1,99 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::thread;
+use vringbuf::RingBuffer;
+use vringbuf::concurrent::{PopError, PushError};
+
+#[test]
+fn test_push_pop() {
+	let mut rbuf = RingBuffer::<String>::with_capacity(4);
+	rbuf.push(String::from("a"));
+	rbuf.push(String::from("b"));
+	rbuf.pop();
+	let (mut producer, mut consumer) = rbuf.split_mpsc();
+	let cap = producer.capacity();
+	assert_eq!(producer.len(), 1);
+
+	// Fill and drain a few laps
+	for lap in 0..3 {
+		while producer.push(format!("{}", lap)).is_ok() {}
+		assert_eq!(producer.len(), cap);
+		if lap == 0 {
+			assert_eq!(consumer.pop().as_deref(), Ok("b"));
+		}
+		while let Ok(value) = consumer.pop() {
+			assert_eq!(value, format!("{}", lap));
+		}
+		assert!(consumer.is_empty());
+	}
+
+	producer.push(String::from("dropped")).unwrap();
+	drop(producer);
+	assert_eq!(consumer.pop().as_deref(), Ok("dropped"));
+	assert_eq!(consumer.pop(), Err(PopError::Closed));
+}
+
+#[test]
+fn test_closed() {
+	let rbuf = RingBuffer::<u8>::with_capacity(16);
+	let (mut producer, consumer) = rbuf.split_mpsc();
+	let mut other = producer.clone();
+	drop(consumer);
+	assert_eq!(producer.push(1), Err(PushError::Closed(1)));
+	assert_eq!(other.push_slice(&[1, 2]), 0);
+}
+
+#[test]
+fn test_threads() {
+	const THREADS: u64 = 4;
+	const N: u64 = 50_000;
+	let rbuf = RingBuffer::<u64>::with_capacity(64);
+	let (producer, mut consumer) = rbuf.split_mpsc();
+
+	let threads: Vec<_> = (0..THREADS).map(|t| {
+		let mut producer = producer.clone();
+		thread::spawn(move || {
+			let mut i = 0;
+			while i < N {
+				// Mix single pushes with claimed ranges
+				let values = [t << 32 | i, t << 32 | (i + 1)];
+				let n = if i % 2 == 0 { producer.push_slice(&values) } else { producer.push(values[0]).map_or(0, |_| 1) };
+				i += n as u64;
+				if n == 0 {
+					thread::yield_now();
+				}
+			}
+		})
+	}).collect();
+	drop(producer);
+
+	let mut next = [0; THREADS as usize];
+	loop {
+		match consumer.pop() {
+			Ok(value) => {
+				let t = (value >> 32) as usize;
+				assert_eq!(value & 0xffff_ffff, next[t]);
+				next[t] += 1;
+			},
+			Err(PopError::Empty) => thread::yield_now(),
+			Err(PopError::Closed) => break,
+		}
+	}
+	assert_eq!(next, [N; THREADS as usize]);
+	for thread in threads {
+		thread.join().unwrap();
+	}
+}
+
+#[test]
+fn test_zero_capacity() {
+	let (mut producer, mut consumer) = RingBuffer::<String>::new().split_mpsc();
+	assert_eq!(producer.capacity(), 0);
+	assert!(matches!(producer.push("a".to_string()), Err(PushError::Full(_))));
+	assert!(matches!(consumer.pop(), Err(PopError::Empty)));
+	assert!(producer.is_empty());
+	drop(producer);
+	assert!(matches!(consumer.pop(), Err(PopError::Closed)));
+	drop(consumer);
+	drop(RingBuffer::<String>::new().split_mpsc());
+}
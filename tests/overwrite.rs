@@ -0,0 +1,76 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::rc::Rc;
+use std::thread;
+use vringbuf::RingBuffer;
+use vringbuf::concurrent::PopError;
+
+#[test]
+fn test_overwrite() {
+	let value = Rc::new(());
+	let mut rbuf = RingBuffer::<Rc<()>>::with_capacity(16);
+	rbuf.push(value.clone());
+	let (mut producer, mut consumer) = rbuf.split_overwrite();
+	let cap = producer.capacity();
+
+	for _ in 0..cap + 5 {
+		producer.push(value.clone()).unwrap();
+	}
+	assert_eq!(producer.len(), cap);
+	assert_eq!(Rc::strong_count(&value), cap + 1);
+	assert_eq!(consumer.take_skipped(), 6);
+	assert_eq!(consumer.take_skipped(), 0);
+
+	assert!(consumer.pop().is_ok());
+	assert_eq!(consumer.len(), cap - 1);
+	drop(producer);
+	while consumer.pop().is_ok() {}
+	assert_eq!(consumer.pop(), Err(PopError::Closed));
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_drop_remaining() {
+	let value = Rc::new(());
+	let rbuf = RingBuffer::<Rc<()>>::with_capacity(16);
+	let (mut producer, consumer) = rbuf.split_overwrite();
+	producer.push(value.clone()).unwrap();
+	drop(consumer);
+	assert!(producer.is_closed());
+	assert!(producer.push(value.clone()).is_err());
+	drop(producer);
+	assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_threads() {
+	const N: u64 = 200_000;
+	let rbuf = RingBuffer::<u64>::with_capacity(64);
+	let (mut producer, mut consumer) = rbuf.split_overwrite();
+
+	let thread = thread::spawn(move || {
+		for i in 0..N {
+			producer.push(i).unwrap();
+		}
+	});
+
+	// Every element is either popped in order or counted as skipped
+	let mut popped = 0;
+	let mut skipped = 0;
+	let mut last = None;
+	loop {
+		match consumer.pop() {
+			Ok(value) => {
+				assert!(last < Some(value));
+				last = Some(value);
+				popped += 1;
+			},
+			Err(PopError::Empty) => thread::yield_now(),
+			Err(PopError::Closed) => break,
+		}
+		skipped += consumer.take_skipped();
+	}
+	thread.join().unwrap();
+	assert_eq!(last, Some(N - 1));
+	assert_eq!(popped + skipped + consumer.take_skipped(), N as usize);
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "rayon")]
+
+use rayon::prelude::*;
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_par_iter() {
+	let mut rbuf = RingBuffer::<u64>::new();
+	rbuf.extend(0..1000);
+	rbuf.remove_tail(500);
+	rbuf.extend(1000..1500);
+
+	assert_eq!(rbuf.par_iter().sum::<u64>(), (500..1500).sum());
+	rbuf.par_iter_mut().for_each(|value| *value *= 2);
+	assert_eq!(rbuf.par_chunks(100).map(|chunk| chunk[0]).collect::<Vec<_>>(), (1000..3000).step_by(200).collect::<Vec<_>>());
+	assert_eq!(rbuf.into_par_iter().map(|value| value / 2).collect::<Vec<_>>(), (500..1500).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_par_collect() {
+	let mut rbuf: RingBuffer<String> = (0..100).into_par_iter().map(|i| i.to_string()).collect();
+	rbuf.par_extend((100..200).into_par_iter().map(|i| i.to_string()));
+	assert_eq!(rbuf.len(), 200);
+	assert!(rbuf.iter().enumerate().all(|(i, s)| *s == i.to_string()));
+}
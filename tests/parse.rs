@@ -0,0 +1,41 @@
+#![cfg(feature = "winnow")]
+
+use vringbuf::RingBuffer;
+use winnow::prelude::*;
+use winnow::error::{ContextError, ErrMode};
+use winnow::stream::Offset;
+use winnow::token::{literal, take_until};
+
+fn header<'a>(input: &mut winnow::Partial<&'a [u8]>) -> ModalResult<&'a [u8]> {
+	let name = take_until(1.., &b": "[..]).parse_next(input)?;
+	literal(&b": "[..]).parse_next(input)?;
+	take_until(0.., &b"\r\n"[..]).parse_next(input)?;
+	literal(&b"\r\n"[..]).parse_next(input)?;
+	Ok(name)
+}
+
+#[test]
+fn test_incremental() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"Host: example.com\r");
+	assert!(matches!(rbuf.parse_partial(header), Err(ErrMode::Incomplete(_))));
+	assert_eq!(rbuf.len(), 18);
+
+	rbuf.extend_from_slice(b"\nAccept: */*\r\n: x\r\n");
+	assert_eq!(rbuf.parse_partial(header), Ok(&b"Host"[..]));
+	assert_eq!(rbuf.parse_partial(header), Ok(&b"Accept"[..]));
+	assert!(matches!(rbuf.parse_partial(header), Err(ErrMode::Backtrack(ContextError { .. }))));
+	assert_eq!(rbuf.as_slice(), b": x\r\n");
+}
+
+#[test]
+fn test_consume() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"abc def");
+	let mut input = rbuf.partial_input();
+	let word = take_until::<_, _, ErrMode<ContextError>>(0.., b' ').parse_next(&mut input).unwrap();
+	assert_eq!(word, b"abc");
+	let parsed = input.offset_from(&rbuf.partial_input());
+	rbuf.consume(parsed);
+	assert_eq!(rbuf.as_slice(), b" def");
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "bytemuck")]
+
+use bytemuck::{Pod, Zeroable};
+use vringbuf::RingBuffer;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Header {
+	kind: u16,
+	flags: u16,
+	len: u32,
+}
+unsafe impl Zeroable for Header {}
+unsafe impl Pod for Header {}
+
+#[test]
+fn test_push_pop() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	let header = Header { kind: 1, flags: 2, len: 3 };
+
+	// Unaligned contents
+	rbuf.push(0);
+	rbuf.push_pod(&header);
+	rbuf.push_pod(&0x1234u16);
+	assert_eq!(rbuf.len(), 1 + 8 + 2);
+	assert_eq!(rbuf.pop_pod::<u8>(), Some(0));
+	assert_eq!(rbuf.pop_pod::<Header>(), Some(header));
+	assert_eq!(rbuf.pop_pod::<u32>(), None);
+	assert_eq!(rbuf.pop_pod::<u16>(), Some(0x1234));
+	assert!(rbuf.is_empty());
+}
+
+#[test]
+fn test_as_pod_slice() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.push_pod(&[1u32, 2, 3]);
+	rbuf.push(4);
+	assert_eq!(rbuf.as_pod_slice::<u32>(), Some(&[1u32, 2, 3][..]));
+
+	// Misaligned after consuming a single byte
+	rbuf.remove_tail(1);
+	assert_eq!(rbuf.as_pod_slice::<u32>(), None);
+	assert_eq!(rbuf.as_pod_slice::<u8>().map(<[u8]>::len), Some(12));
+}
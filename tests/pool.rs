@@ -0,0 +1,38 @@
+#![cfg(feature = "std")]
+
+use vringbuf::{PoolStats, RingBufferPool};
+
+#[test]
+fn test_recycle() {
+	let pool = RingBufferPool::<u32>::new(2);
+	let mut a = pool.get(100);
+	a.extend_from_slice(&[1, 2, 3]);
+	let capacity = a.capacity();
+	let ptr = a.as_ptr();
+	pool.recycle(a);
+	assert_eq!(pool.idle(), 1);
+
+	// Mappings of a different capacity are not reused
+	let b = pool.get(capacity * 3);
+	assert_ne!(b.as_ptr(), ptr);
+	assert_eq!(pool.idle(), 1);
+
+	// Capacities rounding to the same size are
+	let mut c = pool.get(1);
+	assert_eq!(c.as_ptr(), ptr);
+	assert_eq!(c.capacity(), capacity);
+	assert!(c.is_empty());
+	c.extend_from_slice(&[4, 5]);
+	assert_eq!(c.as_slice(), [4, 5]);
+	assert_eq!(pool.idle(), 0);
+
+	pool.recycle(b);
+	pool.recycle(c);
+	pool.recycle(pool.get(capacity * 5));
+	assert_eq!(pool.idle(), 2);
+	assert_eq!(pool.stats(), PoolStats { hits: 1, misses: 3, recycled: 3, discarded: 1 });
+	assert_eq!(pool.stats().hit_rate(), 0.25);
+
+	pool.clear();
+	assert_eq!(pool.idle(), 0);
+}
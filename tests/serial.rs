@@ -0,0 +1,44 @@
+#![cfg(all(feature = "cobs", feature = "slip"))]
+
+use vringbuf::{FrameError, RingBuffer};
+
+#[test]
+fn test_cobs() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	let mut frame = [0u8; 16];
+
+	rbuf.extend_from_slice(&[0x00, 0x03, 0x11, 0x22, 0x02, 0x33, 0x00, 0x01, 0x01, 0x00, 0x02]);
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame), Ok(Some(4)));
+	assert_eq!(&frame[..4], &[0x11, 0x22, 0x00, 0x33]);
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame), Ok(Some(1)));
+	assert_eq!(&frame[..1], &[0x00]);
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame), Ok(None));
+	assert_eq!(rbuf.as_slice(), &[0x02]);
+
+	// Incomplete block followed by a valid frame
+	rbuf.extend_from_slice(&[0x00, 0x02, 0x44, 0x00]);
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame), Err(FrameError::Malformed));
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame), Ok(Some(1)));
+	assert_eq!(&frame[..1], &[0x44]);
+
+	rbuf.extend_from_slice(&[0x04, 0x01, 0x02, 0x03, 0x00]);
+	assert_eq!(rbuf.pop_cobs_frame(&mut frame[..2]), Err(FrameError::Overflow));
+	assert!(rbuf.is_empty());
+}
+
+#[test]
+fn test_slip() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	let mut frame = [0u8; 16];
+
+	rbuf.extend_from_slice(&[0xc0, 0x01, 0xdb, 0xdc, 0xdb, 0xdd, 0x02, 0xc0, 0x03]);
+	assert_eq!(rbuf.pop_slip_frame(&mut frame), Ok(Some(4)));
+	assert_eq!(&frame[..4], &[0x01, 0xc0, 0xdb, 0x02]);
+	assert_eq!(rbuf.pop_slip_frame(&mut frame), Ok(None));
+	assert_eq!(rbuf.as_slice(), &[0x03]);
+
+	rbuf.extend_from_slice(&[0xdb, 0x00, 0xc0, 0x04, 0x05, 0x06, 0xc0]);
+	assert_eq!(rbuf.pop_slip_frame(&mut frame), Err(FrameError::Malformed));
+	assert_eq!(rbuf.pop_slip_frame(&mut frame[..2]), Err(FrameError::Overflow));
+	assert!(rbuf.is_empty());
+}
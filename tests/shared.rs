@@ -0,0 +1,217 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::{io, process, thread};
+use std::time::Duration;
+use vringbuf::{HeaderError, RingBuffer, SharedHeader};
+
+// Shared memory names are global, keep them unique per test run
+fn name(test: &str) -> String {
+	format!("vringbuf-{}-{}", test, process::id())
+}
+
+#[test]
+fn test_create_open() {
+	let name = name("create-open");
+	let mut producer = unsafe { RingBuffer::<u32>::create_shared(&name, 1000).unwrap() };
+	let mut consumer = unsafe { RingBuffer::<u32>::open_shared(&name).unwrap() };
+	assert_eq!(producer.capacity(), consumer.capacity());
+	assert!(producer.capacity() >= 1000);
+
+	assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+	assert_eq!(consumer.len(), 3);
+	assert_eq!(consumer.read(), [1, 2, 3]);
+	consumer.release(1);
+	assert_eq!(consumer.pop(), Some(2));
+	assert_eq!(producer.len(), 1);
+
+	let err = unsafe { RingBuffer::<u32>::create_shared(&name, 1000).unwrap_err() };
+	assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+	let err = unsafe { RingBuffer::<u16>::open_shared(&name).unwrap_err() };
+	assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	let err = err.get_ref().and_then(|err| err.downcast_ref::<HeaderError>());
+	assert_eq!(err, Some(&HeaderError::ElementSize { expected: 2, found: 4 }));
+	drop(producer);
+	// Windows keeps the name around while the consumer holds on to the ring buffer
+	#[cfg(unix)]
+	{
+		let err = unsafe { RingBuffer::<u32>::open_shared(&name).unwrap_err() };
+		assert_eq!(err.kind(), io::ErrorKind::NotFound);
+	}
+	// Opened mappings outlive the name
+	assert_eq!(consumer.pop(), Some(3));
+	assert_eq!(consumer.pop(), None);
+}
+
+#[test]
+fn test_invalid_name() {
+	let err = unsafe { RingBuffer::<u8>::create_shared("a/b", 10).unwrap_err() };
+	assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+// The shared memory object is not inherited by child processes
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cloexec() {
+	use std::os::unix::io::AsRawFd;
+	let name = name("cloexec");
+	let producer = unsafe { RingBuffer::<u8>::create_shared(&name, 10).unwrap() };
+	let consumer = unsafe { RingBuffer::<u8>::open_shared(&name).unwrap() };
+	for fd in [producer.as_raw_fd(), consumer.as_raw_fd()] {
+		let fdinfo = std::fs::read_to_string(format!("/proc/self/fdinfo/{}", fd)).unwrap();
+		let flags = fdinfo.lines().find_map(|line| line.strip_prefix("flags:")).unwrap();
+		assert_ne!(u32::from_str_radix(flags.trim(), 8).unwrap() & 0o2000000, 0);
+	}
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mode() {
+	use std::os::unix::fs::PermissionsExt;
+	let name = name("mode");
+	let _ring = unsafe { RingBuffer::<u8>::create_shared_with_mode(&name, 10, 0o640).unwrap() };
+	let metadata = std::fs::metadata(format!("/dev/shm/{}", name)).unwrap();
+	assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+}
+
+#[test]
+fn test_wrap_around() {
+	const N: u64 = 100_000;
+	let name = name("wrap-around");
+	let mut producer = unsafe { RingBuffer::<u64>::create_shared(&name, 100).unwrap() };
+	let mut consumer = unsafe { RingBuffer::<u64>::open_shared(&name).unwrap() };
+
+	let thread = thread::spawn(move || {
+		let mut i = 0;
+		while i < N {
+			let values: Vec<u64> = (i..N).take(37).collect();
+			i += producer.push_slice(&values) as u64;
+		}
+	});
+	let mut next = 0;
+	let mut values = [0; 64];
+	while next < N {
+		let n = consumer.pop_slice(&mut values);
+		for &value in &values[..n] {
+			assert_eq!(value, next);
+			next += 1;
+		}
+	}
+	thread.join().unwrap();
+}
+
+#[test]
+fn test_recover() {
+	let name = name("recover");
+	let mut producer = unsafe { RingBuffer::<u32>::create_shared(&name, 1000).unwrap() };
+	let mut consumer = unsafe { RingBuffer::<u32>::open_shared(&name).unwrap() };
+	assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+	assert!(!consumer.recover());
+	assert_eq!(consumer.read(), [1, 2, 3]);
+
+	// Corrupt the positions following the layout, the elements are discarded
+	#[cfg(target_os = "linux")]
+	{
+		use std::os::unix::fs::FileExt;
+		let file = std::fs::OpenOptions::new().write(true).open(format!("/dev/shm/{}", name)).unwrap();
+		file.write_all_at(&[0xff; 1000], 24).unwrap();
+		// Corrupted positions stay within the mapping
+		assert!(consumer.read().len() <= consumer.capacity());
+		assert!(producer.push_slice(&[5, 6]) <= 2);
+		assert!(consumer.recover());
+		assert!(consumer.is_empty());
+		assert_eq!(producer.push_slice(&[4]), 1);
+		assert_eq!(consumer.pop(), Some(4));
+	}
+}
+
+// The layout is read by processes not written in Rust, changing it requires bumping the version
+#[test]
+fn test_header_layout() {
+	const W: usize = std::mem::size_of::<usize>();
+	assert_eq!(std::mem::offset_of!(SharedHeader, magic), 0);
+	assert_eq!(std::mem::offset_of!(SharedHeader, version), 4);
+	assert_eq!(std::mem::offset_of!(SharedHeader, size_of), 8);
+	assert_eq!(std::mem::offset_of!(SharedHeader, cap), 8 + W);
+	assert_eq!(std::mem::offset_of!(SharedHeader, head), 128);
+	assert_eq!(std::mem::offset_of!(SharedHeader, tail), 256);
+	assert_eq!(std::mem::size_of::<SharedHeader>(), 640);
+	assert_eq!(SharedHeader::MAGIC, 0x6873_7276);
+	assert_eq!(SharedHeader::VERSION, 4);
+}
+
+// Reads the ring buffer like a foreign process following the documented layout
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+#[test]
+fn test_foreign_reader() {
+	let name = name("foreign-reader");
+	let mut producer = unsafe { RingBuffer::<u32>::create_shared(&name, 1000).unwrap() };
+	assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+	let bytes = std::fs::read(format!("/dev/shm/{}", name)).unwrap();
+	assert_eq!(bytes.len(), producer.header_len() + producer.capacity() * 4);
+
+	let field = |offset: usize| {
+		let mut value = [0u8; 8];
+		value.copy_from_slice(&bytes[offset..offset + 8]);
+		u64::from_ne_bytes(value) as usize
+	};
+	assert_eq!(&bytes[0..4], b"vrsh");
+	assert_eq!(u32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]), SharedHeader::VERSION);
+	assert_eq!(field(8), 4);
+	let cap = field(16);
+	assert_eq!(cap, producer.capacity() * 4);
+	let (head, tail) = (field(128), field(256));
+	assert_eq!((head, tail), (12, 0));
+	let data = &bytes[producer.header_len()..];
+	let elements: Vec<u32> = (tail..head).step_by(4)
+		.map(|pos| u32::from_ne_bytes([data[pos % cap], data[pos % cap + 1], data[pos % cap + 2], data[pos % cap + 3]]))
+		.collect();
+	assert_eq!(elements, [1, 2, 3]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_send_recv() {
+	use std::os::unix::net::UnixStream;
+
+	let name = name("send-recv");
+	let (left, right) = UnixStream::pair().unwrap();
+	let producer = unsafe { RingBuffer::<u32>::create_shared(&name, 1000).unwrap() };
+	producer.send(&left).unwrap();
+	// The name is no longer needed once the ring buffer has been sent
+	drop(producer);
+
+	let mut consumer = unsafe { RingBuffer::<u32>::recv_shared(&right).unwrap() };
+	assert!(consumer.capacity() >= 1000);
+	consumer.send(&left).unwrap();
+	let mut producer = unsafe { RingBuffer::<u32>::recv_shared(&right).unwrap() };
+	assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+	assert_eq!(consumer.read(), [1, 2, 3]);
+
+	// The element type must match
+	consumer.send(&left).unwrap();
+	let err = unsafe { RingBuffer::<u64>::recv_shared(&right).unwrap_err() };
+	assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_blocking() {
+	let name = name("blocking");
+	let mut producer = unsafe { RingBuffer::<u64>::create_shared(&name, 16).unwrap() };
+	let mut consumer = unsafe { RingBuffer::<u64>::open_shared(&name).unwrap() };
+	assert_eq!(consumer.pop_timeout(Duration::from_millis(10)), None);
+
+	const N: u64 = 100000;
+	let thread = thread::spawn(move || {
+		for i in 0..N {
+			producer.push_blocking(i);
+		}
+		producer
+	});
+	for i in 0..N {
+		assert_eq!(consumer.pop_blocking(), i);
+	}
+	let mut producer = thread.join().unwrap();
+
+	while producer.push(0).is_ok() {}
+	assert_eq!(producer.push_timeout(1, Duration::from_millis(10)), Err(1));
+}
@@ -0,0 +1,47 @@
+#![cfg(all(feature = "std", unix, not(heap_fallback)))]
+
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_splice_pipe() {
+	let (mut reader, mut writer) = io::pipe().unwrap();
+	let mut rbuf = RingBuffer::<u8>::with_capacity(100);
+	let capacity = rbuf.capacity();
+
+	// Wrap around the end of the backing memory
+	rbuf.extend_from_slice(&vec![0; capacity - 5]);
+	rbuf.remove_tail(capacity - 5);
+	rbuf.extend_from_slice(b"hello world");
+
+	let mut n = 0;
+	while n < 11 {
+		n += rbuf.splice_to(&writer, 11 - n).unwrap();
+	}
+	assert!(rbuf.is_empty());
+	let mut buf = [0; 11];
+	reader.read_exact(&mut buf).unwrap();
+	assert_eq!(&buf, b"hello world");
+
+	writer.write_all(b"goodbye").unwrap();
+	drop(writer);
+	while rbuf.splice_from(&reader, 100).unwrap() != 0 {}
+	assert_eq!(rbuf.as_slice(), b"goodbye");
+}
+
+#[test]
+fn test_splice_socket() {
+	let (mut left, right) = UnixStream::pair().unwrap();
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"hello");
+	assert_eq!(rbuf.splice_to(&right, 100).unwrap(), 5);
+	let mut buf = [0; 5];
+	left.read_exact(&mut buf).unwrap();
+	assert_eq!(&buf, b"hello");
+
+	left.write_all(b"world").unwrap();
+	drop(left);
+	while rbuf.splice_from(&right, 3).unwrap() != 0 {}
+	assert_eq!(rbuf.as_slice(), b"world");
+}
@@ -0,0 +1,408 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use std::thread;
+use std::time::{Duration, Instant};
+use vringbuf::RingBuffer;
+use vringbuf::concurrent::{PopError, PushError};
+
+#[test]
+fn test_split() {
+	let mut rbuf = RingBuffer::<String>::with_capacity(4);
+	rbuf.push(String::from("a"));
+	let cap = rbuf.capacity();
+
+	let (mut producer, mut consumer) = rbuf.split();
+	assert_eq!(producer.capacity(), cap);
+	assert_eq!(consumer.len(), 1);
+	for i in 1..cap {
+		assert_eq!(producer.push(i.to_string()), Ok(()));
+	}
+	assert!(producer.is_full());
+	assert_eq!(producer.push(String::from("z")), Err(PushError::Full(String::from("z"))));
+
+	assert_eq!(consumer.pop().as_deref(), Ok("a"));
+	assert_eq!(consumer.as_slice().len(), cap - 1);
+	assert_eq!(producer.free_len(), 1);
+	consumer.remove_tail(cap - 2);
+	assert_eq!(consumer.as_slice(), [(cap - 1).to_string()]);
+	assert_eq!(producer.push(String::from("b")), Ok(()));
+}
+
+#[test]
+fn test_threads() {
+	const N: u64 = 1_000_000;
+	let rbuf = RingBuffer::<u64>::with_capacity(1000);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	let thread = thread::spawn(move || {
+		for i in 0..N {
+			let mut value = i;
+			while let Err(PushError::Full(v)) = producer.push(value) {
+				value = v;
+				thread::yield_now();
+			}
+		}
+	});
+
+	let mut expected = 0;
+	while expected < N {
+		match consumer.pop() {
+			Ok(value) => {
+				assert_eq!(value, expected);
+				expected += 1;
+			},
+			Err(_) => thread::yield_now(),
+		}
+	}
+	thread.join().unwrap();
+	assert!(consumer.is_empty());
+}
+
+#[test]
+fn test_drop_remaining() {
+	use std::rc::Rc;
+	let counter = Rc::new(());
+	let mut rbuf = RingBuffer::with_capacity(16);
+	for _ in 0..10 {
+		rbuf.push(counter.clone());
+	}
+	rbuf.remove_tail(3);
+	let (producer, mut consumer) = rbuf.split();
+	drop(consumer.pop());
+	drop(producer);
+	drop(consumer);
+	assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn test_blocking() {
+	const N: u64 = 100_000;
+	let rbuf = RingBuffer::<u64>::with_capacity(100);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	let thread = thread::spawn(move || {
+		for i in 0..N {
+			producer.push_blocking(i).unwrap();
+		}
+	});
+	for i in 0..N {
+		assert_eq!(consumer.pop_blocking(), Some(i));
+	}
+	thread.join().unwrap();
+	assert_eq!(consumer.pop_blocking(), None);
+}
+
+#[test]
+fn test_timeout() {
+	let rbuf = RingBuffer::<u8>::with_capacity(1);
+	let (mut producer, mut consumer) = rbuf.split();
+	assert_eq!(consumer.pop_timeout(Duration::from_millis(10)), Err(PopError::Empty));
+
+	for _ in 0..producer.capacity() {
+		producer.push(0).unwrap();
+	}
+	let start = Instant::now();
+	assert_eq!(producer.push_timeout(1, Duration::from_millis(10)), Err(PushError::Full(1)));
+	assert!(start.elapsed() >= Duration::from_millis(10));
+
+	// Return the consumer from the thread to keep it from being dropped
+	let thread = thread::spawn(move || {
+		thread::sleep(Duration::from_millis(10));
+		(consumer.pop(), consumer)
+	});
+	assert_eq!(producer.push_timeout(2, Duration::from_secs(10)), Ok(()));
+	assert_eq!(thread.join().unwrap().0, Ok(0));
+}
+
+#[test]
+fn test_slices() {
+	const N: usize = 1_000_000;
+	let rbuf = RingBuffer::<u32>::with_capacity(4096);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	let thread = thread::spawn(move || {
+		let data: Vec<u32> = (0..N as u32).collect();
+		let mut data = &data[..];
+		while !data.is_empty() {
+			let n = producer.push_slice(&data[..data.len().min(1000)]);
+			data = &data[n..];
+		}
+	});
+
+	let mut buf = [0u32; 777];
+	let mut expected = 0;
+	while expected < N as u32 {
+		let n = consumer.pop_slice(&mut buf);
+		for &value in &buf[..n] {
+			assert_eq!(value, expected);
+			expected += 1;
+		}
+	}
+	thread.join().unwrap();
+}
+
+#[test]
+fn test_slices_uninit() {
+	use std::mem::MaybeUninit;
+	let rbuf = RingBuffer::<String>::with_capacity(2);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	let values = [MaybeUninit::new(String::from("a")), MaybeUninit::new(String::from("b"))];
+	assert_eq!(unsafe { producer.push_slice_uninit(&values) }, 2);
+
+	let mut dst = [MaybeUninit::<String>::uninit(), MaybeUninit::uninit(), MaybeUninit::uninit()];
+	assert_eq!(consumer.pop_slice_uninit(&mut dst), 2);
+	let values = unsafe { [dst[0].assume_init_read(), dst[1].assume_init_read()] };
+	assert_eq!(values, ["a", "b"]);
+	assert_eq!(consumer.pop_slice_uninit(&mut dst), 0);
+}
+
+#[test]
+fn test_disconnect() {
+	let rbuf = RingBuffer::<i32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	assert!(!producer.is_closed() && !consumer.is_closed());
+
+	producer.push(1).unwrap();
+	producer.push(2).unwrap();
+	drop(producer);
+	assert!(consumer.is_closed());
+	assert_eq!(consumer.pop(), Ok(1));
+	assert_eq!(consumer.pop_blocking(), Some(2));
+	assert_eq!(consumer.pop(), Err(PopError::Closed));
+	assert_eq!(consumer.pop_blocking(), None);
+
+	let rbuf = RingBuffer::<i32>::with_capacity(1);
+	let (mut producer, consumer) = rbuf.split();
+	while producer.push(0).is_ok() {}
+	// Wake up the blocked producer when the consumer goes away
+	let thread = thread::spawn(move || producer.push_blocking(3));
+	thread::sleep(Duration::from_millis(10));
+	drop(consumer);
+	assert_eq!(thread.join().unwrap(), Err(3));
+}
+
+#[test]
+fn test_wait_strategies() {
+	use vringbuf::concurrent::WaitStrategy;
+	const N: u64 = 100_000;
+	let strategies = [WaitStrategy::Spin, WaitStrategy::Yield, WaitStrategy::Backoff, WaitStrategy::Park];
+	for &producer_wait in &strategies {
+		for &consumer_wait in &strategies {
+			let rbuf = RingBuffer::<u64>::with_capacity(10);
+			let (mut producer, mut consumer) = rbuf.split();
+			producer.set_wait_strategy(producer_wait);
+			consumer.set_wait_strategy(consumer_wait);
+			assert_eq!(consumer.wait_strategy(), consumer_wait);
+
+			let thread = thread::spawn(move || {
+				for i in 0..N {
+					producer.push_blocking(i).unwrap();
+				}
+			});
+			for i in 0..N {
+				assert_eq!(consumer.pop_blocking(), Some(i));
+			}
+			assert_eq!(consumer.pop_timeout(Duration::from_millis(1)), Err(PopError::Closed));
+			thread.join().unwrap();
+		}
+	}
+}
+
+#[test]
+fn test_try_iter() {
+	let rbuf = RingBuffer::<u32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	assert_eq!(consumer.try_iter().next(), None);
+
+	for i in 0..5 {
+		producer.push(i).unwrap();
+	}
+	assert_eq!(consumer.try_iter().take(2).collect::<Vec<_>>(), [0, 1]);
+	assert_eq!(consumer.try_iter().collect::<Vec<_>>(), [2, 3, 4]);
+	assert!(consumer.is_empty());
+
+	let mut vec = vec![10];
+	assert_eq!(consumer.drain_available(&mut vec), 0);
+	producer.push_slice(&[5, 6, 7]);
+	assert_eq!(consumer.drain_available(&mut vec), 3);
+	assert_eq!(vec, [10, 5, 6, 7]);
+	assert!(consumer.is_empty());
+}
+
+#[test]
+fn test_grants() {
+	use std::mem::MaybeUninit;
+	let rbuf = RingBuffer::<u8>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	let capacity = producer.capacity();
+	assert!(producer.grant(capacity + 1).is_none());
+
+	// Wrap the offsets around the end of the buffer
+	for _ in 0..capacity / 3 {
+		let grant = producer.grant(3).unwrap();
+		for (i, elem) in grant.iter_mut().enumerate() {
+			*elem = MaybeUninit::new(i as u8);
+		}
+		unsafe { producer.commit(2); }
+		assert_eq!(consumer.read(), [0, 1]);
+		consumer.release(2);
+		assert!(consumer.read().is_empty());
+	}
+
+	let grant = producer.grant(capacity).unwrap();
+	grant[0] = MaybeUninit::new(42);
+	unsafe { producer.commit(1); }
+	assert!(producer.grant(capacity).is_none());
+	assert_eq!(consumer.read(), [42]);
+
+	drop(consumer);
+	assert!(producer.grant(1).is_none());
+}
+
+#[test]
+#[should_panic]
+fn test_release_too_many() {
+	let rbuf = RingBuffer::<u8>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	producer.push(1).unwrap();
+	consumer.release(2);
+}
+
+#[test]
+fn test_watermarks() {
+	let rbuf = RingBuffer::<u32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	let cap = producer.capacity();
+	consumer.set_high_watermark(4);
+	assert_eq!(consumer.high_watermark(), 4);
+	producer.set_low_watermark(usize::MAX);
+	assert_eq!(producer.low_watermark(), cap - 1);
+	producer.set_low_watermark(cap / 2);
+
+	// The consumer is only woken up once 4 elements are buffered
+	let thread = thread::spawn(move || {
+		for i in 0..4 {
+			thread::sleep(Duration::from_millis(10));
+			producer.push(i).unwrap();
+		}
+		producer
+	});
+	assert_eq!(consumer.pop_blocking(), Some(0));
+	assert_eq!(consumer.len(), 3);
+	let mut producer = thread.join().unwrap();
+
+	// The producer is only woken up once the consumer drained half the ring buffer
+	while producer.push(0).is_ok() {}
+	let thread = thread::spawn(move || {
+		while consumer.pop_blocking().is_some() {
+			thread::sleep(Duration::from_millis(1));
+		}
+	});
+	producer.push_blocking(1).unwrap();
+	assert!(producer.len() <= cap / 2 + 1);
+	drop(producer);
+	thread.join().unwrap();
+}
+
+#[test]
+fn test_select() {
+	use vringbuf::concurrent::Select;
+	const N: u32 = 10_000;
+	let mut select = Select::new();
+	let threads: Vec<_> = (0..4).map(|i| {
+		let (mut producer, consumer) = RingBuffer::<u32>::with_capacity(16).split();
+		assert_eq!(select.push(consumer), i);
+		thread::spawn(move || {
+			for value in 0..N {
+				producer.push_blocking(value).unwrap();
+			}
+		})
+	}).collect();
+
+	let mut next = [0; 4];
+	while let Some((index, value)) = select.pop_blocking() {
+		assert_eq!(value, next[index]);
+		next[index] += 1;
+	}
+	assert_eq!(next, [N; 4]);
+	assert_eq!(select.pop(), Err(PopError::Closed));
+	for thread in threads {
+		thread.join().unwrap();
+	}
+}
+
+#[test]
+fn test_select_closed() {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::task::{Context, Poll, Wake, Waker};
+	use vringbuf::concurrent::Select;
+
+	struct Counter(AtomicUsize);
+	impl Wake for Counter {
+		fn wake(self: Arc<Self>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+	let counter = Arc::new(Counter(AtomicUsize::new(0)));
+	let waker = Waker::from(counter.clone());
+	let mut cx = Context::from_waker(&waker);
+
+	// A closed and drained consumer does not keep the others from parking
+	let mut select = Select::new();
+	let (closed, consumer) = RingBuffer::<u32>::with_capacity(16).split();
+	select.push(consumer);
+	drop(closed);
+	let (mut open, consumer) = RingBuffer::<u32>::with_capacity(16).split();
+	select.push(consumer);
+	assert_eq!(select.poll_pop(&mut cx), Poll::Pending);
+
+	open.push(1).unwrap();
+	assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+	assert_eq!(select.poll_pop(&mut cx), Poll::Ready(Some((1, 1))));
+	assert_eq!(select.poll_pop(&mut cx), Poll::Pending);
+	drop(open);
+	assert_eq!(select.poll_pop(&mut cx), Poll::Ready(None));
+}
+
+#[test]
+fn test_grow() {
+	const N: u32 = 100_000;
+	let rbuf = RingBuffer::<u32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	let initial = producer.capacity();
+
+	let thread = thread::spawn(move || {
+		for i in 0..N {
+			if i % 10_000 == 0 {
+				let capacity = producer.capacity();
+				producer.grow(capacity + 1);
+				assert!(producer.capacity() > capacity);
+			}
+			producer.push_blocking(i).unwrap();
+		}
+		producer.capacity()
+	});
+	for i in 0..N {
+		assert_eq!(consumer.pop_blocking(), Some(i));
+	}
+	assert_eq!(consumer.pop_blocking(), None);
+	let capacity = thread.join().unwrap();
+	assert!(capacity > initial);
+	assert_eq!(consumer.capacity(), capacity);
+}
+
+#[test]
+fn test_grow_closed() {
+	let rbuf = RingBuffer::<String>::with_capacity(4);
+	let (mut producer, consumer) = rbuf.split();
+	producer.push(String::from("old")).unwrap();
+	producer.grow(1000);
+	producer.push(String::from("new")).unwrap();
+	drop(consumer);
+	assert!(producer.is_closed());
+	let capacity = producer.capacity();
+	producer.grow(capacity * 2);
+	assert_eq!(producer.capacity(), capacity);
+}
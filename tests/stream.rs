@@ -0,0 +1,128 @@
+#![cfg(all(feature = "std", not(heap_fallback)))]
+
+use futures::executor::block_on;
+use std::thread;
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_send_recv() {
+	const N: u32 = 100_000;
+	let rbuf = RingBuffer::<u32>::with_capacity(64);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	let thread = thread::spawn(move || block_on(async {
+		for i in 0..N {
+			producer.send(i).await.unwrap();
+		}
+	}));
+	block_on(async {
+		for i in 0..N {
+			assert_eq!(consumer.recv().await, Some(i));
+		}
+		assert_eq!(consumer.recv().await, None);
+	});
+	thread.join().unwrap();
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn test_stream_sink() {
+	use futures::{SinkExt, StreamExt};
+
+	const N: u32 = 10_000;
+	let rbuf = RingBuffer::<u32>::with_capacity(64);
+	let (mut producer, consumer) = rbuf.split();
+
+	let thread = thread::spawn(move || block_on(async {
+		let mut items = futures::stream::iter((0..N).map(Ok));
+		producer.send_all(&mut items).await.unwrap();
+	}));
+	let items: Vec<u32> = block_on(consumer.collect());
+	assert!(items.iter().copied().eq(0..N));
+	thread.join().unwrap();
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn test_sink_close() {
+	use futures::{SinkExt, StreamExt};
+
+	let rbuf = RingBuffer::<u32>::with_capacity(64);
+	let (mut producer, consumer) = rbuf.split();
+	block_on(async {
+		producer.send(1).await.unwrap();
+		producer.send(2).await.unwrap();
+		producer.close().await.unwrap();
+	});
+
+	// The stream ends while the producer is still alive
+	let items: Vec<u32> = block_on(consumer.collect());
+	assert_eq!(items, [1, 2]);
+	assert!(producer.is_closed());
+	assert!(block_on(producer.send(3)).is_err());
+}
+
+#[test]
+fn test_spin() {
+	use vringbuf::concurrent::WaitStrategy;
+	const N: u32 = 10_000;
+	let rbuf = RingBuffer::<u32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+	producer.set_wait_strategy(WaitStrategy::Yield);
+	consumer.set_wait_strategy(WaitStrategy::Spin);
+
+	let thread = thread::spawn(move || block_on(async {
+		for i in 0..N {
+			producer.send(i).await.unwrap();
+		}
+	}));
+	block_on(async {
+		for i in 0..N {
+			assert_eq!(consumer.recv().await, Some(i));
+		}
+	});
+	thread.join().unwrap();
+}
+
+#[test]
+fn test_poll_ready() {
+	use std::task::{Context, Poll};
+	use futures::task::noop_waker;
+
+	let waker = noop_waker();
+	let mut cx = Context::from_waker(&waker);
+	let rbuf = RingBuffer::<u32>::with_capacity(16);
+	let (mut producer, mut consumer) = rbuf.split();
+
+	assert_eq!(consumer.poll_read_ready(&mut cx), Poll::Pending);
+	while producer.push(1).is_ok() {}
+	assert_eq!(producer.poll_write_ready(&mut cx), Poll::Pending);
+	assert_eq!(consumer.poll_read_ready(&mut cx), Poll::Ready(()));
+	consumer.pop().unwrap();
+	assert_eq!(producer.poll_write_ready(&mut cx), Poll::Ready(()));
+	drop(consumer);
+	assert_eq!(producer.poll_write_ready(&mut cx), Poll::Ready(()));
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn test_select_stream() {
+	use futures::StreamExt;
+	use vringbuf::concurrent::Select;
+
+	let (mut first, consumer) = RingBuffer::<u32>::with_capacity(16).split();
+	let mut select = Select::from(vec![consumer]);
+	let (mut second, consumer) = RingBuffer::<u32>::with_capacity(16).split();
+	select.push(consumer);
+
+	let thread = thread::spawn(move || {
+		for i in 0..100 {
+			first.push_blocking(i).unwrap();
+			second.push_blocking(i + 100).unwrap();
+		}
+	});
+	let mut values: Vec<u32> = block_on(select.map(|(_, value)| value).collect());
+	values.sort_unstable();
+	assert_eq!(values, (0..200).collect::<Vec<_>>());
+	thread.join().unwrap();
+}
@@ -0,0 +1,38 @@
+#![cfg(all(feature = "io-uring", target_os = "linux", not(heap_fallback)))]
+
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+use io_uring::{types, IoUring};
+use vringbuf::RingBuffer;
+
+#[test]
+fn test_fixed() {
+	// io_uring may be unavailable in sandboxed environments
+	let mut ring = match IoUring::new(8) {
+		Ok(ring) => ring,
+		Err(_) => return,
+	};
+	let (mut reader, mut writer) = io::pipe().unwrap();
+	let mut rbuf = RingBuffer::<u8>::with_capacity(100);
+	rbuf.extend_from_slice(b"hello");
+	unsafe { rbuf.register_fixed(&ring.submitter()).unwrap(); }
+
+	let entry = rbuf.write_fixed(types::Fd(writer.as_raw_fd()), 0).build();
+	unsafe { ring.submission().push(&entry).unwrap(); }
+	ring.submit_and_wait(1).unwrap();
+	let result = ring.completion().next().unwrap().result();
+	assert_eq!(result, 5);
+	rbuf.remove_tail(result as usize);
+	let mut buf = [0; 5];
+	reader.read_exact(&mut buf).unwrap();
+	assert_eq!(&buf, b"hello");
+
+	writer.write_all(b"world").unwrap();
+	let entry = rbuf.read_fixed(types::Fd(reader.as_raw_fd()), 0).build();
+	unsafe { ring.submission().push(&entry).unwrap(); }
+	ring.submit_and_wait(1).unwrap();
+	let result = ring.completion().next().unwrap().result();
+	assert_eq!(result, 5);
+	unsafe { rbuf.add_len(result as usize); }
+	assert_eq!(rbuf.as_slice(), b"world");
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "zeroize")]
+
+use vringbuf::RingBuffer;
+use zeroize::Zeroize;
+
+// Bytes of the mapping around the elements, `before` bytes before the first element
+fn bytes(rbuf: &RingBuffer<u8>, before: usize, len: usize) -> Vec<u8> {
+	unsafe { std::slice::from_raw_parts(rbuf.as_ptr().sub(before), len).to_vec() }
+}
+
+#[test]
+fn test_wipe() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"top secret");
+
+	// Popped and removed elements are wiped
+	assert_eq!(rbuf.pop(), Some(b't'));
+	assert_eq!(bytes(&rbuf, 1, 3), b"\0op");
+	rbuf.remove_tail(3);
+	assert_eq!(bytes(&rbuf, 4, 6), b"\0\0\0\0se");
+
+	// Truncated and cleared elements are wiped
+	rbuf.truncate(3);
+	assert_eq!(bytes(&rbuf, 0, 6), b"sec\0\0\0");
+	rbuf.clear();
+	assert_eq!(bytes(&rbuf, 0, 3), b"\0\0\0");
+}
+
+#[test]
+fn test_zeroize() {
+	let mut rbuf = RingBuffer::<u8>::with_capacity(16);
+	let capacity = rbuf.capacity();
+	// Wrap around a couple of times so the whole capacity was written
+	for _ in 0..3 {
+		rbuf.extend_from_slice(&vec![0xaa; capacity]);
+		rbuf.remove_tail(capacity - 1);
+		rbuf.truncate(0);
+	}
+	rbuf.extend_from_slice(b"key");
+
+	rbuf.zeroize();
+	assert!(rbuf.is_empty());
+	assert_eq!(rbuf.capacity(), capacity);
+	assert!(rbuf.reserved_mut().iter().all(|byte| unsafe { byte.assume_init() } == 0));
+}
+
+#[test]
+fn test_records() {
+	let mut rbuf = RingBuffer::<u8>::new();
+	rbuf.extend_from_slice(b"alpha\nbeta\n");
+
+	// The records borrow the bytes, so dropping the iterator does not wipe them
+	let records: Vec<&[u8]> = rbuf.records(b'\n').collect();
+	assert_eq!(records, [&b"alpha"[..], b"beta"]);
+	assert!(rbuf.is_empty());
+
+	rbuf.extend_from_slice(b"gamma\n");
+	let lines: Vec<&[u8]> = rbuf.lines().collect();
+	assert_eq!(lines, [b"gamma"]);
+}